@@ -1,9 +1,8 @@
 use crate::components::*;
-use crate::game_configs::HOLE_SIZE;
+use crate::game_configs::{HOLE_SIZE, PIPE_WIDTH, PLAYER_POOL};
 use crate::resources::GeneHandler;
 use hecs::World;
-use pixie::ResourceContainer;
-use rand::rngs::ThreadRng;
+use pixie::{EntityPools, PrefabError, PrefabRegistry, ResourceContainer};
 use rand::Rng;
 
 pub fn background(world: &mut World) {
@@ -58,84 +57,69 @@ pub fn background(world: &mut World) {
     }
 }
 
-pub fn pipe(world: &mut World, pos: f32) {
+/// Register the flappy components and load the bundled prefab file
+pub fn register_prefabs(prefabs: &mut PrefabRegistry) -> Result<(), PrefabError> {
+    prefabs.register_component::<PipePair>("PipePair");
+    prefabs.register_component::<Pipe>("Pipe");
+    prefabs.register_component::<PipeTarget>("PipeTarget");
+    prefabs.load_str(include_str!("../assets/prefabs.json"))
+}
+
+/// Spawn a pipe pair: a `PipePair` root that scrolls, with the caps and bodies as children
+pub fn pipe(
+    world: &mut World,
+    prefabs: &PrefabRegistry,
+    rng: &mut impl Rng,
+    pos: f32,
+) -> Result<(), PrefabError> {
     // Generate random pipe height
-    // Note: We need to pass ThreadRng from resources since we can't access resources here
-    // For now, create a temporary RNG - this should be refactored to accept resources
-    let mut rng = ThreadRng::default();
     let rand = rng.gen_range(3.0..7.0);
 
-    // Top pipe cap
-    world.spawn((
-        Sprite {
-            uv: [0.0, 0.25, 0., 0.25],
-            atlas: "tile".into(),
-        },
-        Transform {
-            position: [pos, rand - 6.0, 0.3],
-            size: [2.0, 2.0],
+    let pair = prefabs.spawn_prefab(
+        world,
+        "pipe_pair",
+        (Transform {
+            position: [pos, 0.0, 0.0],
+            size: [1.0, 1.0],
             rotation: 0.0,
-        },
-        Pipe {
-            reposition_size: 16.0,
-            pipe_index: 0,
-        },
-        PipeTarget {},
-    ));
+        },),
+    )?;
 
-    // Top pipe body
-    world.spawn((
-        Sprite {
-            uv: [0.0, 0.25, 0.25, 0.25],
-            atlas: "tile".into(),
-        },
-        Transform {
-            position: [pos, rand * 0.5 - 7.0, 0.3],
-            size: [2.0, rand],
-            rotation: 0.0,
-        },
-        Pipe {
-            reposition_size: 16.0,
-            pipe_index: 1,
-        },
-    ));
-
-    // Bottom pipe cap
-    world.spawn((
-        Sprite {
-            uv: [0.0, 0.25, 0.5, 0.75],
-            atlas: "tile".into(),
-        },
-        Transform {
-            position: [pos, rand + HOLE_SIZE - 4.0, 0.3],
-            size: [2.0, 2.0],
-            rotation: 0.0,
-        },
-        Pipe {
-            reposition_size: 16.0,
-            pipe_index: 2,
-        },
-    ));
+    let parts = [
+        "pipe_top_cap",
+        "pipe_top_body",
+        "pipe_bottom_cap",
+        "pipe_bottom_body",
+    ];
+    for (pipe_index, part) in parts.into_iter().enumerate() {
+        prefabs.spawn_prefab(
+            world,
+            part,
+            (pipe_part_transform(pipe_index as u8, rand), Parent(pair)),
+        )?;
+    }
+    Ok(())
+}
 
-    // Bottom pipe body
-    world.spawn((
-        Sprite {
-            uv: [0.0, 0.25, 0.5, 0.5],
-            atlas: "tile".into(),
-        },
-        Transform {
-            position: [pos, (rand + HOLE_SIZE - 4.0) * 0.5 + 5.5, 0.3],
-            size: [2.0, 13.0 - (rand + HOLE_SIZE)],
-            rotation: 0.0,
-        },
-        Pipe {
-            reposition_size: 16.0,
-            pipe_index: 3,
-        },
-    ));
+/// Local transform of one part of a pipe pair whose gap sits at `rand`
+pub fn pipe_part_transform(pipe_index: u8, rand: f32) -> Transform {
+    let (y, height) = match pipe_index {
+        0 => (rand - 6.0, 2.0),
+        1 => ((rand - 6.0) * 0.5 - 4.0, rand),
+        2 => (rand + HOLE_SIZE - 4.0, 2.0),
+        _ => (
+            (rand + HOLE_SIZE - 4.0) * 0.5 + 5.5,
+            13.0 - (rand + HOLE_SIZE),
+        ),
+    };
+    Transform {
+        position: [0.0, y, 0.3],
+        size: [PIPE_WIDTH, height],
+        rotation: 0.0,
+    }
 }
 
-/// Helper to spawn ai_player with proper DNA from resources
+/// Helper to spawn ai_player with proper DNA from resources, reusing pooled players
 pub fn ai_player_with_resources(world: &mut World, resources: &mut ResourceContainer) {
     let dna = if let Some(gene_handler) = resources.get_mut::<GeneHandler>() {
         gene_handler.get_dna()
@@ -147,27 +131,90 @@ pub fn ai_player_with_resources(world: &mut World, resources: &mut ResourceConta
         }
     };
 
-    world.spawn((
-        Sprite {
-            uv: [0.0, 0.25, 0.0, 1.0],
-            atlas: "player".into(),
-        },
-        Transform {
-            position: [0., 0., 0.3],
-            size: [1., 1.],
-            rotation: 0.0,
-        },
-        Player::default(),
-        Animation {
-            current_frame: 0,
-            frame_count: 4,
-            frame_duration: 0.2,
-            elapsed_time: 0.0,
-            loop_animation: true,
-            finished: false,
-            atlas_columns: 4,
-            atlas_rows: 1,
-        },
-        dna,
-    ));
+    let pools = resources.get_or_insert_with(EntityPools::default);
+    pools.pool(PLAYER_POOL).spawn(
+        world,
+        (
+            Sprite {
+                uv: [0.0, 0.25, 0.0, 1.0],
+                atlas: "player".into(),
+            },
+            Transform {
+                position: [0., 0., 0.3],
+                size: [1., 1.],
+                rotation: 0.0,
+            },
+            Player::default(),
+            Animation {
+                current_frame: 0,
+                frame_count: 4,
+                frame_duration: 0.2,
+                elapsed_time: 0.0,
+                loop_animation: true,
+                finished: false,
+                atlas_columns: 4,
+                atlas_rows: 1,
+            },
+            dna,
+        ),
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pixie::GameRng;
+
+    #[test]
+    fn pipe_prefabs_spawn_a_pair_with_four_parts() {
+        let mut prefabs = PrefabRegistry::new();
+        register_prefabs(&mut prefabs).unwrap();
+        let mut world = World::new();
+
+        pipe(&mut world, &prefabs, &mut GameRng::new(0), 8.0).unwrap();
+
+        let (pair, transform) = world
+            .query::<&Transform>()
+            .with::<&PipePair>()
+            .iter()
+            .map(|(entity, transform)| (entity, transform.clone()))
+            .next()
+            .unwrap();
+        assert_eq!(transform.position, [8.0, 0.0, 0.0]);
+        let mut indices: Vec<u8> = world
+            .query::<(&Pipe, &Parent, &Sprite)>()
+            .iter()
+            .map(|(_, (pipe, parent, sprite))| {
+                assert_eq!(parent.0, pair);
+                assert_eq!(sprite.atlas.as_str(), "tile");
+                pipe.pipe_index
+            })
+            .collect();
+        indices.sort_unstable();
+        assert_eq!(indices, [0, 1, 2, 3]);
+        let mut targets = world.query::<&Pipe>().with::<&PipeTarget>();
+        let targets: Vec<u8> = targets.iter().map(|(_, pipe)| pipe.pipe_index).collect();
+        assert_eq!(targets, [0]);
+    }
+
+    #[test]
+    fn ai_players_reuse_pooled_entities() {
+        let mut world = World::new();
+        let mut resources = ResourceContainer::new();
+        ai_player_with_resources(&mut world, &mut resources);
+        let player = world.query::<&Player>().iter().next().unwrap().0;
+        world.get::<&mut Transform>(player).unwrap().position[1] = 5.0;
+
+        resources
+            .get_mut::<EntityPools>()
+            .unwrap()
+            .pool(PLAYER_POOL)
+            .release(&mut world, player)
+            .unwrap();
+        ai_player_with_resources(&mut world, &mut resources);
+
+        assert_eq!(world.len(), 1);
+        assert!(!world.satisfies::<&Disabled>(player).unwrap());
+        assert_eq!(world.get::<&Transform>(player).unwrap().position[1], 0.0);
+    }
 }
@@ -1,4 +1,5 @@
 use crate::game_configs::GENE_SIZE;
+use serde::Deserialize;
 
 // Re-export generic components from engine
 pub use pixie::components::*;
@@ -8,13 +9,19 @@ pub struct Background {
     pub reposition_size: f32,
 }
 
-#[derive(Clone)]
-pub struct Pipe {
+/// Root of a pipe pair; its `Pipe` parts follow it as children
+#[derive(Clone, Deserialize)]
+pub struct PipePair {
     pub reposition_size: f32,
+}
+
+/// One cap or body of a pipe pair, laid out by `builder::pipe_part_transform`
+#[derive(Clone, Deserialize)]
+pub struct Pipe {
     pub pipe_index: u8,
 }
 
-#[derive(Clone)]
+#[derive(Clone, Deserialize)]
 pub struct PipeTarget {}
 
 #[derive(Clone, Default)]
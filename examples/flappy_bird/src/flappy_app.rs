@@ -1,13 +1,15 @@
 use hecs::World;
-use rand::rngs::ThreadRng;
-use winit::event::{ElementState, WindowEvent};
-use winit::keyboard::{KeyCode, PhysicalKey};
+use winit::event::WindowEvent;
+use winit::keyboard::KeyCode;
 
-use pixie::{Application, ResourceContainer, Text, TextStyle, Transform};
+use pixie::{
+    ActionMap, Application, Binding, EntityPools, EventReader, Events, GameRng, Input,
+    PrefabRegistry, ResourceContainer, Text, TextStyle, Transform, WorldNames,
+};
 
-use crate::builder::{ai_player_with_resources, background, pipe};
+use crate::builder::{ai_player_with_resources, background, pipe, register_prefabs};
 use crate::components::*;
-use crate::game_configs::GENE_SIZE;
+use crate::game_configs::{GENE_SIZE, PAUSE_ACTION, PLAYER_POOL, RESET_ACTION};
 use crate::resources::*;
 // systems are now built and owned by the engine
 
@@ -21,18 +23,20 @@ pub enum Stage {
     End,
 }
 
+/// `Name`s of the HUD text entities, which survive game resets
+const STATS_TEXT: &str = "hud_stats";
+const INSTRUCTION_TEXT: &str = "hud_instructions";
+
 pub struct FlappyApplication {
     stage: Stage,
-    stats_text_entity: Option<hecs::Entity>,
-    instruction_text_entity: Option<hecs::Entity>,
+    crash_reader: EventReader<PlayerCrashed>,
 }
 
 impl Default for FlappyApplication {
     fn default() -> Self {
         FlappyApplication {
             stage: Stage::Ready,
-            stats_text_entity: None,
-            instruction_text_entity: None,
+            crash_reader: EventReader::default(),
         }
     }
 }
@@ -48,13 +52,15 @@ impl Application for FlappyApplication {
 
         // Insert resources (Camera and DeltaTime are created automatically by Engine)
         resources.insert(GameFinished(false));
-        resources.insert(ThreadRng::default());
         resources.insert(Score::default());
         resources.insert(GeneHandler::default());
-        resources.insert(self.stage);
+        resources.add_events::<PlayerCrashed>();
+        Self::init_bindings(resources);
+        Self::init_prefabs(resources);
 
         // Create text entities
-        self.stats_text_entity = Some(world.spawn((
+        world.spawn((
+            Name::new(STATS_TEXT),
             Transform {
                 position: [-4.75, 8.75, 0.0],
                 size: [1.0, 1.0],
@@ -68,29 +74,16 @@ impl Application for FlappyApplication {
                 color: [0.0, 0.0, 0.0],
                 z_index: 1.0,
             },
-        )));
-
-        self.instruction_text_entity = Some(world.spawn((
-            Transform {
-                position: [-3.25, 1.25, 0.0],
-                size: [1.0, 1.0],
-                rotation: 0.0,
-            },
-            Text {
-                content: "Press any key to start".to_string(),
-            },
-            TextStyle {
-                size: [0.5, 0.5],
-                color: [0.0, 0.0, 0.0],
-                z_index: 1.0,
-            },
-        )));
+        ));
+        Self::spawn_instruction_text(world);
 
         // Initialize game
         self.init_game(world, resources);
     }
 
     fn update(&mut self, world: &mut World, resources: &mut ResourceContainer, _dt: f32) {
+        self.handle_keys(world, resources);
+        self.record_crashes(resources);
         self.check_game_finished(resources);
 
         if self.stage == Stage::End {
@@ -98,89 +91,106 @@ impl Application for FlappyApplication {
                 gene_handler.process_generation();
             }
             self.init_game(world, resources);
-            self.stage = Stage::Run;
+            self.set_stage(resources, Stage::Run);
         }
 
-        // Always update stage resource for systems to read
-        resources.insert(self.stage);
-
         // Update text entities
         self.update_texts(world, resources);
     }
 
     fn handle_input(
         &mut self,
-        world: &mut World,
-        resources: &mut ResourceContainer,
-        event: &WindowEvent,
+        _world: &mut World,
+        _resources: &mut ResourceContainer,
+        _event: &WindowEvent,
     ) -> bool {
-        match event {
-            WindowEvent::KeyboardInput {
-                event: key_event, ..
-            } => {
-                let physical_key = key_event.physical_key;
-                let state = key_event.state;
-
-                match self.stage {
-                    Stage::End => {
-                        if state == ElementState::Released {
-                            self.init_game(world, resources);
-                        }
-                        true
-                    }
-                    Stage::Ready | Stage::Pause => {
-                        if state == ElementState::Released {
-                            self.stage = Stage::Run;
-                        }
-                        true
-                    }
-                    Stage::Run => match physical_key {
-                        PhysicalKey::Code(KeyCode::KeyP) => {
-                            if state == ElementState::Released {
-                                self.stage = Stage::Pause;
-                            }
-                            true
-                        }
-                        PhysicalKey::Code(KeyCode::KeyR) => {
-                            if state == ElementState::Released {
-                                world.clear();
-                            }
-                            true
-                        }
-                        _ => false,
-                    },
+        // Keys are polled from the engine-managed Input in update, so replays see them too
+        false
+    }
+}
+
+impl FlappyApplication {
+    fn init_bindings(resources: &mut ResourceContainer) {
+        if !resources.contains::<ActionMap>() {
+            resources.insert(ActionMap::default());
+        }
+        let actions = resources
+            .get_mut::<ActionMap>()
+            .expect("ActionMap resource not found");
+        actions.bind(PAUSE_ACTION, Binding::Key(KeyCode::KeyP));
+        actions.bind(RESET_ACTION, Binding::Key(KeyCode::KeyR));
+
+        // Player rebinds override the defaults when the config file exists
+        #[cfg(not(target_arch = "wasm32"))]
+        {
+            use crate::game_configs::BINDINGS_FILE;
+            if std::path::Path::new(BINDINGS_FILE).exists() {
+                match ActionMap::load_file(BINDINGS_FILE) {
+                    Ok(overrides) => actions.merge(overrides),
+                    Err(error) => log::warn!("ignoring {BINDINGS_FILE}: {error}"),
                 }
             }
-            _ => false,
         }
     }
 
-    fn should_run_fixed(&self, _world: &World, _resources: &ResourceContainer) -> bool {
-        self.stage == Stage::Run
+    /// Start, pause and reset on key release
+    fn handle_keys(&mut self, world: &mut World, resources: &mut ResourceContainer) {
+        let (any_released, pause, reset) = {
+            let input = resources.fetch::<Input>();
+            let actions = resources.fetch::<ActionMap>();
+            (
+                input.any_key_just_released(),
+                actions.just_released(&input, PAUSE_ACTION),
+                actions.just_released(&input, RESET_ACTION),
+            )
+        };
+        if !any_released {
+            return;
+        }
+
+        match self.stage {
+            Stage::End => self.init_game(world, resources),
+            Stage::Ready | Stage::Pause => self.set_stage(resources, Stage::Run),
+            Stage::Run if pause => self.set_stage(resources, Stage::Pause),
+            Stage::Run if reset => {
+                world.clear();
+                if let Some(pools) = resources.get_mut::<EntityPools>() {
+                    pools.clear();
+                }
+            }
+            Stage::Run => {}
+        }
     }
-}
 
-impl FlappyApplication {
-    fn init_game(&mut self, world: &mut World, resources: &mut ResourceContainer) {
-        // Clear all entities except text entities
-        let text_entities = vec![self.stats_text_entity, self.instruction_text_entity]
-            .into_iter()
-            .flatten()
-            .collect::<std::collections::HashSet<_>>();
+    fn init_prefabs(resources: &mut ResourceContainer) {
+        register_prefabs(resources.get_or_insert_with(PrefabRegistry::new))
+            .expect("bundled prefabs are valid");
+    }
 
+    fn init_game(&mut self, world: &mut World, resources: &mut ResourceContainer) {
+        // Pool surviving players and clear everything else except the named HUD texts
         let to_delete: Vec<hecs::Entity> = world
             .iter()
+            .filter(|entity_ref| !entity_ref.has::<Name>() && !entity_ref.has::<Disabled>())
             .map(|entity_ref| entity_ref.entity())
-            .filter(|entity| !text_entities.contains(entity))
             .collect();
 
+        let pools = resources.get_or_insert_with(EntityPools::default);
         for entity in to_delete {
-            let _ = world.despawn(entity);
+            if world.satisfies::<&Player>(entity).unwrap_or(false) {
+                let _ = pools.pool(PLAYER_POOL).release(world, entity);
+            } else {
+                let _ = world.despawn(entity);
+            }
         }
 
         background(world);
-        pipe(world, 16.);
-        pipe(world, 8.);
+        {
+            let prefabs = resources.fetch::<PrefabRegistry>();
+            let mut rng = resources.fetch_mut::<GameRng>();
+            pipe(world, &prefabs, &mut *rng, 16.).expect("bundled pipe prefabs are valid");
+            pipe(world, &prefabs, &mut *rng, 8.).expect("bundled pipe prefabs are valid");
+        }
 
         for _ in 0..100 {
             ai_player_with_resources(world, resources);
@@ -190,17 +200,37 @@ impl FlappyApplication {
         resources.insert(GameFinished(false));
         resources.insert(Score::default());
 
-        self.stage = Stage::Ready;
+        self.set_stage(resources, Stage::Ready);
+    }
+
+    /// Keep the `Stage` resource that gates the gameplay systems in sync
+    fn set_stage(&mut self, resources: &mut ResourceContainer, stage: Stage) {
+        self.stage = stage;
+        resources.insert(stage);
     }
 
-    fn check_game_finished(&mut self, resources: &ResourceContainer) {
-        if let Some(finished) = resources.get::<GameFinished>() {
-            if finished.0 {
-                self.stage = Stage::End;
+    fn record_crashes(&mut self, resources: &mut ResourceContainer) {
+        let crashes: Vec<PlayerCrashed> = resources
+            .get::<Events<PlayerCrashed>>()
+            .map(|events| events.read(&mut self.crash_reader).copied().collect())
+            .unwrap_or_default();
+
+        if let Some(gene_handler) = resources.get_mut::<GeneHandler>() {
+            for crash in crashes {
+                gene_handler.set_score(crash.dna_index, crash.score);
             }
         }
     }
 
+    fn check_game_finished(&mut self, resources: &mut ResourceContainer) {
+        if resources
+            .get::<GameFinished>()
+            .is_some_and(|finished| finished.0)
+        {
+            self.set_stage(resources, Stage::End);
+        }
+    }
+
     pub fn get_gene_data(
         &self,
         world: &World,
@@ -212,16 +242,19 @@ impl FlappyApplication {
 
         // Find last player
         let mut last_player: Option<([f32; 3], usize)> = None;
-        for (_entity, (transform, dna, _player)) in
-            world.query::<(&pixie::Transform, &Dna, &Player)>().iter()
+        for (_entity, (transform, dna, _player)) in world
+            .query::<(&pixie::Transform, &Dna, &Player)>()
+            .without::<&Disabled>()
+            .iter()
         {
             last_player = Some((transform.position, dna.index));
         }
 
         // Find nearest pipe
         let mut pipe_position = [99.0, 0.0];
-        for (_entity, (transform, _pipe_target)) in
-            world.query::<(&pixie::Transform, &PipeTarget)>().iter()
+        for (_entity, (transform, _pipe_target)) in world
+            .query::<(&pixie::GlobalTransform, &PipeTarget)>()
+            .iter()
         {
             if transform.position[0] > -3.0 && pipe_position[0] > transform.position[0] {
                 pipe_position = [transform.position[0], transform.position[1]];
@@ -243,53 +276,111 @@ impl FlappyApplication {
     }
 
     fn update_texts(&mut self, world: &mut World, resources: &ResourceContainer) {
-        // Update stats text
-        if let Some(entity) = self.stats_text_entity {
-            if let Ok(mut text) = world.get::<&mut Text>(entity) {
-                let gene_handler = resources
-                    .get::<GeneHandler>()
-                    .expect("GeneHandler resource not found");
-                let score = resources.get::<Score>().expect("Score resource not found");
+        let names = resources
+            .get::<WorldNames>()
+            .expect("WorldNames resource not found");
 
-                // Count players
-                let players = world.query::<&Player>().iter().count();
-
-                let new_content = format!(
-                    "Generation: {}\nScore: {:.3}\nSurvive: {}",
-                    gene_handler.generation, score.0, players
-                );
+        // Update stats text
+        if let Some(entity) = names.find(world, STATS_TEXT) {
+            let gene_handler = resources
+                .get::<GeneHandler>()
+                .expect("GeneHandler resource not found");
+            let score = resources.get::<Score>().expect("Score resource not found");
+
+            // Count players
+            let players = world
+                .query::<&Player>()
+                .without::<&Disabled>()
+                .iter()
+                .count();
+
+            let new_content = format!(
+                "Generation: {}\nScore: {:.3}\nSurvive: {}",
+                gene_handler.generation, score.0, players
+            );
 
+            if let Ok(mut text) = world.get::<&mut Text>(entity) {
                 text.content = new_content;
             }
         }
 
-        // Update instruction text visibility based on stage
-        if let Some(entity) = self.instruction_text_entity {
-            // Hide/show instruction text based on stage
-            if self.stage == Stage::Ready {
-                // Make sure it exists
-                if world.get::<&Text>(entity).is_err() {
-                    // Re-spawn if it was removed
-                    self.instruction_text_entity = Some(world.spawn((
-                        Transform {
-                            position: [-3.25, 1.25, 0.0],
-                            size: [1.0, 1.0],
-                            rotation: 0.0,
-                        },
-                        Text {
-                            content: "Press any key to start".to_string(),
-                        },
-                        TextStyle {
-                            size: [0.5, 0.5],
-                            color: [0.0, 0.0, 0.0],
-                            z_index: 1.0,
-                        },
-                    )));
-                }
-            } else {
-                // Remove instruction text when not in Ready stage
+        // Show the instruction text only in the Ready stage
+        match (
+            names.find(world, INSTRUCTION_TEXT),
+            self.stage == Stage::Ready,
+        ) {
+            (None, true) => Self::spawn_instruction_text(world),
+            (Some(entity), false) => {
                 let _ = world.despawn(entity);
             }
+            _ => {}
+        }
+    }
+
+    fn spawn_instruction_text(world: &mut World) {
+        world.spawn((
+            Name::new(INSTRUCTION_TEXT),
+            Transform {
+                position: [-3.25, 1.25, 0.0],
+                size: [1.0, 1.0],
+                rotation: 0.0,
+            },
+            Text {
+                content: "Press any key to start".to_string(),
+            },
+            TextStyle {
+                size: [0.5, 0.5],
+                color: [0.0, 0.0, 0.0],
+                z_index: 1.0,
+            },
+        ));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pixie::{EngineConfig, HeadlessRunner, InputEvent, InputMode, InputRecording};
+
+    fn runner(config: &EngineConfig) -> HeadlessRunner<FlappyApplication> {
+        HeadlessRunner::new(
+            FlappyApplication::default(),
+            config,
+            Vec::new(),
+            crate::system::build(),
+        )
+        .unwrap()
+    }
+
+    fn stage(runner: &HeadlessRunner<FlappyApplication>) -> Stage {
+        *runner.resources().fetch::<Stage>()
+    }
+
+    #[test]
+    fn replayed_keys_start_and_pause_the_game() {
+        let config = EngineConfig::new("test", 500, 900)
+            .with_rng_seed(3)
+            .with_input_mode(InputMode::Record { path: None });
+        let mut recorder = runner(&config);
+        let mut stages = Vec::new();
+        for key in [KeyCode::Space, KeyCode::KeyP] {
+            recorder.send_input(InputEvent::KeyPressed(key));
+            recorder.send_input(InputEvent::KeyReleased(key));
+            recorder.step_fixed();
+            stages.push(stage(&recorder));
+        }
+        assert_eq!(stages, [Stage::Run, Stage::Pause]);
+
+        let recording =
+            InputRecording::from_bytes(&recorder.recording().unwrap().to_bytes()).unwrap();
+        let mut replayer = runner(
+            &EngineConfig::new("test", 500, 900).with_input_mode(InputMode::Replay(recording)),
+        );
+        let mut replayed = Vec::new();
+        for _ in 0..2 {
+            replayer.step_fixed();
+            replayed.push(stage(&replayer));
         }
+        assert_eq!(replayed, stages);
     }
 }
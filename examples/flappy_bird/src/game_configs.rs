@@ -1,7 +1,15 @@
 pub const SCREEN_SIZE: [u32; 2] = [500, 900];
 
 pub const HOLE_SIZE: f32 = 2.0;
+pub const PIPE_WIDTH: f32 = 2.0;
 pub const GRAVITY : f32 = 0.5;
 pub const JUMP_FORCE : f32 = 6.0;
 pub const GAME_SPEED : f32 = 5.0;
-pub const GENE_SIZE:usize = 51;
\ No newline at end of file
+pub const GENE_SIZE:usize = 51;
+
+pub const PAUSE_ACTION: &str = "pause";
+pub const RESET_ACTION: &str = "reset";
+/// `EntityPools` entry that keeps crashed players for the next generation
+pub const PLAYER_POOL: &str = "players";
+#[cfg(not(target_arch = "wasm32"))]
+pub const BINDINGS_FILE: &str = "flappy_bindings.cfg";
\ No newline at end of file
@@ -44,7 +44,8 @@ async fn run() -> Result<(), pixie::EngineError> {
         pixie::TextureAtlasAsset::from_static("player", include_bytes!("../assets/img/player.png")),
     ];
 
-    pixie::Engine::start(app, title, width, height, texture_atlases, dispatcher).await
+    let config = pixie::EngineConfig::new(title, width, height);
+    pixie::Engine::start(app, config, texture_atlases, dispatcher).await
 }
 
 #[cfg(not(target_arch = "wasm32"))]
@@ -1,2 +1,9 @@
 #[derive(Default)]
-pub struct GameFinished(pub bool);
\ No newline at end of file
+pub struct GameFinished(pub bool);
+
+/// Sent by `check_collision` for every player that hit a pipe or left the screen.
+#[derive(Clone, Copy, Debug)]
+pub struct PlayerCrashed {
+    pub dna_index: usize,
+    pub score: f32,
+}
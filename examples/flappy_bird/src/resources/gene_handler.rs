@@ -1,7 +1,7 @@
 use crate::components::Dna;
 use crate::game_configs::GENE_SIZE;
-use rand::rngs::ThreadRng;
-use rand::{thread_rng, Rng};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
 
 const EVOLVE_OFFSET: f32 = 0.1;
 const SURVIVE_RATIO: f32 = 0.1;
@@ -9,7 +9,7 @@ pub struct GeneHandler {
     gene_container: Vec<[f32; GENE_SIZE]>,
     fitness: Vec<f32>,
     pub generation: usize,
-    rng: ThreadRng,
+    rng: StdRng,
     gene_count: usize,
 }
 
@@ -19,7 +19,7 @@ impl Default for GeneHandler {
             gene_container: vec![],
             fitness: vec![],
             generation: 0,
-            rng: thread_rng(),
+            rng: StdRng::from_entropy(),
             gene_count: 100,
         };
 
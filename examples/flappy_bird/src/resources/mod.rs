@@ -1,5 +1,5 @@
 pub use score::Score;
-pub use game_stage::GameFinished;
+pub use game_stage::{GameFinished, PlayerCrashed};
 pub use gene_handler::GeneHandler;
 
 mod game_stage;
@@ -1,39 +1,36 @@
 use hecs::World;
-use pixie::ResourceContainer;
+use pixie::{EntityPools, ResourceContainer};
 
-use crate::components::{Dna, Pipe, Player, Transform};
-use crate::flappy_app::Stage;
-use crate::resources::{GeneHandler, Score};
+use crate::components::{Disabled, Dna, GlobalTransform, Pipe, Player, Transform};
+use crate::game_configs::PLAYER_POOL;
+use crate::resources::{PlayerCrashed, Score};
 
 /// Check collisions between players and pipes/boundaries
 pub fn check_collision(world: &mut World, resources: &mut ResourceContainer) {
-    let stage = resources.get::<Stage>().expect("Stage resource not found");
-
-    // Only run when game is in Run stage
-    if *stage != Stage::Run {
-        return;
-    }
-
     let score = resources
         .get::<Score>()
         .expect("Score resource not found")
         .0;
 
-    // Collect entities to delete (can't delete while iterating)
-    let mut entities_to_delete = Vec::new();
+    // Collect crashed players (can't pool them while iterating)
+    let mut crashed = Vec::new();
 
     // Check each player
-    for (entity, (_player, player_tr, dna)) in world.query::<(&Player, &Transform, &Dna)>().iter() {
+    for (entity, (_player, player_tr, dna)) in world
+        .query::<(&Player, &Transform, &Dna)>()
+        .without::<&Disabled>()
+        .iter()
+    {
         let pt = player_tr.position;
 
         // Check boundary collision
         if pt[1] < -7.0 || pt[1] > 9.0 {
-            entities_to_delete.push((entity, dna.index));
+            crashed.push((entity, dna.index));
             continue;
         }
 
         // Check pipe collision
-        for (_pipe_entity, (_pipe, pipe_tr)) in world.query::<(&Pipe, &Transform)>().iter() {
+        for (_pipe_entity, (_pipe, pipe_tr)) in world.query::<(&Pipe, &GlobalTransform)>().iter() {
             // Find closest point on pipe to player
             let obstacle_point = [
                 if pt[0] > pipe_tr.position[0] + pipe_tr.size[0] * 0.5 {
@@ -56,17 +53,18 @@ pub fn check_collision(world: &mut World, resources: &mut ResourceContainer) {
                 + (obstacle_point[1] - pt[1]) * (obstacle_point[1] - pt[1]);
 
             if dist_pow < 0.2 {
-                entities_to_delete.push((entity, dna.index));
+                crashed.push((entity, dna.index));
                 break;
             }
         }
     }
 
-    // Delete collided entities and record scores
-    if let Some(gene_handler) = resources.get_mut::<GeneHandler>() {
-        for (entity, dna_index) in entities_to_delete {
-            gene_handler.set_score(dna_index, score);
-            let _ = world.despawn(entity);
-        }
+    // Pool collided players for the next generation; the application records their scores
+    for (entity, dna_index) in crashed {
+        let _ = resources
+            .get_or_insert_with(EntityPools::default)
+            .pool(PLAYER_POOL)
+            .release(world, entity);
+        resources.send_event(PlayerCrashed { dna_index, score });
     }
 }
@@ -1,11 +1,13 @@
-// Re-export generic systems from engine
-pub use pixie::systems::*;
 pub use pixie::dispatcher::UnifiedDispatcher;
+use pixie::dispatcher::{resource_equals, IntoSystemDescriptor, SystemStage};
+use pixie::systems::propagate_transforms;
+
+use crate::flappy_app::Stage;
 
 // Re-export game-specific system functions
 pub use check_collision::check_collision;
 pub use scroll_background::scroll_background;
-pub use scroll_pipe::scroll_pipe;
+pub use scroll_pipe::ScrollPipe;
 pub use update_player::update_player;
 pub use check_game_stage::check_game_stage;
 pub use process_nn::process_nn;
@@ -17,18 +19,60 @@ mod update_player;
 mod check_game_stage;
 mod process_nn;
 
-// Define game-specific system execution order with function pointers
+// Gameplay systems only run while the round is in progress
 pixie::construct_dispatcher!(
-    update_camera,
-    scroll_background,
-    scroll_pipe,
-    process_nn,
-    update_player,
-    check_collision,
-    check_game_stage,
-    update_animation
+    process_nn
+        .label("think")
+        .in_stage(SystemStage::PrePhysics)
+        .run_if(resource_equals(Stage::Run)),
+    scroll_background.run_if(resource_equals(Stage::Run)),
+    ScrollPipe.run_if(resource_equals(Stage::Run)),
+    update_player
+        .after("think")
+        .run_if(resource_equals(Stage::Run)),
+    // Pipe parts collide at their world position, so refresh it after scrolling
+    propagate_transforms
+        .in_stage(SystemStage::PostPhysics)
+        .before("collide")
+        .run_if(resource_equals(Stage::Run)),
+    check_collision
+        .label("collide")
+        .in_stage(SystemStage::PostPhysics)
+        .run_if(resource_equals(Stage::Run)),
+    check_game_stage
+        .after("collide")
+        .in_stage(SystemStage::PostPhysics)
+        .run_if(resource_equals(Stage::Run))
 );
 
+/// Fixed-step schedule; the engine's default variable schedule animates sprites per frame
 pub fn build() -> Box<dyn UnifiedDispatcher> {
     new_dispatch()
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::resources::{GameFinished, Score};
+    use pixie::ResourceContainer;
+
+    #[test]
+    fn gameplay_systems_wait_for_run_stage() {
+        let mut dispatcher = build();
+        let mut world = hecs::World::new();
+        let mut resources = ResourceContainer::new();
+        let mut time = pixie::Time::new(0.1);
+        time.begin_fixed_step();
+        resources.insert(time);
+        resources.insert(Score::default());
+        resources.insert(Stage::Ready);
+
+        // With no players left, check_game_stage ends the round as soon as it runs
+        dispatcher.run_now(&mut world, &mut resources);
+        assert!(!resources.contains::<GameFinished>());
+
+        resources.insert(Stage::Run);
+        dispatcher.run_now(&mut world, &mut resources);
+        assert!(resources.get::<GameFinished>().unwrap().0);
+    }
+}
@@ -1,8 +1,7 @@
 use hecs::World;
 use pixie::ResourceContainer;
 
-use crate::components::{Dna, PipeTarget, Player, Transform};
-use crate::flappy_app::Stage;
+use crate::components::{Disabled, Dna, GlobalTransform, PipeTarget, Player, Transform};
 
 /// Process neural network layer
 fn process_layer(input_array: Vec<f32>, genes: Vec<&f32>, neuron_count: usize) -> Vec<f32> {
@@ -38,24 +37,22 @@ fn process_layer(input_array: Vec<f32>, genes: Vec<&f32>, neuron_count: usize) -
 }
 
 /// Process neural network for AI players
-pub fn process_nn(world: &mut World, resources: &mut ResourceContainer) {
-    let stage = resources.get::<Stage>().expect("Stage resource not found");
-
-    // Only run when game is in Run stage
-    if *stage != Stage::Run {
-        return;
-    }
-
+pub fn process_nn(world: &mut World, _resources: &mut ResourceContainer) {
     // Find nearest pipe
     let mut pipe_position = [99.0, 0.0];
-    for (_entity, (_pipe_target, pipe_tr)) in world.query::<(&PipeTarget, &Transform)>().iter() {
+    for (_entity, (_pipe_target, pipe_tr)) in
+        world.query::<(&PipeTarget, &GlobalTransform)>().iter()
+    {
         if pipe_tr.position[0] > -1.5 && pipe_position[0] > pipe_tr.position[0] {
             pipe_position = [pipe_tr.position[0], pipe_tr.position[1]];
         }
     }
 
     // Process each player's neural network
-    for (_entity, (player, p_tr, gene)) in world.query_mut::<(&mut Player, &Transform, &Dna)>() {
+    for (_entity, (player, p_tr, gene)) in world
+        .query_mut::<(&mut Player, &Transform, &Dna)>()
+        .without::<&Disabled>()
+    {
         let input_data = vec![
             pipe_position[0] - p_tr.position[0],
             pipe_position[1] - p_tr.position[1],
@@ -1,96 +1,96 @@
 use hecs::World;
-use pixie::ResourceContainer;
-use rand::rngs::ThreadRng;
+use pixie::{GameRng, ResourceContainer, System};
 use rand::Rng;
 
-use crate::components::{Pipe, Transform};
-use crate::flappy_app::Stage;
-use crate::game_configs::{GAME_SPEED, HOLE_SIZE};
+use crate::builder::pipe_part_transform;
+use crate::components::{Children, Pipe, PipePair, Transform};
+use crate::game_configs::{GAME_SPEED, PIPE_WIDTH};
 use crate::resources::Score;
-use pixie::DeltaTime;
-
-type PipeReposition = (hecs::Entity, u8, [f32; 3], [f32; 2], f32);
+use pixie::Time;
 
 /// Scroll pipes and respawn them when off-screen
-pub fn scroll_pipe(world: &mut World, resources: &mut ResourceContainer) {
-    let dt_value = resources
-        .get::<DeltaTime>()
-        .expect("DeltaTime resource not found")
-        .0;
-    let stage = resources.get::<Stage>().expect("Stage resource not found");
+///
+/// New pipe heights come from the engine's seeded `GameRng`, so replays see the same pipes.
+pub struct ScrollPipe;
 
-    // Only run when game is in Run stage
-    if *stage != Stage::Run {
-        return;
+impl System for ScrollPipe {
+    fn run(&mut self, world: &mut World, resources: &mut ResourceContainer) {
+        scroll_pipe(world, resources);
     }
+}
+
+fn scroll_pipe(world: &mut World, resources: &mut ResourceContainer) {
+    let dt_value = resources
+        .get::<Time>()
+        .expect("Time resource not found")
+        .delta();
 
     // Update score based on time
     if let Some(score) = resources.get_mut::<Score>() {
         score.0 += dt_value;
     }
 
-    // Collect entities that need repositioning and normal updates
-    let mut to_reposition: Vec<PipeReposition> = Vec::new();
-    let mut to_update: Vec<(hecs::Entity, [f32; 3])> = Vec::new();
-
-    for (entity, (pipe, transform)) in world.query::<(&Pipe, &Transform)>().iter() {
-        let mut new_transform = transform.clone();
-        new_transform.position[0] -= dt_value * GAME_SPEED;
+    // Pairs that scrolled off-screen share one random height per frame
+    let mut rand = None;
+    let mut wrapped = Vec::new();
 
-        if new_transform.position[0] + new_transform.size[0] / 2.0 < -6.0 {
-            to_reposition.push((
-                entity,
-                pipe.pipe_index,
-                new_transform.position,
-                new_transform.size,
-                pipe.reposition_size,
-            ));
-        } else {
-            // Collect for update after query is done
-            to_update.push((entity, new_transform.position));
+    for (_entity, (pair, transform, children)) in
+        world.query_mut::<(&PipePair, &mut Transform, &Children)>()
+    {
+        transform.position[0] -= dt_value * GAME_SPEED;
+        if transform.position[0] + PIPE_WIDTH / 2.0 >= -6.0 {
+            continue;
         }
+
+        // Reposition the pair and lay its parts out around a new gap
+        let rand = *rand.get_or_insert_with(|| {
+            resources
+                .get_mut::<GameRng>()
+                .expect("GameRng resource not found")
+                .gen_range(1.0..9.0)
+        });
+        transform.position[0] += pair.reposition_size;
+        wrapped.extend(children.0.iter().map(|child| (*child, rand)));
     }
 
-    // Apply normal position updates
-    for (entity, position) in to_update {
-        if let Ok(mut t) = world.get::<&mut Transform>(entity) {
-            t.position = position;
+    for (child, rand) in wrapped {
+        if let Ok((pipe, transform)) = world.query_one_mut::<(&Pipe, &mut Transform)>(child) {
+            *transform = pipe_part_transform(pipe.pipe_index, rand);
         }
     }
+}
 
-    // Reposition pipes with new random height
-    if !to_reposition.is_empty() {
-        let rng = resources
-            .get_mut::<ThreadRng>()
-            .expect("ThreadRng resource not found");
-        let rand = rng.gen_range(1.0..9.0);
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::builder::{pipe, register_prefabs};
+    use pixie::PrefabRegistry;
 
-        for (entity, pipe_index, mut position, mut size, reposition_size) in to_reposition {
-            position[0] += reposition_size;
+    fn wrapped_heights(seed: u64) -> Vec<[f32; 3]> {
+        let mut prefabs = PrefabRegistry::new();
+        register_prefabs(&mut prefabs).unwrap();
+        let mut world = World::new();
+        let mut resources = ResourceContainer::new();
+        resources.insert(GameRng::new(seed));
+        pipe(&mut world, &prefabs, &mut GameRng::new(seed), -20.0).unwrap();
+        let mut time = Time::new(0.1);
+        time.begin_fixed_step();
+        resources.insert(time);
 
-            match pipe_index {
-                0 => {
-                    position[1] = rand - 6.0;
-                }
-                1 => {
-                    position[1] = (rand - 6.0) * 0.5 - 4.0;
-                    size[1] = rand;
-                }
-                2 => {
-                    position[1] = rand + HOLE_SIZE - 4.0;
-                }
-                3 => {
-                    position[1] = (rand + HOLE_SIZE - 4.0) * 0.5 + 5.5;
-                    size[1] = 13.0 - (rand + HOLE_SIZE);
-                }
-                _ => {}
-            }
+        ScrollPipe.run(&mut world, &mut resources);
 
-            // Update transform
-            if let Ok(mut transform) = world.get::<&mut Transform>(entity) {
-                transform.position = position;
-                transform.size = size;
-            }
-        }
+        let mut parts: Vec<(u8, [f32; 3])> = world
+            .query_mut::<(&Pipe, &Transform)>()
+            .into_iter()
+            .map(|(_, (pipe, transform))| (pipe.pipe_index, transform.position))
+            .collect();
+        parts.sort_by_key(|(pipe_index, _)| *pipe_index);
+        parts.into_iter().map(|(_, position)| position).collect()
+    }
+
+    #[test]
+    fn wrapped_pipe_heights_follow_the_game_rng_seed() {
+        assert_eq!(wrapped_heights(7), wrapped_heights(7));
+        assert_ne!(wrapped_heights(7), wrapped_heights(8));
     }
 }
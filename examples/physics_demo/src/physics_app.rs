@@ -1,19 +1,27 @@
-use hecs::{Entity, World};
-use winit::event::{ElementState, MouseButton, WindowEvent};
-use winit::keyboard::{KeyCode, PhysicalKey};
-
-use pixie::{Application, Gravity, ResourceContainer, Sprite, Text, TextStyle, Transform};
+use hecs::World;
+use winit::event::WindowEvent;
+use winit::keyboard::KeyCode;
+
+use pixie::{
+    Application, Disabled, EntityPool, EntityPools, Gravity, Input, Name, PrefabError,
+    PrefabRegistry, ResourceContainer, Sprite, Text, TextStyle, Timer, Transform, WorldNames,
+};
 use pixie::{BodyType, BoxCollider, CircleCollider, Force, RigidBody, Velocity};
 
 // systems are now built and owned by the engine; keep module private here
 use crate::config;
 
+/// `Name` of the HUD text entity
+const HUD_TEXT: &str = "hud";
+
+/// `EntityPools` entry that keeps balls between resets
+const BALL_POOL: &str = "balls";
+
 pub struct PhysicsApp {
     ball_state: BallState,
     balls_to_shoot: Vec<[f32; 2]>, // Store positions for balls to be shot
     shot_index: usize,
-    shoot_timer: f32,
-    shoot_interval: f32,
+    shoot_timer: Timer,
 
     // Ball shooting configuration
     ball_count: usize,
@@ -24,7 +32,6 @@ pub struct PhysicsApp {
     ball_sizes: [f32; 3], // Three different ball sizes
     ball_mass: f32,
     ball_restitution: f32,
-    hud_text_entity: Option<Entity>,
 }
 
 #[derive(Debug, Clone, Copy)]
@@ -40,8 +47,7 @@ impl Default for PhysicsApp {
             ball_state: BallState::Ready,
             balls_to_shoot: Vec::new(),
             shot_index: 0,
-            shoot_timer: 0.0,
-            shoot_interval: 0.05, // Shoot one ball every 0.05 seconds
+            shoot_timer: Timer::repeating(0.05), // Shoot one ball every 0.05 seconds
 
             // Ball shooting configuration
             ball_count: 100,
@@ -52,7 +58,6 @@ impl Default for PhysicsApp {
             ball_sizes: [0.3, 0.5, 0.7], // Three different ball sizes
             ball_mass: 1.0,              // Increase mass to resist gravity better
             ball_restitution: 0.6,       // Increase bounce
-            hud_text_entity: None,
         }
     }
 }
@@ -63,6 +68,12 @@ impl Application for PhysicsApp {
 
         // Insert resources (Camera and DeltaTime are created automatically by Engine)
         resources.insert(Gravity::default());
+        resources
+            .get_or_insert_with(PrefabRegistry::new)
+            .load_str(include_str!("../assets/prefabs.json"))
+            .expect("bundled prefabs are valid");
+        resources.get_or_insert_with(WorldNames::default);
+        self.prewarm_balls(world, resources);
 
         // Create boundaries (static walls)
         self.create_boundary(world, 0.0, -12.5, config::BOX_SIZE[0], 1.0); // Bottom
@@ -83,61 +94,60 @@ impl Application for PhysicsApp {
 
         self.create_hud(world);
         self.start_ball_shooting();
-        self.update_hud(world);
+        self.update_hud(world, resources);
         log::info!(
             "Physics demo initialized - {} launch groups + 3 walls created",
             self.ball_count
         );
     }
 
-    fn update(&mut self, world: &mut World, _resources: &mut ResourceContainer, dt: f32) {
+    fn update(&mut self, world: &mut World, resources: &mut ResourceContainer, dt: f32) {
+        if resources
+            .get::<Input>()
+            .is_some_and(|input| input.key_just_pressed(KeyCode::KeyR))
+        {
+            self.reset(world, resources);
+        }
+
         // Handle ball shooting sequence
         if matches!(self.ball_state, BallState::Shooting) {
-            self.process_ball_shooting(world, dt);
+            self.process_ball_shooting(world, resources, dt);
         }
-        self.update_hud(world);
+        self.update_hud(world, resources);
     }
 
     // No dispatcher building in app anymore
 
     fn handle_input(
         &mut self,
-        world: &mut World,
+        _world: &mut World,
         _resources: &mut ResourceContainer,
-        event: &WindowEvent,
+        _event: &WindowEvent,
     ) -> bool {
-        match event {
-            WindowEvent::MouseInput {
-                state: ElementState::Pressed,
-                button: MouseButton::Left,
-                ..
-            } => {
-                // Only allow shooting when in Ready state
-                // if matches!(self.ball_state, BallState::Ready) {
-                //     self.start_ball_shooting();
-                //     true
-                // } else {
-                //     false // Ignore clicks when already shooting or complete
-                // }
-                false
-            }
-            WindowEvent::KeyboardInput {
-                event: key_event, ..
-            } if key_event.state == ElementState::Pressed => match key_event.physical_key {
-                PhysicalKey::Code(KeyCode::KeyR) => {
-                    self.reset(world);
-                    true
-                }
-                _ => false,
-            },
-            _ => false,
-        }
+        // Reset is polled from the engine-managed Input resource in update
+        false
     }
 }
 
 impl PhysicsApp {
+    /// Spawn a full volley of disabled balls up front so shooting reuses them
+    fn prewarm_balls(&self, world: &mut World, resources: &mut ResourceContainer) {
+        let balls = self.ball_count * 3;
+        resources.get_or_insert_with(EntityPools::default);
+        let prefabs = resources.fetch::<PrefabRegistry>();
+        resources
+            .fetch_mut::<EntityPools>()
+            .insert(BALL_POOL, EntityPool::with_max_size(balls))
+            .prewarm(world, balls, |world| {
+                prefabs
+                    .spawn_prefab(world, "ball", ())
+                    .expect("bundled prefabs are valid")
+            });
+    }
+
     fn create_hud(&mut self, world: &mut World) {
-        self.hud_text_entity = Some(world.spawn((
+        world.spawn((
+            Name::new(HUD_TEXT),
             Transform::new([-10.5, 18.5, 0.0], [1.0, 1.0]),
             Text::default(),
             TextStyle {
@@ -145,7 +155,7 @@ impl PhysicsApp {
                 color: [1.0, 1.0, 1.0],
                 z_index: 2.0,
             },
-        )));
+        ));
 
         world.spawn((
             Transform::new([-2.0, 4.0, 0.0], [1.0, 1.0]),
@@ -160,9 +170,10 @@ impl PhysicsApp {
         ));
     }
 
-    fn update_hud(&self, world: &mut World) {
+    fn update_hud(&self, world: &mut World, resources: &ResourceContainer) {
         let ball_count = world
             .query::<&RigidBody>()
+            .without::<&Disabled>()
             .iter()
             .filter(|(_, body)| body.body_type == BodyType::Dynamic)
             .count();
@@ -172,14 +183,18 @@ impl PhysicsApp {
             BallState::Complete => "Complete",
         };
 
-        if let Some(entity) = self.hud_text_entity {
+        let hud = resources
+            .get::<WorldNames>()
+            .and_then(|names| names.find(world, HUD_TEXT));
+        if let Some(entity) = hud {
+            let content = format!(
+                "PHYSICS SANDBOX\n\nBalls: {}\nTarget: {}\nStatus: {}\n\nR Reset",
+                ball_count,
+                self.ball_count * 3,
+                status
+            );
             if let Ok(mut text) = world.get::<&mut Text>(entity) {
-                text.content = format!(
-                    "PHYSICS SANDBOX\n\nBalls: {}\nTarget: {}\nStatus: {}\n\nR Reset",
-                    ball_count,
-                    self.ball_count * 3,
-                    status
-                );
+                text.content = content;
             }
         }
     }
@@ -202,30 +217,37 @@ impl PhysicsApp {
             },
             Velocity::default(),
             Force::default(),
-            BoxCollider { width, height },
+            BoxCollider {
+                width,
+                height,
+                fixed_rotation: false,
+            },
         ));
     }
 
-    fn reset(&mut self, world: &mut World) {
+    fn reset(&mut self, world: &mut World, resources: &ResourceContainer) {
         // Reset ball state FIRST to stop any ongoing shooting
         self.ball_state = BallState::Ready;
         self.balls_to_shoot.clear();
         self.shot_index = 0;
-        self.shoot_timer = 0.0;
+        self.shoot_timer.reset();
 
-        // Collect entities to delete (can't delete while iterating)
-        let to_delete: Vec<hecs::Entity> = world
+        // Collect entities to pool (can't disable while iterating)
+        let to_release: Vec<hecs::Entity> = world
             .query::<&RigidBody>()
+            .without::<&Disabled>()
             .iter()
             .filter(|(_, body)| body.body_type == BodyType::Dynamic)
             .map(|(entity, _)| entity)
             .collect();
 
-        log::info!("Deleting {} dynamic entities", to_delete.len());
+        log::info!("Pooling {} dynamic entities", to_release.len());
 
-        for entity in to_delete {
-            if let Err(e) = world.despawn(entity) {
-                log::error!("Failed to delete entity: {:?}", e);
+        let mut pools = resources.fetch_mut::<EntityPools>();
+        let pool = pools.pool(BALL_POOL);
+        for entity in to_release {
+            if let Err(e) = pool.release(world, entity) {
+                log::error!("Failed to pool entity: {:?}", e);
             }
         }
 
@@ -250,36 +272,48 @@ impl PhysicsApp {
             self.start_x,
             self.start_y,
             self.shoot_angle,
-            self.shoot_interval
+            self.shoot_timer.duration()
         );
     }
 
-    fn process_ball_shooting(&mut self, world: &mut World, dt: f32) {
-        self.shoot_timer += dt;
+    fn process_ball_shooting(&mut self, world: &mut World, resources: &ResourceContainer, dt: f32) {
+        let shots = self.shoot_timer.tick(dt).times_finished_this_tick();
 
-        if self.shoot_timer >= self.shoot_interval && self.shot_index < self.balls_to_shoot.len() {
+        for _ in 0..shots {
+            if self.shot_index >= self.balls_to_shoot.len() {
+                break;
+            }
             let pos = self.balls_to_shoot[self.shot_index];
 
             // Cycle through ball sizes: 0.3, 0.5, 0.7
             let size_index = self.shot_index % 3;
             let radius = self.ball_sizes[size_index];
 
-            // log::info!("Shooting ball {} at ({}, {}) with radius {}",
-            //     self.shot_index, pos[0], pos[1], radius);
-
-            self.shoot_ball(world, pos, radius);
-
+            let prefabs = resources
+                .get::<PrefabRegistry>()
+                .expect("PrefabRegistry resource not found");
+            let mut pools = resources.fetch_mut::<EntityPools>();
+            let pool = pools.pool(BALL_POOL);
+            if let Err(error) = self.shoot_ball(world, prefabs, pool, pos, radius) {
+                log::error!("failed to shoot ball: {error}");
+            }
             self.shot_index += 1;
-            self.shoot_timer = 0.0;
+        }
 
-            if self.shot_index >= self.balls_to_shoot.len() {
-                self.ball_state = BallState::Complete;
-                log::info!("Ball shooting sequence completed");
-            }
+        if self.shot_index >= self.balls_to_shoot.len() {
+            self.ball_state = BallState::Complete;
+            log::info!("Ball shooting sequence completed");
         }
     }
 
-    fn shoot_ball(&self, world: &mut World, pos: [f32; 2], radius: f32) {
+    fn shoot_ball(
+        &self,
+        world: &mut World,
+        prefabs: &PrefabRegistry,
+        pool: &mut EntityPool,
+        pos: [f32; 2],
+        radius: f32,
+    ) -> Result<(), PrefabError> {
         let angle_rad = self.shoot_angle.to_radians();
         let velocity_x = angle_rad.cos() * self.shoot_speed;
         let velocity_y = angle_rad.sin() * self.shoot_speed;
@@ -288,72 +322,40 @@ impl PhysicsApp {
         // Mass proportional to area (radius²) for 2D physics
         let mass = self.ball_mass * (radius * radius) / (0.5 * 0.5);
 
-        world.spawn((
-            Transform {
-                position: [pos[0], pos[1], 0.5],
-                size: [ball_size, ball_size],
-                rotation: 0.0,
-            },
-            Sprite {
-                uv: [0.0, 1.0, 0.0, 1.0],
-                atlas: "ball".into(),
-            },
-            RigidBody {
-                body_type: BodyType::Dynamic,
-                mass,
-                restitution: self.ball_restitution,
-            },
-            Velocity {
-                linear: [velocity_x, velocity_y],
-                angular: 2.5,
-            },
-            Force::default(),
-            CircleCollider { radius },
-        ));
-        world.spawn((
-            Transform {
-                position: [pos[0] + ball_size * 0.5, pos[1] - ball_size, 0.5],
-                size: [ball_size, ball_size],
-                rotation: 0.0,
-            },
-            Sprite {
-                uv: [0.0, 1.0, 0.0, 1.0],
-                atlas: "ball".into(),
-            },
-            RigidBody {
-                body_type: BodyType::Dynamic,
-                mass,
-                restitution: self.ball_restitution,
-            },
-            Velocity {
-                linear: [velocity_x, velocity_y],
-                angular: -1.5,
-            },
-            Force::default(),
-            CircleCollider { radius },
-        ));
-        world.spawn((
-            Transform {
-                position: [pos[0] - ball_size * 0.5, pos[1] + ball_size, 0.5],
-                size: [ball_size, ball_size],
-                rotation: 0.0,
-            },
-            Sprite {
-                uv: [0.0, 1.0, 0.0, 1.0],
-                atlas: "ball".into(),
-            },
-            RigidBody {
-                body_type: BodyType::Dynamic,
-                mass,
-                restitution: self.ball_restitution,
-            },
-            Velocity {
-                linear: [velocity_x, velocity_y],
-                angular: 0.75,
-            },
-            Force::default(),
-            CircleCollider { radius },
-        ));
+        // Offset from `pos` and spin of each ball in the volley
+        let volley = [
+            ([0.0, 0.0], 2.5),
+            ([ball_size * 0.5, -ball_size], -1.5),
+            ([-ball_size * 0.5, ball_size], 0.75),
+        ];
+        for (offset, angular) in volley {
+            let ball = (
+                Transform {
+                    position: [pos[0] + offset[0], pos[1] + offset[1], 0.5],
+                    size: [ball_size, ball_size],
+                    rotation: 0.0,
+                },
+                RigidBody {
+                    body_type: BodyType::Dynamic,
+                    mass,
+                    restitution: self.ball_restitution,
+                },
+                Velocity {
+                    linear: [velocity_x, velocity_y],
+                    angular,
+                },
+                CircleCollider { radius },
+            );
+            match pool.acquire(world) {
+                Some(entity) => world
+                    .insert(entity, ball)
+                    .expect("acquired entities are alive"),
+                None => {
+                    prefabs.spawn_prefab(world, "ball", ball)?;
+                }
+            }
+        }
+        Ok(())
     }
 }
 
@@ -398,8 +400,57 @@ mod tests {
         let mut resources = ResourceContainer::new();
         app.init(&mut world, &mut resources);
 
-        app.update(&mut world, &mut resources, app.shoot_interval);
+        app.update(&mut world, &mut resources, app.shoot_timer.duration());
+
+        assert!(hud_content(&world).contains("Balls: 3"));
+    }
+
+    #[test]
+    fn polled_reset_key_restarts_shooting() {
+        let mut app = PhysicsApp::default();
+        let mut world = World::new();
+        let mut resources = ResourceContainer::new();
+        resources.insert(Input::default());
+        app.init(&mut world, &mut resources);
+        app.update(&mut world, &mut resources, app.shoot_timer.duration());
+
+        resources
+            .get_mut::<Input>()
+            .unwrap()
+            .press_key(KeyCode::KeyR);
+        app.update(&mut world, &mut resources, 0.0);
 
+        assert!(hud_content(&world).contains("Balls: 0"));
+    }
+
+    #[test]
+    fn reuses_pooled_balls_across_resets() {
+        let mut app = PhysicsApp::default();
+        let mut world = World::new();
+        let mut resources = ResourceContainer::new();
+        resources.insert(Input::default());
+        app.init(&mut world, &mut resources);
+        let spawned = world.len();
+        let pooled = |resources: &ResourceContainer| {
+            resources
+                .fetch::<EntityPools>()
+                .get(BALL_POOL)
+                .unwrap()
+                .free_len()
+        };
+        assert_eq!(pooled(&resources), 300);
+
+        app.update(&mut world, &mut resources, app.shoot_timer.duration());
+        assert_eq!(pooled(&resources), 297);
+        resources
+            .get_mut::<Input>()
+            .unwrap()
+            .press_key(KeyCode::KeyR);
+        app.update(&mut world, &mut resources, 0.0);
+        assert_eq!(pooled(&resources), 300);
+
+        app.update(&mut world, &mut resources, app.shoot_timer.duration());
         assert!(hud_content(&world).contains("Balls: 3"));
+        assert_eq!(world.len(), spawned);
     }
 }
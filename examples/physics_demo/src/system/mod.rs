@@ -2,9 +2,8 @@
 use pixie::systems::*;
 pub use pixie::dispatcher::UnifiedDispatcher;
 
-// Define physics demo system execution order
+// Define physics demo fixed-step system order; camera and animation run per frame
 pixie::construct_dispatcher!(
-    update_camera,
     apply_gravity,
     update_physics,
     collision_system
@@ -0,0 +1,40 @@
+//! Times `collision_system` on growing ball pits with each `BroadPhase`.
+//!
+//! Run with `cargo bench -p pixie --bench collision`.
+
+use std::time::{Duration, Instant};
+
+use pixie::scenes::ball_pit;
+use pixie::{collision_system, BroadPhase, ResourceContainer};
+
+const STEPS: u32 = 20;
+
+fn time_steps(balls: usize, broad_phase: BroadPhase) -> Duration {
+    let mut world = ball_pit(balls, 1);
+    let mut resources = ResourceContainer::new();
+    resources.insert(broad_phase);
+
+    let start = Instant::now();
+    for _ in 0..STEPS {
+        collision_system(&mut world, &mut resources);
+    }
+    start.elapsed() / STEPS
+}
+
+fn main() {
+    println!(
+        "{:>6} {:>14} {:>14} {:>8}",
+        "balls", "brute ms/step", "sweep ms/step", "speedup"
+    );
+    for balls in [100, 200, 400, 800, 1600] {
+        let brute = time_steps(balls, BroadPhase::BruteForce);
+        let sweep = time_steps(balls, BroadPhase::SweepAndPrune);
+        println!(
+            "{:>6} {:>14.3} {:>14.3} {:>7.1}x",
+            balls,
+            brute.as_secs_f64() * 1000.0,
+            sweep.as_secs_f64() * 1000.0,
+            brute.as_secs_f64() / sweep.as_secs_f64()
+        );
+    }
+}
@@ -14,6 +14,9 @@ pub trait Application {
     fn fixed_update(&mut self, _world: &mut World, _resources: &mut ResourceContainer, _fixed_dt: f32) { }
 
     /// Handle input (returns whether the event was consumed)
+    ///
+    /// Only live window events arrive here. Replays feed the engine-managed `Input`
+    /// resource, so gameplay input should be polled from `Input` or `ActionMap` instead.
     fn handle_input(&mut self, world: &mut World, resources: &mut ResourceContainer, event: &WindowEvent) -> bool;
 
     /// Whether to run fixed step (control via pause/state). Default: always run
@@ -3,8 +3,10 @@ use std::fmt;
 use std::sync::Arc;
 
 use hecs::Entity;
+use serde::{Deserialize, Serialize};
 
-#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+#[derive(Clone, Debug, Eq, Hash, PartialEq, Deserialize)]
+#[serde(from = "String")]
 pub struct AtlasId(Arc<str>);
 
 impl AtlasId {
@@ -29,6 +31,13 @@ impl From<String> for AtlasId {
     }
 }
 
+/// Serializes as the atlas name, the same form it is deserialized from.
+impl Serialize for AtlasId {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.as_str())
+    }
+}
+
 impl fmt::Display for AtlasId {
     fn fmt(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
         formatter.write_str(self.as_str())
@@ -0,0 +1,341 @@
+use std::collections::HashSet;
+use std::marker::PhantomData;
+use std::ops::{Deref, DerefMut};
+
+use hecs::{Component, ComponentError, Entity, MissingComponent, QueryOneError, World};
+
+use crate::resources::ResourceContainer;
+
+/// Point on the change clock kept by `ResourceContainer`
+///
+/// Writes are stamped with `ResourceContainer::change_tick`, which moves on at the end of
+/// every simulation frame. A reader remembers the current tick when it looks at the
+/// changes and next time asks for everything stamped at or after it.
+/// `ChangeTick::default()` is older than every stamp.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct ChangeTick(u64);
+
+impl ChangeTick {
+    pub(crate) const FIRST: ChangeTick = ChangeTick(1);
+
+    pub(crate) fn next(self) -> Self {
+        ChangeTick(self.0 + 1)
+    }
+}
+
+/// When the `T` of an entity was added and last mutably accessed
+///
+/// The engine inserts it once next to every tracked `T` at the end of the frame the
+/// component first shows up in. Until then the entity counts as added and changed.
+pub struct ComponentTicks<T> {
+    added: ChangeTick,
+    changed: ChangeTick,
+    marker: PhantomData<fn() -> T>,
+}
+
+impl<T> ComponentTicks<T> {
+    fn new(tick: ChangeTick) -> Self {
+        Self {
+            added: tick,
+            changed: tick,
+            marker: PhantomData,
+        }
+    }
+
+    pub fn added(&self) -> ChangeTick {
+        self.added
+    }
+
+    pub fn changed(&self) -> ChangeTick {
+        self.changed
+    }
+}
+
+/// Mutable access to a tracked component that stamps its changed tick when written
+///
+/// Reading through it leaves the tick alone, so only real writes count as changes.
+pub struct Mut<'a, T> {
+    value: &'a mut T,
+    ticks: Option<&'a mut ComponentTicks<T>>,
+    tick: ChangeTick,
+}
+
+impl<'a, T> Mut<'a, T> {
+    /// Wrap the items of a `(&mut T, Option<&mut ComponentTicks<T>>)` query, stamping
+    /// writes with `tick` from `ResourceContainer::change_tick` or
+    /// `SystemResources::change_tick`
+    pub fn new(
+        value: &'a mut T,
+        ticks: Option<&'a mut ComponentTicks<T>>,
+        tick: ChangeTick,
+    ) -> Self {
+        Self { value, ticks, tick }
+    }
+}
+
+impl<T> Deref for Mut<'_, T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        self.value
+    }
+}
+
+impl<T> DerefMut for Mut<'_, T> {
+    fn deref_mut(&mut self) -> &mut T {
+        if let Some(ticks) = self.ticks.as_deref_mut() {
+            ticks.changed = self.tick;
+        }
+        self.value
+    }
+}
+
+/// Mutably borrow the `T` of `entity` so writes show up in `Changed<T>`
+pub fn get_tracked_mut<'w, T: Component>(
+    world: &'w mut World,
+    resources: &ResourceContainer,
+    entity: Entity,
+) -> Result<Mut<'w, T>, ComponentError> {
+    let tick = resources.change_tick();
+    match world.query_one_mut::<(&mut T, Option<&mut ComponentTicks<T>>)>(entity) {
+        Ok((value, ticks)) => Ok(Mut::new(value, ticks, tick)),
+        Err(QueryOneError::NoSuchEntity) => Err(ComponentError::NoSuchEntity),
+        Err(QueryOneError::Unsatisfied) => Err(ComponentError::MissingComponent(
+            MissingComponent::new::<T>(),
+        )),
+    }
+}
+
+/// Filter for entities whose `T` was added at or after a tick
+///
+/// Reads `ComponentTicks<T>` instead of adding marker components, e.g.
+/// `Added::<Text>::since(tick).entities(&world)`, or `matches(ticks)` inside a query for
+/// `Option<&ComponentTicks<Text>>`.
+pub struct Added<T> {
+    since: ChangeTick,
+    marker: PhantomData<fn() -> T>,
+}
+
+impl<T: Component> Added<T> {
+    pub fn since(since: ChangeTick) -> Self {
+        Self {
+            since,
+            marker: PhantomData,
+        }
+    }
+
+    pub fn matches(&self, ticks: Option<&ComponentTicks<T>>) -> bool {
+        ticks.is_none_or(|ticks| ticks.added >= self.since)
+    }
+
+    pub fn entities(&self, world: &World) -> Vec<Entity> {
+        matching(world, |ticks| self.matches(ticks))
+    }
+}
+
+/// Filter for entities whose `T` was added or mutably accessed at or after a tick
+///
+/// Writes only count when they go through `Mut`, e.g. from `get_tracked_mut`.
+pub struct Changed<T> {
+    since: ChangeTick,
+    marker: PhantomData<fn() -> T>,
+}
+
+impl<T: Component> Changed<T> {
+    pub fn since(since: ChangeTick) -> Self {
+        Self {
+            since,
+            marker: PhantomData,
+        }
+    }
+
+    pub fn matches(&self, ticks: Option<&ComponentTicks<T>>) -> bool {
+        ticks.is_none_or(|ticks| ticks.changed >= self.since)
+    }
+
+    pub fn entities(&self, world: &World) -> Vec<Entity> {
+        matching(world, |ticks| self.matches(ticks))
+    }
+}
+
+fn matching<T: Component>(
+    world: &World,
+    filter: impl Fn(Option<&ComponentTicks<T>>) -> bool,
+) -> Vec<Entity> {
+    world
+        .query::<Option<&ComponentTicks<T>>>()
+        .with::<&T>()
+        .iter()
+        .filter(|(_, ticks)| filter(*ticks))
+        .map(|(entity, _)| entity)
+        .collect()
+}
+
+/// Entities that lost their tracked `T` or were despawned
+///
+/// Removals are found at the end of every frame and kept for two frames, so caches keyed
+/// by entity can be cleaned up.
+pub struct RemovedComponents<T> {
+    /// Entities given `ComponentTicks<T>` and not yet reported as removed
+    tracked: HashSet<Entity>,
+    /// Removals of the last two frames with the tick they were found at
+    removed: Vec<(Entity, ChangeTick)>,
+    /// Removals found before this tick were dropped
+    kept_since: ChangeTick,
+    /// Tick the current frame's removals start at
+    frame_start: ChangeTick,
+    marker: PhantomData<fn() -> T>,
+}
+
+impl<T: Component> RemovedComponents<T> {
+    /// Entities removed at or after `since`, or `None` if some of them were already
+    /// dropped and the caller has to rebuild from the world
+    pub fn since(&self, since: ChangeTick) -> Option<impl Iterator<Item = Entity> + '_> {
+        (since >= self.kept_since).then(|| {
+            self.removed
+                .iter()
+                .filter(move |(_, tick)| *tick >= since)
+                .map(|(entity, _)| *entity)
+        })
+    }
+
+    fn maintain(&mut self, world: &mut World, tick: ChangeTick) {
+        let kept_since = self.frame_start;
+        self.removed.retain(|(_, removed)| *removed >= kept_since);
+        self.kept_since = kept_since;
+        self.frame_start = tick;
+
+        // Removed components leave their ticks behind
+        let stripped: Vec<Entity> = world
+            .query_mut::<()>()
+            .with::<&ComponentTicks<T>>()
+            .without::<&T>()
+            .into_iter()
+            .map(|(entity, ())| entity)
+            .collect();
+        for entity in stripped {
+            let _ = world.remove_one::<ComponentTicks<T>>(entity);
+            self.tracked.remove(&entity);
+            self.removed.push((entity, tick));
+        }
+
+        // Despawned entities take their ticks along; only look for them when some are gone
+        let alive = world
+            .query_mut::<()>()
+            .with::<&ComponentTicks<T>>()
+            .into_iter()
+            .len();
+        if alive != self.tracked.len() {
+            let despawned: Vec<Entity> = self
+                .tracked
+                .iter()
+                .copied()
+                .filter(|entity| !world.contains(*entity))
+                .collect();
+            for entity in despawned {
+                self.tracked.remove(&entity);
+                self.removed.push((entity, tick));
+            }
+        }
+
+        let added: Vec<Entity> = world
+            .query_mut::<()>()
+            .with::<&T>()
+            .without::<&ComponentTicks<T>>()
+            .into_iter()
+            .map(|(entity, ())| entity)
+            .collect();
+        for entity in added {
+            let _ = world.insert_one(entity, ComponentTicks::<T>::new(tick));
+            self.tracked.insert(entity);
+        }
+    }
+}
+
+impl<T> Default for RemovedComponents<T> {
+    fn default() -> Self {
+        Self {
+            tracked: HashSet::new(),
+            removed: Vec::new(),
+            kept_since: ChangeTick::default(),
+            frame_start: ChangeTick::default(),
+            marker: PhantomData,
+        }
+    }
+}
+
+/// Give new tracked `T` components their ticks and collect removals
+pub(crate) fn maintain_component_ticks<T: Component>(
+    world: &mut World,
+    resources: &mut ResourceContainer,
+) {
+    let tick = resources.change_tick();
+    if let Some(removed) = resources.get_mut::<RemovedComponents<T>>() {
+        removed.maintain(world, tick);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Clone, Debug, PartialEq)]
+    struct Health(u32);
+
+    #[test]
+    fn tracks_added_changed_and_removed_components_by_tick() {
+        let mut world = World::new();
+        let mut resources = ResourceContainer::new();
+        resources.track_changes::<Health>();
+        let steady = world.spawn((Health(3),));
+        let hurt = world.spawn((Health(3),));
+        let doomed = world.spawn((Health(3),));
+        let stripped = world.spawn((Health(3),));
+
+        // Not maintained yet: every entity counts as added
+        assert_eq!(
+            Added::<Health>::since(ChangeTick::FIRST)
+                .entities(&world)
+                .len(),
+            4
+        );
+        resources.detect_changes(&mut world);
+        let since = resources.change_tick();
+        assert!(Changed::<Health>::since(since).entities(&world).is_empty());
+
+        get_tracked_mut::<Health>(&mut world, &resources, hurt)
+            .unwrap()
+            .0 = 2;
+        // Reading through `Mut` is not a change
+        assert_eq!(
+            get_tracked_mut::<Health>(&mut world, &resources, steady)
+                .unwrap()
+                .0,
+            3
+        );
+        world.despawn(doomed).unwrap();
+        world.remove_one::<Health>(stripped).unwrap();
+        resources.detect_changes(&mut world);
+
+        assert_eq!(Changed::<Health>::since(since).entities(&world), [hurt]);
+        assert!(Added::<Health>::since(since).entities(&world).is_empty());
+        let removed: Vec<Entity> = resources
+            .get::<RemovedComponents<Health>>()
+            .unwrap()
+            .since(since)
+            .unwrap()
+            .collect();
+        assert_eq!(removed.len(), 2);
+        assert!(removed.contains(&doomed));
+        assert!(removed.contains(&stripped));
+        assert!(!world
+            .satisfies::<&ComponentTicks<Health>>(stripped)
+            .unwrap());
+
+        // Removals are only kept for two frames
+        resources.detect_changes(&mut world);
+        resources.detect_changes(&mut world);
+        let removed = resources.get::<RemovedComponents<Health>>().unwrap();
+        assert!(removed.since(since).is_none());
+    }
+}
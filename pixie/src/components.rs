@@ -2,14 +2,15 @@
 // Any type that implements Send + Sync can be used as a component
 
 #[allow(dead_code)]
-#[derive(Debug, Clone, Eq, PartialEq, Hash, Copy)]
+#[derive(Debug, Clone, Eq, PartialEq, Hash, Copy, Serialize, Deserialize)]
 pub enum BodyType {
     Static,
     Kinematic,
     Dynamic,
 }
 
-#[derive(Clone)]
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(default)]
 pub struct Collider {
     pub aabb_offset: [f32; 4],
 }
@@ -21,13 +22,14 @@ impl Default for Collider {
     }
 }
 
-#[derive(Clone)]
+#[derive(Clone, Serialize, Deserialize)]
 pub struct Sprite {
     pub uv: [f32; 4],
     pub atlas: AtlasId,
 }
 
-#[derive(Clone, Debug, PartialEq)]
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
 pub struct Transform {
     pub position: [f32; 3],
     pub size: [f32; 2],
@@ -59,12 +61,145 @@ impl Default for Transform {
     }
 }
 
-#[derive(Clone, Default)]
+/// Name for finding an entity again through the `WorldNames` resource.
+#[derive(Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct Name(pub String);
+
+impl Name {
+    pub fn new(name: impl Into<String>) -> Self {
+        Self(name.into())
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+/// Labels for finding groups of entities through `WorldNames::tagged`.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Tags(pub Vec<String>);
+
+impl Tags {
+    pub fn new<I, S>(tags: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        Self(tags.into_iter().map(Into::into).collect())
+    }
+
+    pub fn contains(&self, tag: &str) -> bool {
+        self.0.iter().any(|candidate| candidate == tag)
+    }
+}
+
+/// Entity whose `Transform` this entity's `Transform` is relative to.
+///
+/// Local positions and sizes are in units of the parent's size, so a parent's `size`
+/// scales its children. Rigid bodies are simulated in local space and should be roots.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct Parent(pub Entity);
+
+/// Direct children of an entity, kept in sync with their `Parent` by `propagate_transforms`.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Children(pub Vec<Entity>);
+
+/// World-space transform computed by `propagate_transforms` from the `Transform` hierarchy.
+///
+/// Equals `Transform` for entities without a `Parent`. Rotated children of a parent with
+/// a non-uniform size are approximated, as shear is not represented.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct GlobalTransform {
+    pub position: [f32; 3],
+    pub size: [f32; 2],
+    pub rotation: f32,
+}
+
+impl GlobalTransform {
+    /// World transform of a child whose local transform is `local`.
+    pub fn mul_transform(&self, local: &Transform) -> GlobalTransform {
+        let (sin, cos) = self.rotation.sin_cos();
+        let x = local.position[0] * self.size[0];
+        let y = local.position[1] * self.size[1];
+        GlobalTransform {
+            position: [
+                self.position[0] + x * cos - y * sin,
+                self.position[1] + x * sin + y * cos,
+                self.position[2] + local.position[2],
+            ],
+            size: [self.size[0] * local.size[0], self.size[1] * local.size[1]],
+            rotation: self.rotation + local.rotation,
+        }
+    }
+}
+
+impl Default for GlobalTransform {
+    fn default() -> Self {
+        GlobalTransform::from(&Transform::default())
+    }
+}
+
+impl From<&Transform> for GlobalTransform {
+    fn from(value: &Transform) -> Self {
+        Self {
+            position: value.position,
+            size: value.size,
+            rotation: value.rotation,
+        }
+    }
+}
+
+/// `Transform` from before the latest fixed step, kept for render interpolation.
+///
+/// Maintained by the engine for entities with a `RigidBody`.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct PreviousTransform {
+    pub position: [f32; 3],
+    pub rotation: f32,
+}
+
+impl PreviousTransform {
+    /// Blends from this snapshot towards `current` by `alpha` in `[0, 1]`.
+    pub fn lerp(&self, current: &Transform, alpha: f32) -> ([f32; 3], f32) {
+        let position = [
+            self.position[0] + (current.position[0] - self.position[0]) * alpha,
+            self.position[1] + (current.position[1] - self.position[1]) * alpha,
+            self.position[2] + (current.position[2] - self.position[2]) * alpha,
+        ];
+        let rotation = self.rotation + (current.rotation - self.rotation) * alpha;
+        (position, rotation)
+    }
+}
+
+impl From<&Transform> for PreviousTransform {
+    fn from(value: &Transform) -> Self {
+        Self {
+            position: value.position,
+            rotation: value.rotation,
+        }
+    }
+}
+
+/// Marker that renders an entity at its current `Transform` without interpolation.
+///
+/// Insert it before a teleport and remove it once the jump has been rendered.
+#[derive(Clone, Copy, Debug, Default, Serialize, Deserialize)]
+pub struct NoInterpolation;
+
+/// Marker for entities parked in an `EntityPool`.
+///
+/// Physics, collisions, animation and rendering skip disabled entities.
+#[derive(Clone, Copy, Debug, Default, Serialize, Deserialize)]
+pub struct Disabled;
+
+#[derive(Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
 pub struct Text {
     pub content: String,
 }
 
-#[derive(Clone)]
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(default)]
 pub struct TextStyle {
     /// World-space size of the font em square rasterized at 48 pixels.
     pub size: [f32; 2],
@@ -82,7 +217,8 @@ impl Default for TextStyle {
     }
 }
 
-#[derive(Clone)]
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(default)]
 pub struct Animation {
     pub current_frame: u32,
     pub frame_count: u32,
@@ -110,7 +246,8 @@ impl Default for Animation {
 }
 
 // Physics components
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(default)]
 pub struct RigidBody {
     pub body_type: BodyType,
     pub mass: f32,
@@ -127,19 +264,22 @@ impl Default for RigidBody {
     }
 }
 
-#[derive(Clone, Default, Debug)]
+#[derive(Clone, Default, Debug, Serialize, Deserialize)]
+#[serde(default)]
 pub struct Velocity {
     pub linear: [f32; 2],
     pub angular: f32,
 }
 
-#[derive(Clone, Default)]
+#[derive(Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
 pub struct Force {
     pub linear: [f32; 2],
     pub torque: f32,
 }
 
-#[derive(Clone)]
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(default)]
 pub struct CircleCollider {
     pub radius: f32,
 }
@@ -150,10 +290,13 @@ impl Default for CircleCollider {
     }
 }
 
-#[derive(Clone)]
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(default)]
 pub struct BoxCollider {
     pub width: f32,
     pub height: f32,
+    /// Collide as an axis-aligned box, ignoring `Transform::rotation`.
+    pub fixed_rotation: bool,
 }
 
 impl Default for BoxCollider {
@@ -161,7 +304,10 @@ impl Default for BoxCollider {
         BoxCollider {
             width: 1.0,
             height: 1.0,
+            fixed_rotation: false,
         }
     }
 }
 use crate::AtlasId;
+use hecs::Entity;
+use serde::{Deserialize, Serialize};
@@ -0,0 +1,204 @@
+use std::path::PathBuf;
+
+use crate::recording::InputRecording;
+
+/// Where the simulation takes its input from.
+#[derive(Clone, Debug, Default)]
+pub enum InputMode {
+    /// Live window events only.
+    #[default]
+    Live,
+    /// Live window events, also captured into an `InputRecording`.
+    /// The windowed engine writes it to `path` on exit.
+    Record { path: Option<PathBuf> },
+    /// Frame timings and inputs come from the recording; live input is ignored.
+    Replay(InputRecording),
+}
+
+/// Startup and frame loop settings shared by `Engine` and `HeadlessRunner`.
+///
+/// Construct with `EngineConfig::new` and adjust with the `with_*` builder methods.
+#[derive(Clone, Debug)]
+pub struct EngineConfig {
+    pub title: String,
+    pub width: u32,
+    pub height: u32,
+    /// Seconds simulated by each fixed step.
+    pub fixed_dt: f32,
+    /// Longest frame the loop will advance in one redraw, in seconds.
+    pub max_frame_time: f32,
+    /// Fixed steps run per frame before the remaining backlog is dropped.
+    pub max_fixed_steps_per_frame: u32,
+    /// Half height of the default orthographic camera, in world units.
+    pub camera_height: f32,
+    pub clear_color: wgpu::Color,
+    /// Requested surface present mode. `None` uses the adapter's preferred mode.
+    pub present_mode: Option<wgpu::PresentMode>,
+    pub resizable: bool,
+    /// Initial number of atlas batches reserved by the render extractor.
+    pub sprite_atlas_capacity: usize,
+    /// Initial number of text entries reserved by the render extractor.
+    pub text_capacity: usize,
+    /// Seed for the `GameRng` resource. `None` picks a random seed.
+    /// Ignored when replaying, which uses the recording's seed.
+    pub rng_seed: Option<u64>,
+    pub input_mode: InputMode,
+    /// Fixed ticks kept in the `SnapshotHistory` resource. `0` records no snapshots.
+    pub snapshot_history: usize,
+    /// Capture `FrameStats` spans from startup and write them as a Chrome trace on exit.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub chrome_trace: Option<PathBuf>,
+}
+
+impl EngineConfig {
+    pub fn new(title: impl Into<String>, width: u32, height: u32) -> Self {
+        Self {
+            title: title.into(),
+            width,
+            height,
+            ..Self::default()
+        }
+    }
+
+    pub fn with_fixed_dt(mut self, fixed_dt: f32) -> Self {
+        self.fixed_dt = fixed_dt;
+        self
+    }
+
+    pub fn with_max_frame_time(mut self, max_frame_time: f32) -> Self {
+        self.max_frame_time = max_frame_time;
+        self
+    }
+
+    pub fn with_max_fixed_steps_per_frame(mut self, max_fixed_steps_per_frame: u32) -> Self {
+        self.max_fixed_steps_per_frame = max_fixed_steps_per_frame;
+        self
+    }
+
+    pub fn with_camera_height(mut self, camera_height: f32) -> Self {
+        self.camera_height = camera_height;
+        self
+    }
+
+    pub fn with_clear_color(mut self, clear_color: wgpu::Color) -> Self {
+        self.clear_color = clear_color;
+        self
+    }
+
+    pub fn with_present_mode(mut self, present_mode: wgpu::PresentMode) -> Self {
+        self.present_mode = Some(present_mode);
+        self
+    }
+
+    pub fn with_resizable(mut self, resizable: bool) -> Self {
+        self.resizable = resizable;
+        self
+    }
+
+    pub fn with_render_capacity(
+        mut self,
+        sprite_atlas_capacity: usize,
+        text_capacity: usize,
+    ) -> Self {
+        self.sprite_atlas_capacity = sprite_atlas_capacity;
+        self.text_capacity = text_capacity;
+        self
+    }
+
+    pub fn with_rng_seed(mut self, seed: u64) -> Self {
+        self.rng_seed = Some(seed);
+        self
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn with_chrome_trace(mut self, path: impl Into<PathBuf>) -> Self {
+        self.chrome_trace = Some(path.into());
+        self
+    }
+
+    pub fn with_input_mode(mut self, input_mode: InputMode) -> Self {
+        self.input_mode = input_mode;
+        self
+    }
+
+    pub fn with_snapshot_history(mut self, capacity: usize) -> Self {
+        self.snapshot_history = capacity;
+        self
+    }
+
+    pub fn aspect_ratio(&self) -> f32 {
+        self.width as f32 / self.height as f32
+    }
+}
+
+impl Default for EngineConfig {
+    fn default() -> Self {
+        Self {
+            title: "pixie".to_string(),
+            width: 800,
+            height: 600,
+            fixed_dt: 1.0 / 60.0, // 60 Hz physics
+            max_frame_time: 0.2,
+            max_fixed_steps_per_frame: 16,
+            camera_height: 20.0,
+            clear_color: wgpu::Color {
+                r: 0.0,
+                g: 1.0,
+                b: 0.0,
+                a: 1.0,
+            },
+            present_mode: None,
+            resizable: true,
+            sprite_atlas_capacity: 8,
+            text_capacity: 128,
+            rng_seed: None,
+            input_mode: InputMode::Live,
+            snapshot_history: 0,
+            #[cfg(not(target_arch = "wasm32"))]
+            chrome_trace: None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn new_keeps_loop_defaults() {
+        let config = EngineConfig::new("game", 500, 900);
+
+        assert_eq!(config.title, "game");
+        assert_eq!(config.width, 500);
+        assert_eq!(config.height, 900);
+        assert_eq!(config.fixed_dt, 1.0 / 60.0);
+        assert_eq!(config.max_frame_time, 0.2);
+        assert_eq!(config.camera_height, 20.0);
+        assert!(config.resizable);
+        assert!(config.present_mode.is_none());
+    }
+
+    #[test]
+    fn builder_methods_override_defaults() {
+        let config = EngineConfig::new("game", 100, 50)
+            .with_fixed_dt(0.01)
+            .with_max_frame_time(0.1)
+            .with_max_fixed_steps_per_frame(4)
+            .with_camera_height(9.0)
+            .with_present_mode(wgpu::PresentMode::Immediate)
+            .with_resizable(false)
+            .with_render_capacity(2, 16)
+            .with_rng_seed(3);
+
+        assert_eq!(config.fixed_dt, 0.01);
+        assert_eq!(config.max_frame_time, 0.1);
+        assert_eq!(config.max_fixed_steps_per_frame, 4);
+        assert_eq!(config.camera_height, 9.0);
+        assert_eq!(config.present_mode, Some(wgpu::PresentMode::Immediate));
+        assert!(!config.resizable);
+        assert_eq!(config.sprite_atlas_capacity, 2);
+        assert_eq!(config.text_capacity, 16);
+        assert_eq!(config.rng_seed, Some(3));
+        assert_eq!(config.aspect_ratio(), 2.0);
+    }
+}
@@ -0,0 +1,344 @@
+use std::any::{type_name, Any, TypeId};
+
+use hecs::{Component, World};
+
+use crate::change_detection::ChangeTick;
+use crate::resources::ResourceContainer;
+
+/// System function type - takes world and resources, returns nothing
+///
+/// These systems get exclusive access and never run alongside other systems.
+pub type SystemFn = fn(&mut World, &mut ResourceContainer);
+
+/// Exclusive system that can keep private state between runs, such as scratch buffers,
+/// a spatial hash or its own RNG
+///
+/// Plain functions and `FnMut` closures taking `(&mut World, &mut ResourceContainer)`
+/// implement it automatically.
+pub trait System {
+    /// Called once by the engine after `Application::init`, before the first run
+    fn init(&mut self, _world: &mut World, _resources: &mut ResourceContainer) {}
+
+    fn run(&mut self, world: &mut World, resources: &mut ResourceContainer);
+
+    /// Name used in schedule errors
+    fn name(&self) -> &'static str {
+        type_name::<Self>()
+    }
+}
+
+impl<F> System for F
+where
+    F: FnMut(&mut World, &mut ResourceContainer),
+{
+    fn run(&mut self, world: &mut World, resources: &mut ResourceContainer) {
+        self(world, resources)
+    }
+}
+
+/// Boxed exclusive system, so closures and stateful systems can be listed next to
+/// plain functions
+pub type ExclusiveSystem = Box<dyn System>;
+
+/// Parallel system function type - shared world plus the resources it declared
+pub type ParallelSystemFn = fn(&World, &mut SystemResources);
+
+/// Components and resources a parallel system reads and writes
+///
+/// Two systems conflict when one writes something the other reads or writes.
+/// The native dispatcher only runs non-conflicting systems at the same time.
+#[derive(Clone, Debug, Default)]
+pub struct SystemAccess {
+    component_reads: Vec<(TypeId, &'static str)>,
+    component_writes: Vec<(TypeId, &'static str)>,
+    resource_reads: Vec<(TypeId, &'static str)>,
+    resource_writes: Vec<(TypeId, &'static str)>,
+}
+
+impl SystemAccess {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Declare a component queried as `&T`
+    pub fn read<T: Component>(mut self) -> Self {
+        self.component_reads.push(entry::<T>());
+        self
+    }
+
+    /// Declare a component queried as `&mut T`
+    pub fn write<T: Component>(mut self) -> Self {
+        self.component_writes.push(entry::<T>());
+        self
+    }
+
+    /// Declare a resource fetched with `SystemResources::get`
+    pub fn read_resource<T: Sync + 'static>(mut self) -> Self {
+        self.resource_reads.push(entry::<T>());
+        self
+    }
+
+    /// Declare a resource fetched with `SystemResources::get_mut`
+    pub fn write_resource<T: Send + Sync + 'static>(mut self) -> Self {
+        self.resource_writes.push(entry::<T>());
+        self
+    }
+
+    pub fn conflicts_with(&self, other: &SystemAccess) -> bool {
+        overlaps(&self.component_writes, &other.component_reads)
+            || overlaps(&self.component_writes, &other.component_writes)
+            || overlaps(&other.component_writes, &self.component_reads)
+            || overlaps(&self.resource_writes, &other.resource_reads)
+            || overlaps(&self.resource_writes, &other.resource_writes)
+            || overlaps(&other.resource_writes, &self.resource_reads)
+    }
+
+    /// Whether the system declared write access to resource `T`
+    pub fn writes_resource<T: 'static>(&self) -> bool {
+        let type_id = TypeId::of::<T>();
+        self.resource_writes.iter().any(|(id, _)| *id == type_id)
+    }
+
+    fn resources(&self) -> impl Iterator<Item = &(TypeId, &'static str)> {
+        self.resource_reads.iter().chain(&self.resource_writes)
+    }
+}
+
+fn entry<T: 'static>() -> (TypeId, &'static str) {
+    (TypeId::of::<T>(), type_name::<T>())
+}
+
+fn overlaps(left: &[(TypeId, &'static str)], right: &[(TypeId, &'static str)]) -> bool {
+    left.iter()
+        .any(|(id, _)| right.iter().any(|(other, _)| id == other))
+}
+
+/// A system that declares its access up front so it can share a batch with others
+#[derive(Clone)]
+pub struct ParallelSystem {
+    name: &'static str,
+    run: ParallelSystemFn,
+    access: SystemAccess,
+}
+
+impl ParallelSystem {
+    pub fn new(name: &'static str, run: ParallelSystemFn, access: SystemAccess) -> Self {
+        Self { name, run, access }
+    }
+
+    pub fn name(&self) -> &'static str {
+        self.name
+    }
+
+    pub fn access(&self) -> &SystemAccess {
+        &self.access
+    }
+
+    /// Run on its own with access borrowed from the container
+    pub(crate) fn run_alone(&self, world: &World, resources: &mut ResourceContainer) {
+        let mut views = SystemResources::for_systems(resources, &[self]);
+        (self.run)(world, &mut views[0]);
+    }
+
+    // Only the native dispatcher runs systems side by side
+    #[cfg_attr(target_arch = "wasm32", allow(dead_code))]
+    pub(crate) fn run(&self, world: &World, resources: &mut SystemResources) {
+        (self.run)(world, resources)
+    }
+}
+
+/// Resources a parallel system declared, borrowed from the `ResourceContainer`
+///
+/// Fetching a resource the system did not declare panics with the system and type names.
+pub struct SystemResources {
+    system: &'static str,
+    change_tick: ChangeTick,
+    /// Declared resources, with a pointer when the container holds them
+    entries: Vec<ResourceEntry>,
+}
+
+struct ResourceEntry {
+    type_id: TypeId,
+    writable: bool,
+    resource: Option<*mut (dyn Any + Send + Sync)>,
+}
+
+// SAFETY: `ResourceContainer` only stores `Send + Sync` resources, and the dispatcher
+// never hands a written resource to two systems of the same batch.
+unsafe impl Send for SystemResources {}
+
+impl SystemResources {
+    /// Borrow the declared resources of every system in one batch.
+    ///
+    /// The systems must not conflict with each other.
+    pub(crate) fn for_systems(
+        container: &mut ResourceContainer,
+        systems: &[&ParallelSystem],
+    ) -> Vec<SystemResources> {
+        for system in systems {
+            for (type_id, _) in &system.access.resource_writes {
+                container.mark_changed(*type_id);
+            }
+        }
+        let pointers = container.resource_pointers(
+            systems
+                .iter()
+                .flat_map(|system| system.access.resources().map(|(id, _)| *id)),
+        );
+
+        systems
+            .iter()
+            .map(|system| {
+                let declared = system
+                    .access
+                    .resource_reads
+                    .iter()
+                    .map(|(id, _)| (*id, false))
+                    .chain(
+                        system
+                            .access
+                            .resource_writes
+                            .iter()
+                            .map(|(id, _)| (*id, true)),
+                    );
+                let entries = declared
+                    .map(|(type_id, writable)| ResourceEntry {
+                        type_id,
+                        writable,
+                        resource: pointers
+                            .iter()
+                            .find(|(id, _)| *id == type_id)
+                            .map(|(_, pointer)| *pointer),
+                    })
+                    .collect();
+                SystemResources {
+                    system: system.name,
+                    change_tick: container.change_tick(),
+                    entries,
+                }
+            })
+            .collect()
+    }
+
+    /// Get a declared resource, or `None` if the container does not hold it
+    pub fn get<T: Sync + 'static>(&self) -> Option<&T> {
+        let entry = self.entry::<T>(false);
+        // SAFETY: the pointer targets a boxed resource that outlives the batch, and no
+        // system of the batch writes it while this one may read it.
+        entry
+            .resource
+            .and_then(|pointer| unsafe { (*pointer).downcast_ref::<T>() })
+    }
+
+    /// Get a resource declared with `write_resource`
+    pub fn get_mut<T: Send + Sync + 'static>(&mut self) -> Option<&mut T> {
+        let entry = self.entry::<T>(true);
+        // SAFETY: writes are exclusive within a batch and `&mut self` prevents aliasing here.
+        entry
+            .resource
+            .and_then(|pointer| unsafe { (*pointer).downcast_mut::<T>() })
+    }
+
+    /// The tick to stamp component writes with, for `Mut::new`
+    pub fn change_tick(&self) -> ChangeTick {
+        self.change_tick
+    }
+
+    fn entry<T: 'static>(&self, write: bool) -> &ResourceEntry {
+        let type_id = TypeId::of::<T>();
+        let entry = self
+            .entries
+            .iter()
+            .find(|entry| entry.type_id == type_id && (entry.writable || !write));
+        match entry {
+            Some(entry) => entry,
+            None => panic!(
+                "system `{}` did not declare {} access to resource `{}`",
+                self.system,
+                if write { "write" } else { "read" },
+                type_name::<T>()
+            ),
+        }
+    }
+}
+
+/// A system as stored by the dispatchers
+pub enum DispatchSystem {
+    /// Runs alone with `&mut World` and the whole `ResourceContainer`
+    Exclusive(ExclusiveSystem),
+    Parallel(ParallelSystem),
+}
+
+impl DispatchSystem {
+    #[cfg_attr(target_arch = "wasm32", allow(dead_code))]
+    pub(crate) fn access(&self) -> Option<&SystemAccess> {
+        match self {
+            DispatchSystem::Exclusive(_) => None,
+            DispatchSystem::Parallel(system) => Some(&system.access),
+        }
+    }
+
+    pub(crate) fn init(&mut self, world: &mut World, resources: &mut ResourceContainer) {
+        if let DispatchSystem::Exclusive(system) = self {
+            system.init(world, resources);
+        }
+    }
+
+    /// Run with the whole world and resource container to itself
+    pub(crate) fn run_alone(&mut self, world: &mut World, resources: &mut ResourceContainer) {
+        match self {
+            DispatchSystem::Exclusive(system) => system.run(world, resources),
+            DispatchSystem::Parallel(system) => system.run_alone(world, resources),
+        }
+    }
+}
+
+impl From<ParallelSystem> for DispatchSystem {
+    fn from(system: ParallelSystem) -> Self {
+        DispatchSystem::Parallel(system)
+    }
+}
+
+/// Conversion that lets plain functions and `ParallelSystem`s be listed together
+pub trait IntoDispatchSystem {
+    fn into_dispatch_system(self) -> DispatchSystem;
+
+    /// Name used in schedule errors. Defaults to the type name, which is the path of a
+    /// plain system function.
+    fn system_name(&self) -> &'static str {
+        type_name::<Self>()
+    }
+}
+
+impl<S: System + 'static> IntoDispatchSystem for S {
+    fn into_dispatch_system(self) -> DispatchSystem {
+        DispatchSystem::Exclusive(Box::new(self))
+    }
+
+    fn system_name(&self) -> &'static str {
+        self.name()
+    }
+}
+
+impl IntoDispatchSystem for ParallelSystem {
+    fn into_dispatch_system(self) -> DispatchSystem {
+        DispatchSystem::Parallel(self)
+    }
+
+    fn system_name(&self) -> &'static str {
+        self.name
+    }
+}
+
+impl IntoDispatchSystem for DispatchSystem {
+    fn into_dispatch_system(self) -> DispatchSystem {
+        self
+    }
+
+    fn system_name(&self) -> &'static str {
+        match self {
+            DispatchSystem::Exclusive(system) => system.name(),
+            DispatchSystem::Parallel(system) => system.name,
+        }
+    }
+}
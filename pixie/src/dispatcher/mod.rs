@@ -1,21 +1,94 @@
 use hecs::World;
 use crate::resources::ResourceContainer;
+use crate::systems::{update_animation_system, update_camera_system};
 
+pub use access::*;
+pub use schedule::*;
 #[cfg(not(target_arch = "wasm32"))]
 pub use multi_thread::*;
-#[cfg(target_arch = "wasm32")]
 pub use single_thread::*;
 
-#[cfg(target_arch = "wasm32")]
-#[macro_use]
+mod access;
+mod schedule;
+
+// Compiled on every target so native code can opt into sequential runs; its
+// `construct_dispatcher!` is only exported on wasm
+#[cfg_attr(target_arch = "wasm32", macro_use)]
 mod single_thread;
 
 #[cfg(not(target_arch = "wasm32"))]
 #[macro_use]
 mod multi_thread;
 
+/// When a dispatcher applies queued `Commands` between systems
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum CommandFlush {
+    /// Apply after every system, so each system sees the changes of the ones before it
+    #[default]
+    AfterEachSystem,
+    /// Apply only where `apply_commands` appears in the system list
+    SyncPoints,
+}
+
 /// Unified dispatcher trait for running systems
-/// In hecs, systems are just functions, so dispatcher stores function pointers
+/// In hecs, systems are just functions, so dispatcher stores function pointers,
+/// boxed `System`s or `ParallelSystem` descriptions
 pub trait UnifiedDispatcher {
+    /// Called once after `Application::init` so systems can set up their state
+    fn init(&mut self, _world: &mut World, _resources: &mut ResourceContainer) { }
+
     fn run_now(&mut self, world: &mut World, resources: &mut ResourceContainer);
 }
+
+#[cfg(not(target_arch = "wasm32"))]
+type PlatformDispatcher = MultiThreadedDispatcher;
+#[cfg(target_arch = "wasm32")]
+type PlatformDispatcher = SingleThreadedDispatcher;
+
+/// The two system schedules the engine runs
+///
+/// `fixed` runs once per fixed step (physics, gameplay rules), `variable` once per frame
+/// after `Application::update` (camera follow, animation and other presentation work).
+pub struct Schedules {
+    pub fixed: Box<dyn UnifiedDispatcher>,
+    pub variable: Box<dyn UnifiedDispatcher>,
+}
+
+impl Schedules {
+    /// Use `fixed` for fixed steps and `default_variable_schedule` for frames
+    pub fn new(fixed: Box<dyn UnifiedDispatcher>) -> Self {
+        Self {
+            fixed,
+            variable: default_variable_schedule(),
+        }
+    }
+
+    pub fn with_variable(mut self, variable: Box<dyn UnifiedDispatcher>) -> Self {
+        self.variable = variable;
+        self
+    }
+}
+
+impl From<Box<dyn UnifiedDispatcher>> for Schedules {
+    fn from(fixed: Box<dyn UnifiedDispatcher>) -> Self {
+        Schedules::new(fixed)
+    }
+}
+
+/// Per-frame schedule used unless another one is registered: `update_camera` and
+/// `update_animation` in the render-prep stage
+///
+/// Both declare their access, so the native dispatcher runs them as one parallel batch.
+pub fn default_variable_schedule() -> Box<dyn UnifiedDispatcher> {
+    let dispatcher =
+        PlatformDispatcher::new(default_variable_systems(), CommandFlush::AfterEachSystem)
+            .expect("default variable schedule has no ordering constraints");
+    Box::new(dispatcher)
+}
+
+pub(crate) fn default_variable_systems() -> Vec<SystemDescriptor> {
+    vec![
+        update_camera_system().in_stage(SystemStage::RenderPrep),
+        update_animation_system().in_stage(SystemStage::RenderPrep),
+    ]
+}
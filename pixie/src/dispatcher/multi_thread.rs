@@ -1,59 +1,545 @@
-use super::UnifiedDispatcher;
+use super::{
+    build_schedule, CommandFlush, DispatchSystem, ParallelSystem, ScheduleError, ScheduledSystem,
+    SystemAccess, SystemDescriptor, SystemResources, UnifiedDispatcher,
+};
 use hecs::World;
-use crate::resources::ResourceContainer;
-
-// TODO: Multi-threaded dispatcher implementation for hecs
-//
-// The multi-threaded dispatcher is not yet implemented for hecs.
-// This will require manual parallelization using rayon or similar crates.
-//
-// Implementation notes:
-// 1. hecs doesn't have automatic parallel scheduling like specs
-// 2. Need to manually split queries to run in parallel
-// 3. Can use rayon::par_iter() for parallel processing
-// 4. Must ensure no data races (separate mut queries, or use atomic operations)
-//
-// Example approach:
-// - Use rayon to parallelize independent systems
-// - Split entity batches for parallel processing within systems
-// - Carefully manage mutable access to world and resources
-//
-// For now, use the single-threaded dispatcher (automatically selected on non-WASM)
-
-/// System function type - takes world and resources, returns nothing
-pub type SystemFn = fn(&mut World, &mut ResourceContainer);
-
-/// Macro to construct a multi-threaded dispatcher with given system functions
+use instant::Instant;
+use crate::resources::{record_since, Commands, FrameStats, ResourceContainer};
+use crate::systems::apply_commands;
+use std::ops::Range;
+use std::time::Duration;
+
+/// Macro to construct a multi-threaded dispatcher with given systems
 ///
-/// Currently falls back to single-threaded execution.
-/// TODO: Implement proper multi-threaded execution
+/// Entries are plain `SystemFn`s, which run exclusively, or `ParallelSystem`s, which
+/// share a batch with neighbouring systems whose declared access does not conflict.
+/// Either can be configured with the `IntoSystemDescriptor` methods (`label`, `in_stage`,
+/// `before`, `after`, `run_if`). The generated `new_dispatch` panics on an invalid schedule.
+/// Prefix the list with `commands: sync_points;` to apply queued `Commands` only where
+/// `apply_commands` is listed instead of after every batch.
 #[macro_export]
 macro_rules! construct_dispatcher {
-    ( $( $system_fn:expr ),* $(,)? ) => {
+    ( commands: sync_points; $( $system:expr ),* $(,)? ) => {
+        pub fn new_dispatch() -> Box<dyn $crate::dispatcher::UnifiedDispatcher> {
+            let systems = vec![
+                $( $crate::dispatcher::IntoSystemDescriptor::into_descriptor($system) ),*
+            ];
+            let dispatcher = $crate::dispatcher::MultiThreadedDispatcher::new(
+                systems,
+                $crate::dispatcher::CommandFlush::SyncPoints,
+            )
+            .unwrap_or_else(|error| panic!("invalid system schedule: {error}"));
+            Box::new(dispatcher)
+        }
+    };
+    ( $( $system:expr ),* $(,)? ) => {
         pub fn new_dispatch() -> Box<dyn $crate::dispatcher::UnifiedDispatcher> {
-            log::warn!("Multi-threaded dispatcher not yet implemented for hecs. Using single-threaded fallback.");
-            let mut systems: Vec<$crate::dispatcher::SystemFn> = Vec::new();
-            $(
-                systems.push($system_fn);
-            )*
-            Box::new($crate::dispatcher::MultiThreadedDispatcher { systems })
+            let systems = vec![
+                $( $crate::dispatcher::IntoSystemDescriptor::into_descriptor($system) ),*
+            ];
+            let dispatcher = $crate::dispatcher::MultiThreadedDispatcher::new(
+                systems,
+                $crate::dispatcher::CommandFlush::AfterEachSystem,
+            )
+            .unwrap_or_else(|error| panic!("invalid system schedule: {error}"));
+            Box::new(dispatcher)
         }
     };
 }
 
-/// Multi-threaded dispatcher (placeholder - currently single-threaded)
+/// Multi-threaded dispatcher
 ///
-/// TODO: Implement parallel execution using rayon
+/// Runs systems in schedule order, grouped into batches of consecutive parallel systems
+/// of one stage that neither conflict nor are ordered against each other. Each batch
+/// runs on the calling thread and the rayon thread pool; exclusive systems run alone.
+/// Each system's wall-clock time goes to `FrameStats` when the resource exists.
+///
+/// Batches are cut so that the results match `SingleThreadedDispatcher`: a system with
+/// run conditions starts a new batch, so its conditions see everything earlier systems
+/// did, and with `CommandFlush::AfterEachSystem` a system that writes `Commands` ends its
+/// batch, so the commands are applied before the next system runs.
 pub struct MultiThreadedDispatcher {
-    pub systems: Vec<SystemFn>,
+    systems: Vec<ScheduledSystem>,
+    batches: Vec<Range<usize>>,
+    command_flush: CommandFlush,
+}
+
+impl MultiThreadedDispatcher {
+    pub fn new(
+        systems: Vec<SystemDescriptor>,
+        command_flush: CommandFlush,
+    ) -> Result<Self, ScheduleError> {
+        let systems = build_schedule(systems)?;
+        Ok(Self {
+            batches: batches(&systems, command_flush),
+            systems,
+            command_flush,
+        })
+    }
 }
 
 impl UnifiedDispatcher for MultiThreadedDispatcher {
+    fn init(&mut self, world: &mut World, resources: &mut ResourceContainer) {
+        for system in &mut self.systems {
+            system.system.init(world, resources);
+        }
+    }
+
     fn run_now(&mut self, world: &mut World, resources: &mut ResourceContainer) {
-        // TODO: Implement parallel execution
-        // For now, just run sequentially like single-threaded
-        for system_fn in &self.systems {
-            system_fn(world, resources);
+        for batch in &self.batches {
+            let running: Vec<usize> = batch
+                .clone()
+                .filter(|&index| self.systems[index].should_run(world, resources))
+                .collect();
+            match running.as_slice() {
+                [] => continue,
+                [index] => {
+                    let system = &mut self.systems[*index];
+                    let start = Instant::now();
+                    system.system.run_alone(world, resources);
+                    record_since(resources, system.name(), start);
+                }
+                indices => {
+                    let systems: Vec<&ScheduledSystem> =
+                        indices.iter().map(|&index| &self.systems[index]).collect();
+                    run_batch(&systems, world, resources);
+                }
+            }
+            if self.command_flush == CommandFlush::AfterEachSystem {
+                apply_commands(world, resources);
+            }
+        }
+    }
+}
+
+/// Start, duration and trace thread of one system run
+type Span = (Instant, Duration, usize);
+
+fn run_batch(systems: &[&ScheduledSystem], world: &World, resources: &mut ResourceContainer) {
+    let parallel: Vec<&ParallelSystem> = systems
+        .iter()
+        .map(|system| match &system.system {
+            DispatchSystem::Parallel(system) => system,
+            DispatchSystem::Exclusive(_) => unreachable!("exclusive systems run alone"),
+        })
+        .collect();
+    let mut views = SystemResources::for_systems(resources, &parallel).into_iter();
+    let mut first_view = views.next().expect("batches are never empty");
+    let mut spans: Vec<Option<Span>> = vec![None; parallel.len()];
+    let (first_span, other_spans) = spans.split_first_mut().expect("batches are never empty");
+
+    // The calling thread takes the first system, so batches overlap even on a one-thread pool
+    rayon::in_place_scope(|scope| {
+        for ((system, mut view), span) in parallel[1..].iter().zip(views).zip(other_spans) {
+            scope.spawn(move |_| *span = Some(timed(|| system.run(world, &mut view))));
+        }
+        *first_span = Some(timed(|| parallel[0].run(world, &mut first_view)));
+    });
+
+    if let Some(stats) = resources.get_mut::<FrameStats>() {
+        for (system, span) in systems.iter().zip(spans) {
+            if let Some((start, duration, thread)) = span {
+                stats.record_on_thread(system.name(), start, duration, thread);
+            }
+        }
+    }
+}
+
+fn timed(run: impl FnOnce()) -> Span {
+    // Trace row 0 is the calling thread, worker n is drawn on row n + 1
+    let thread = rayon::current_thread_index().map_or(0, |index| index + 1);
+    let start = Instant::now();
+    run();
+    (start, start.elapsed(), thread)
+}
+
+/// Split systems into consecutive runs that can execute together.
+///
+/// Exclusive systems get a batch of their own; a parallel system joins the current
+/// batch unless it conflicts with, is ordered after, or is in a later stage than a
+/// system already in it. Systems with run conditions only start batches, and with
+/// `CommandFlush::AfterEachSystem` nothing joins after a system that writes `Commands`.
+pub(crate) fn batches(
+    systems: &[ScheduledSystem],
+    command_flush: CommandFlush,
+) -> Vec<Range<usize>> {
+    let flushes_commands = |access: &SystemAccess| {
+        command_flush == CommandFlush::AfterEachSystem && access.writes_resource::<Commands>()
+    };
+    let mut batches = Vec::new();
+    let mut start = 0;
+    for (index, system) in systems.iter().enumerate() {
+        let joins_current = match system.access() {
+            None => false,
+            Some(_) if system.has_run_conditions() => false,
+            Some(access) => {
+                system.after.iter().all(|&previous| previous < start)
+                    && systems[start..index].iter().all(|other| {
+                        other.stage() == system.stage()
+                            && other.access().is_some_and(|other| {
+                                !other.conflicts_with(access) && !flushes_commands(other)
+                            })
+                    })
+            }
+        };
+        if !joins_current && start < index {
+            batches.push(start..index);
+            start = index;
+        }
+        if system.access().is_none() {
+            batches.push(index..index + 1);
+            start = index + 1;
+        }
+    }
+    if start < systems.len() {
+        batches.push(start..systems.len());
+    }
+    batches
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dispatcher::{
+        resource_exists, IntoSystemDescriptor, SingleThreadedDispatcher, System, SystemAccess,
+    };
+    use crate::resources::Commands;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::time::{Duration, Instant};
+
+    #[derive(Default)]
+    struct SeenEntities(Vec<usize>);
+
+    fn queue_spawn(_world: &mut World, resources: &mut ResourceContainer) {
+        resources.get_mut::<Commands>().unwrap().spawn((1u32,));
+    }
+
+    fn count_entities(world: &mut World, resources: &mut ResourceContainer) {
+        let count = world.len() as usize;
+        resources.get_mut::<SeenEntities>().unwrap().0.push(count);
+    }
+
+    fn run(dispatcher: &mut MultiThreadedDispatcher) -> Vec<usize> {
+        let mut world = World::new();
+        let mut resources = ResourceContainer::new();
+        resources.insert(Commands::default());
+        resources.insert(SeenEntities::default());
+        dispatcher.run_now(&mut world, &mut resources);
+        resources.remove::<SeenEntities>().unwrap().0
+    }
+
+    #[test]
+    fn flushes_commands_after_each_system_by_default() {
+        let mut dispatcher = MultiThreadedDispatcher::new(
+            vec![
+                queue_spawn.into_descriptor(),
+                count_entities.into_descriptor(),
+            ],
+            CommandFlush::AfterEachSystem,
+        )
+        .unwrap();
+
+        assert_eq!(run(&mut dispatcher), [1]);
+    }
+
+    #[test]
+    fn flushes_commands_only_at_sync_points() {
+        let mut dispatcher = MultiThreadedDispatcher::new(
+            vec![
+                queue_spawn.into_descriptor(),
+                count_entities.into_descriptor(),
+                apply_commands.into_descriptor(),
+                count_entities.into_descriptor(),
+            ],
+            CommandFlush::SyncPoints,
+        )
+        .unwrap();
+
+        assert_eq!(run(&mut dispatcher), [0, 1]);
+    }
+
+    #[test]
+    fn skips_systems_whose_run_conditions_fail() {
+        struct Enabled;
+        let mut dispatcher = MultiThreadedDispatcher::new(
+            vec![
+                queue_spawn.run_if(resource_exists::<Enabled>()),
+                count_entities.into_descriptor(),
+            ],
+            CommandFlush::AfterEachSystem,
+        )
+        .unwrap();
+
+        assert_eq!(run(&mut dispatcher), [0]);
+    }
+
+    /// Keeps its own history of entity counts, starting with the one seen by `init`
+    #[derive(Default)]
+    struct CountingSystem {
+        runs: Vec<usize>,
+    }
+
+    impl System for CountingSystem {
+        fn init(&mut self, world: &mut World, _resources: &mut ResourceContainer) {
+            self.runs.push(world.len() as usize);
+        }
+
+        fn run(&mut self, world: &mut World, resources: &mut ResourceContainer) {
+            self.runs.push(world.len() as usize);
+            let seen = self.runs.clone();
+            resources.get_mut::<SeenEntities>().unwrap().0 = seen;
         }
     }
+
+    #[test]
+    fn runs_stateful_systems_and_closures() {
+        let mut spawned = 0;
+        let spawn_twice = move |_world: &mut World, resources: &mut ResourceContainer| {
+            if spawned < 2 {
+                spawned += 1;
+                resources.get_mut::<Commands>().unwrap().spawn((spawned,));
+            }
+        };
+        let mut dispatcher = MultiThreadedDispatcher::new(
+            vec![
+                spawn_twice.into_descriptor(),
+                CountingSystem::default().into_descriptor(),
+            ],
+            CommandFlush::AfterEachSystem,
+        )
+        .unwrap();
+        let mut world = World::new();
+        let mut resources = ResourceContainer::new();
+        resources.insert(Commands::default());
+        resources.insert(SeenEntities::default());
+
+        dispatcher.init(&mut world, &mut resources);
+        for _ in 0..3 {
+            dispatcher.run_now(&mut world, &mut resources);
+        }
+
+        assert_eq!(resources.get::<SeenEntities>().unwrap().0, [0, 1, 2, 2]);
+    }
+
+    struct Position;
+    struct Velocity;
+
+    /// Tracks how many systems run at once
+    #[derive(Default)]
+    struct Overlap {
+        active: AtomicUsize,
+        max_active: AtomicUsize,
+    }
+
+    impl Overlap {
+        fn enter(&self) {
+            let active = self.active.fetch_add(1, Ordering::SeqCst) + 1;
+            self.max_active.fetch_max(active, Ordering::SeqCst);
+        }
+
+        fn exit(&self) {
+            self.active.fetch_sub(1, Ordering::SeqCst);
+        }
+    }
+
+    fn hold_briefly(_world: &World, resources: &mut SystemResources) {
+        let overlap = resources.get::<Overlap>().unwrap();
+        overlap.enter();
+        std::thread::sleep(Duration::from_millis(20));
+        overlap.exit();
+    }
+
+    fn wait_for_partner(_world: &World, resources: &mut SystemResources) {
+        let overlap = resources.get::<Overlap>().unwrap();
+        overlap.enter();
+        let deadline = Instant::now() + Duration::from_secs(5);
+        while overlap.max_active.load(Ordering::SeqCst) < 2 && Instant::now() < deadline {
+            std::thread::yield_now();
+        }
+        overlap.exit();
+    }
+
+    fn overlap_of(systems: Vec<SystemDescriptor>) -> usize {
+        let mut dispatcher =
+            MultiThreadedDispatcher::new(systems, CommandFlush::AfterEachSystem).unwrap();
+        let mut world = World::new();
+        let mut resources = ResourceContainer::new();
+        resources.insert(Commands::default());
+        resources.insert(Overlap::default());
+        dispatcher.run_now(&mut world, &mut resources);
+        let overlap = resources.get::<Overlap>().unwrap();
+        overlap.max_active.load(Ordering::SeqCst)
+    }
+
+    fn system(run: crate::dispatcher::ParallelSystemFn, access: SystemAccess) -> SystemDescriptor {
+        ParallelSystem::new("test", run, access.read_resource::<Overlap>()).into_descriptor()
+    }
+
+    #[test]
+    fn runs_disjoint_systems_at_the_same_time() {
+        let max_active = overlap_of(vec![
+            system(wait_for_partner, SystemAccess::new().write::<Position>()),
+            system(wait_for_partner, SystemAccess::new().write::<Velocity>()),
+        ]);
+
+        assert_eq!(max_active, 2);
+    }
+
+    #[test]
+    fn never_overlaps_conflicting_systems() {
+        let max_active = overlap_of(vec![
+            system(hold_briefly, SystemAccess::new().write::<Position>()),
+            system(hold_briefly, SystemAccess::new().read::<Position>()),
+            system(hold_briefly, SystemAccess::new().write::<Position>()),
+        ]);
+
+        assert_eq!(max_active, 1);
+    }
+
+    #[test]
+    fn splits_batches_at_conflicts_constraints_and_exclusive_systems() {
+        let systems = build_schedule(vec![
+            system(hold_briefly, SystemAccess::new().read::<Position>()),
+            system(hold_briefly, SystemAccess::new().read::<Position>()),
+            system(hold_briefly, SystemAccess::new().write::<Position>()),
+            system(hold_briefly, SystemAccess::new().write::<Velocity>()),
+            count_entities.into_descriptor(),
+            system(
+                hold_briefly,
+                SystemAccess::new().write_resource::<Commands>(),
+            ),
+            system(
+                hold_briefly,
+                SystemAccess::new().write_resource::<Commands>(),
+            ),
+            system(hold_briefly, SystemAccess::new()).label("first"),
+            system(hold_briefly, SystemAccess::new()).after("first"),
+        ])
+        .unwrap();
+
+        assert_eq!(
+            batches(&systems, CommandFlush::SyncPoints),
+            [0..2, 2..4, 4..5, 5..6, 6..8, 8..9]
+        );
+        assert_eq!(
+            batches(&systems, CommandFlush::AfterEachSystem)[3..],
+            [5..6, 6..7, 7..8, 8..9]
+        );
+    }
+
+    #[test]
+    fn batches_the_default_variable_schedule() {
+        let systems = build_schedule(crate::dispatcher::default_variable_systems()).unwrap();
+
+        assert_eq!(batches(&systems, CommandFlush::AfterEachSystem), vec![0..2]);
+    }
+
+    struct Marker;
+
+    #[derive(Default)]
+    struct Flag(bool);
+
+    #[derive(Default)]
+    struct FlagRuns(usize);
+
+    fn queue_marker(_world: &World, resources: &mut SystemResources) {
+        resources.get_mut::<Commands>().unwrap().spawn((Marker,));
+    }
+
+    fn record_markers(world: &World, resources: &mut SystemResources) {
+        let count = world.query::<&Marker>().iter().count();
+        resources.get_mut::<SeenEntities>().unwrap().0.push(count);
+    }
+
+    fn raise_flag(_world: &World, resources: &mut SystemResources) {
+        resources.get_mut::<Flag>().unwrap().0 = true;
+    }
+
+    fn count_flag_runs(_world: &World, resources: &mut SystemResources) {
+        resources.get_mut::<FlagRuns>().unwrap().0 += 1;
+    }
+
+    /// Pairs that would share a batch if only their declared access counted
+    fn flush_and_condition_systems() -> Vec<SystemDescriptor> {
+        let flag_raised =
+            |_world: &World, resources: &ResourceContainer| resources.get::<Flag>().unwrap().0;
+        vec![
+            ParallelSystem::new(
+                "queue_marker",
+                queue_marker,
+                SystemAccess::new().write_resource::<Commands>(),
+            )
+            .into_descriptor(),
+            ParallelSystem::new(
+                "record_markers",
+                record_markers,
+                SystemAccess::new()
+                    .read::<Marker>()
+                    .write_resource::<SeenEntities>(),
+            )
+            .into_descriptor(),
+            ParallelSystem::new(
+                "raise_flag",
+                raise_flag,
+                SystemAccess::new().write_resource::<Flag>(),
+            )
+            .into_descriptor(),
+            ParallelSystem::new(
+                "count_flag_runs",
+                count_flag_runs,
+                SystemAccess::new().write_resource::<FlagRuns>(),
+            )
+            .run_if(flag_raised),
+        ]
+    }
+
+    #[test]
+    fn matches_the_single_threaded_dispatcher() {
+        fn outcome(dispatcher: &mut dyn UnifiedDispatcher) -> (Vec<usize>, usize) {
+            let mut world = World::new();
+            let mut resources = ResourceContainer::new();
+            resources.insert(Commands::default());
+            resources.insert(SeenEntities::default());
+            resources.insert(Flag::default());
+            resources.insert(FlagRuns::default());
+            for _ in 0..2 {
+                dispatcher.run_now(&mut world, &mut resources);
+            }
+            let runs = resources.get::<FlagRuns>().unwrap().0;
+            (resources.remove::<SeenEntities>().unwrap().0, runs)
+        }
+        let mut single = SingleThreadedDispatcher::new(
+            flush_and_condition_systems(),
+            CommandFlush::AfterEachSystem,
+        )
+        .unwrap();
+        let mut multi = MultiThreadedDispatcher::new(
+            flush_and_condition_systems(),
+            CommandFlush::AfterEachSystem,
+        )
+        .unwrap();
+
+        let expected = outcome(&mut single);
+        assert_eq!(expected, (vec![1, 2], 2));
+        assert_eq!(outcome(&mut multi), expected);
+    }
+
+    #[test]
+    #[should_panic(expected = "did not declare write access to resource")]
+    fn panics_on_undeclared_resource_access() {
+        fn sneaky(_world: &World, resources: &mut SystemResources) {
+            resources.get_mut::<Commands>();
+        }
+        let mut dispatcher = MultiThreadedDispatcher::new(
+            vec![system(sneaky, SystemAccess::new())],
+            CommandFlush::AfterEachSystem,
+        )
+        .unwrap();
+        let mut world = World::new();
+        let mut resources = ResourceContainer::new();
+        resources.insert(Commands::default());
+        resources.insert(Overlap::default());
+
+        dispatcher.run_now(&mut world, &mut resources);
+    }
 }
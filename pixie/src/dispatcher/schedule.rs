@@ -0,0 +1,428 @@
+use std::collections::{BTreeSet, HashMap};
+use std::fmt;
+
+use hecs::World;
+
+use crate::resources::ResourceContainer;
+
+use super::{DispatchSystem, IntoDispatchSystem, SystemAccess};
+
+/// Checked before a system runs; the system is skipped when it returns false
+pub type RunCondition = Box<dyn Fn(&World, &ResourceContainer) -> bool>;
+
+/// Named phases of one dispatcher run, executed in declaration order
+///
+/// Systems listed without a stage go to `Physics`. Inside a stage, systems keep their
+/// list order unless `before`/`after` constraints say otherwise.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum SystemStage {
+    PrePhysics,
+    #[default]
+    Physics,
+    PostPhysics,
+    RenderPrep,
+}
+
+/// A system plus its label, stage, ordering constraints and run conditions
+///
+/// Built with the `IntoSystemDescriptor` methods, e.g.
+/// `update_player.label("player").in_stage(SystemStage::Physics).after("input")`.
+pub struct SystemDescriptor {
+    system: DispatchSystem,
+    name: &'static str,
+    label: Option<&'static str>,
+    stage: SystemStage,
+    before: Vec<&'static str>,
+    after: Vec<&'static str>,
+    conditions: Vec<RunCondition>,
+}
+
+impl SystemDescriptor {
+    pub fn new(system: impl IntoDispatchSystem) -> Self {
+        let name = system.system_name();
+        Self {
+            system: system.into_dispatch_system(),
+            name,
+            label: None,
+            stage: SystemStage::default(),
+            before: Vec::new(),
+            after: Vec::new(),
+            conditions: Vec::new(),
+        }
+    }
+
+    /// Label if set, otherwise the system name
+    fn display_name(&self) -> &'static str {
+        self.label.unwrap_or(self.name)
+    }
+}
+
+/// Builder methods available on anything the dispatcher macros accept
+pub trait IntoSystemDescriptor: Sized {
+    fn into_descriptor(self) -> SystemDescriptor;
+
+    /// Name other systems use in `before` and `after`
+    fn label(self, label: &'static str) -> SystemDescriptor {
+        let mut descriptor = self.into_descriptor();
+        descriptor.label = Some(label);
+        descriptor
+    }
+
+    fn in_stage(self, stage: SystemStage) -> SystemDescriptor {
+        let mut descriptor = self.into_descriptor();
+        descriptor.stage = stage;
+        descriptor
+    }
+
+    /// Run before the system labeled `label`
+    fn before(self, label: &'static str) -> SystemDescriptor {
+        let mut descriptor = self.into_descriptor();
+        descriptor.before.push(label);
+        descriptor
+    }
+
+    /// Run after the system labeled `label`
+    fn after(self, label: &'static str) -> SystemDescriptor {
+        let mut descriptor = self.into_descriptor();
+        descriptor.after.push(label);
+        descriptor
+    }
+
+    /// Skip the system unless `condition` holds. Several conditions must all hold.
+    fn run_if(
+        self,
+        condition: impl Fn(&World, &ResourceContainer) -> bool + 'static,
+    ) -> SystemDescriptor {
+        let mut descriptor = self.into_descriptor();
+        descriptor.conditions.push(Box::new(condition));
+        descriptor
+    }
+}
+
+impl<T: IntoDispatchSystem> IntoSystemDescriptor for T {
+    fn into_descriptor(self) -> SystemDescriptor {
+        SystemDescriptor::new(self)
+    }
+}
+
+impl IntoSystemDescriptor for SystemDescriptor {
+    fn into_descriptor(self) -> SystemDescriptor {
+        self
+    }
+}
+
+/// Run condition that holds while resource `T` exists and equals `value`
+pub fn resource_equals<T: PartialEq + 'static>(
+    value: T,
+) -> impl Fn(&World, &ResourceContainer) -> bool + 'static {
+    move |_world, resources| resources.get::<T>() == Some(&value)
+}
+
+/// Run condition that holds while resource `T` exists
+pub fn resource_exists<T: 'static>() -> impl Fn(&World, &ResourceContainer) -> bool + 'static {
+    |_world, resources| resources.contains::<T>()
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ScheduleError {
+    DuplicateLabel(&'static str),
+    UnknownLabel {
+        system: &'static str,
+        label: &'static str,
+    },
+    /// An ordering constraint points from a later stage to an earlier one
+    StageOrder {
+        before: &'static str,
+        after: &'static str,
+    },
+    /// Systems whose constraints form a loop, in constraint order
+    Cycle(Vec<&'static str>),
+}
+
+impl fmt::Display for ScheduleError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ScheduleError::DuplicateLabel(label) => {
+                write!(f, "label `{label}` is used by more than one system")
+            }
+            ScheduleError::UnknownLabel { system, label } => {
+                write!(
+                    f,
+                    "system `{system}` is ordered against unknown label `{label}`"
+                )
+            }
+            ScheduleError::StageOrder { before, after } => write!(
+                f,
+                "system `{before}` must run before `{after}` but is in a later stage"
+            ),
+            ScheduleError::Cycle(systems) => {
+                write!(f, "system ordering cycle: {}", systems.join(" -> "))?;
+                if let Some(first) = systems.first() {
+                    write!(f, " -> {first}")?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+impl std::error::Error for ScheduleError {}
+
+/// A system placed in its final position by `build_schedule`
+pub struct ScheduledSystem {
+    pub(crate) system: DispatchSystem,
+    name: &'static str,
+    stage: SystemStage,
+    conditions: Vec<RunCondition>,
+    /// Positions of the systems this one was explicitly ordered after
+    #[cfg_attr(target_arch = "wasm32", allow(dead_code))]
+    pub(crate) after: Vec<usize>,
+}
+
+impl ScheduledSystem {
+    /// Label if set, otherwise the system name. Timings in `FrameStats` use it too.
+    pub fn name(&self) -> &'static str {
+        self.name
+    }
+
+    pub fn stage(&self) -> SystemStage {
+        self.stage
+    }
+
+    #[cfg_attr(target_arch = "wasm32", allow(dead_code))]
+    pub(crate) fn access(&self) -> Option<&SystemAccess> {
+        self.system.access()
+    }
+
+    #[cfg_attr(target_arch = "wasm32", allow(dead_code))]
+    pub(crate) fn has_run_conditions(&self) -> bool {
+        !self.conditions.is_empty()
+    }
+
+    pub(crate) fn should_run(&self, world: &World, resources: &ResourceContainer) -> bool {
+        self.conditions
+            .iter()
+            .all(|condition| condition(world, resources))
+    }
+}
+
+/// Order systems by stage, then by their `before`/`after` constraints, then by list order.
+pub fn build_schedule(
+    descriptors: Vec<SystemDescriptor>,
+) -> Result<Vec<ScheduledSystem>, ScheduleError> {
+    let mut labels = HashMap::new();
+    for (index, descriptor) in descriptors.iter().enumerate() {
+        if let Some(label) = descriptor.label {
+            if labels.insert(label, index).is_some() {
+                return Err(ScheduleError::DuplicateLabel(label));
+            }
+        }
+    }
+
+    let lookup = |system: &SystemDescriptor, label: &'static str| {
+        labels
+            .get(label)
+            .copied()
+            .ok_or(ScheduleError::UnknownLabel {
+                system: system.display_name(),
+                label,
+            })
+    };
+
+    // predecessors[i] holds every system that has to run before system i
+    let mut predecessors = vec![Vec::new(); descriptors.len()];
+    for (index, descriptor) in descriptors.iter().enumerate() {
+        for label in &descriptor.after {
+            predecessors[index].push(lookup(descriptor, label)?);
+        }
+        for label in &descriptor.before {
+            predecessors[lookup(descriptor, label)?].push(index);
+        }
+    }
+
+    for (index, before) in predecessors.iter().enumerate() {
+        for &other in before {
+            if descriptors[other].stage > descriptors[index].stage {
+                return Err(ScheduleError::StageOrder {
+                    before: descriptors[other].display_name(),
+                    after: descriptors[index].display_name(),
+                });
+            }
+        }
+    }
+
+    let order = sort(&descriptors, &predecessors)?;
+
+    let mut positions = vec![0; descriptors.len()];
+    for (position, &index) in order.iter().enumerate() {
+        positions[index] = position;
+    }
+    let mut slots: Vec<Option<SystemDescriptor>> = descriptors.into_iter().map(Some).collect();
+    Ok(order
+        .into_iter()
+        .map(|index| {
+            let descriptor = slots[index].take().expect("each system is placed once");
+            ScheduledSystem {
+                name: descriptor.display_name(),
+                system: descriptor.system,
+                stage: descriptor.stage,
+                conditions: descriptor.conditions,
+                after: predecessors[index]
+                    .iter()
+                    .map(|&other| positions[other])
+                    .collect(),
+            }
+        })
+        .collect())
+}
+
+/// Kahn's algorithm, always taking the earliest stage and list position that is ready.
+fn sort(
+    descriptors: &[SystemDescriptor],
+    predecessors: &[Vec<usize>],
+) -> Result<Vec<usize>, ScheduleError> {
+    let mut waiting_on: Vec<usize> = predecessors.iter().map(Vec::len).collect();
+    let mut successors = vec![Vec::new(); descriptors.len()];
+    for (index, before) in predecessors.iter().enumerate() {
+        for &other in before {
+            successors[other].push(index);
+        }
+    }
+
+    let mut ready: BTreeSet<(SystemStage, usize)> = (0..descriptors.len())
+        .filter(|&index| waiting_on[index] == 0)
+        .map(|index| (descriptors[index].stage, index))
+        .collect();
+    let mut order = Vec::with_capacity(descriptors.len());
+    while let Some((_, index)) = ready.pop_first() {
+        order.push(index);
+        for &next in &successors[index] {
+            waiting_on[next] -= 1;
+            if waiting_on[next] == 0 {
+                ready.insert((descriptors[next].stage, next));
+            }
+        }
+    }
+
+    if order.len() == descriptors.len() {
+        return Ok(order);
+    }
+
+    // Every unsorted system still waits on another unsorted one, so walking those
+    // predecessors from any of them must revisit a system.
+    let mut path = vec![(0..descriptors.len())
+        .find(|&index| waiting_on[index] > 0)
+        .expect("an unsorted system exists")];
+    loop {
+        let current = *path.last().expect("path is never empty");
+        let previous = predecessors[current]
+            .iter()
+            .copied()
+            .find(|&other| waiting_on[other] > 0)
+            .expect("unsorted systems wait on an unsorted system");
+        if let Some(start) = path.iter().position(|&index| index == previous) {
+            let mut cycle: Vec<&'static str> = path[start..]
+                .iter()
+                .map(|&index| descriptors[index].display_name())
+                .collect();
+            // The walk followed predecessors, so reverse it into run order
+            cycle.reverse();
+            return Err(ScheduleError::Cycle(cycle));
+        }
+        path.push(previous);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn noop(_world: &mut World, _resources: &mut ResourceContainer) {}
+
+    fn stages(schedule: &[ScheduledSystem]) -> Vec<SystemStage> {
+        schedule.iter().map(ScheduledSystem::stage).collect()
+    }
+
+    #[test]
+    fn orders_by_stage_then_constraints_then_list() {
+        let schedule = build_schedule(vec![
+            noop.label("render").in_stage(SystemStage::RenderPrep),
+            noop.label("late").after("early"),
+            noop.label("early"),
+            noop.label("input").in_stage(SystemStage::PrePhysics),
+            noop.label("collide")
+                .in_stage(SystemStage::PostPhysics)
+                .after("input"),
+        ])
+        .unwrap();
+
+        assert_eq!(
+            stages(&schedule),
+            [
+                SystemStage::PrePhysics,
+                SystemStage::Physics,
+                SystemStage::Physics,
+                SystemStage::PostPhysics,
+                SystemStage::RenderPrep,
+            ]
+        );
+        // "early" moved ahead of "late", which now records it as a predecessor
+        assert_eq!(schedule[2].after, [1]);
+    }
+
+    #[test]
+    fn reports_cycles_and_bad_labels() {
+        let cycle = build_schedule(vec![
+            noop.label("a").after("c"),
+            noop.label("b").after("a"),
+            noop.label("c").after("b"),
+            noop.label("free"),
+        ]);
+        match cycle {
+            Err(ScheduleError::Cycle(systems)) => {
+                assert_eq!(systems.len(), 3);
+                assert!(!systems.contains(&"free"));
+            }
+            other => panic!("expected a cycle, got {:?}", other.err()),
+        }
+
+        assert_eq!(
+            build_schedule(vec![noop.label("a"), noop.label("a")]).err(),
+            Some(ScheduleError::DuplicateLabel("a"))
+        );
+        assert_eq!(
+            build_schedule(vec![noop.label("a").before("missing")]).err(),
+            Some(ScheduleError::UnknownLabel {
+                system: "a",
+                label: "missing"
+            })
+        );
+        assert_eq!(
+            build_schedule(vec![
+                noop.label("a").in_stage(SystemStage::PostPhysics),
+                noop.label("b").after("a"),
+            ])
+            .err(),
+            Some(ScheduleError::StageOrder {
+                before: "a",
+                after: "b"
+            })
+        );
+    }
+
+    #[test]
+    fn run_conditions_read_resources() {
+        let schedule = build_schedule(vec![noop
+            .run_if(resource_equals(3u32))
+            .run_if(resource_exists::<u32>())])
+        .unwrap();
+        let world = World::new();
+        let mut resources = ResourceContainer::new();
+
+        assert!(!schedule[0].should_run(&world, &resources));
+        resources.insert(2u32);
+        assert!(!schedule[0].should_run(&world, &resources));
+        resources.insert(3u32);
+        assert!(schedule[0].should_run(&world, &resources));
+    }
+}
@@ -1,9 +1,11 @@
-use super::UnifiedDispatcher;
+use super::{
+    build_schedule, CommandFlush, ScheduleError, ScheduledSystem, SystemDescriptor,
+    UnifiedDispatcher,
+};
 use hecs::World;
-use crate::resources::ResourceContainer;
-
-/// System function type - takes world and resources, returns nothing
-pub type SystemFn = fn(&mut World, &mut ResourceContainer);
+use instant::Instant;
+use crate::resources::{record_since, ResourceContainer};
+use crate::systems::apply_commands;
 
 /// Macro to construct a single-threaded dispatcher with given system functions
 ///
@@ -15,28 +17,98 @@ pub type SystemFn = fn(&mut World, &mut ResourceContainer);
 ///     collision_system
 /// );
 /// ```
+///
+/// Queued `Commands` are applied after every system. To apply them only at chosen
+/// points, prefix the list and place `apply_commands` where the flushes should happen:
+/// ```ignore
+/// construct_dispatcher!(
+///     commands: sync_points;
+///     spawn_bullets,
+///     move_bullets,
+///     apply_commands,
+///     collision_system
+/// );
+/// ```
+///
+/// Stages, ordering constraints and run conditions come from `IntoSystemDescriptor`:
+/// ```ignore
+/// construct_dispatcher!(
+///     read_input.label("input").in_stage(SystemStage::PrePhysics),
+///     move_player.after("input").run_if(resource_equals(Stage::Run)),
+///     update_animation.in_stage(SystemStage::RenderPrep)
+/// );
+/// ```
+#[cfg(target_arch = "wasm32")]
 #[macro_export]
 macro_rules! construct_dispatcher {
+    ( commands: sync_points; $( $system_fn:expr ),* $(,)? ) => {
+        pub fn new_dispatch() -> Box<dyn $crate::dispatcher::UnifiedDispatcher> {
+            let systems = vec![
+                $( $crate::dispatcher::IntoSystemDescriptor::into_descriptor($system_fn) ),*
+            ];
+            let dispatcher = $crate::dispatcher::SingleThreadedDispatcher::new(
+                systems,
+                $crate::dispatcher::CommandFlush::SyncPoints,
+            )
+            .unwrap_or_else(|error| panic!("invalid system schedule: {error}"));
+            Box::new(dispatcher)
+        }
+    };
     ( $( $system_fn:expr ),* $(,)? ) => {
         pub fn new_dispatch() -> Box<dyn $crate::dispatcher::UnifiedDispatcher> {
-            let mut systems: Vec<$crate::dispatcher::SystemFn> = Vec::new();
-            $(
-                systems.push($system_fn);
-            )*
-            Box::new($crate::dispatcher::SingleThreadedDispatcher { systems })
+            let systems = vec![
+                $( $crate::dispatcher::IntoSystemDescriptor::into_descriptor($system_fn) ),*
+            ];
+            let dispatcher = $crate::dispatcher::SingleThreadedDispatcher::new(
+                systems,
+                $crate::dispatcher::CommandFlush::AfterEachSystem,
+            )
+            .unwrap_or_else(|error| panic!("invalid system schedule: {error}"));
+            Box::new(dispatcher)
         }
     };
 }
 
 /// Single-threaded dispatcher for WASM and simple use cases
+///
+/// Runs every system in schedule order, including `ParallelSystem`s, and records each
+/// one's wall-clock time in `FrameStats` when the resource exists. Available on native
+/// too, where it runs the same schedule as `MultiThreadedDispatcher` with the same results.
 pub struct SingleThreadedDispatcher {
-    pub systems: Vec<SystemFn>,
+    systems: Vec<ScheduledSystem>,
+    command_flush: CommandFlush,
+}
+
+impl SingleThreadedDispatcher {
+    pub fn new(
+        systems: Vec<SystemDescriptor>,
+        command_flush: CommandFlush,
+    ) -> Result<Self, ScheduleError> {
+        Ok(Self {
+            systems: build_schedule(systems)?,
+            command_flush,
+        })
+    }
 }
 
 impl UnifiedDispatcher for SingleThreadedDispatcher {
+    fn init(&mut self, world: &mut World, resources: &mut ResourceContainer) {
+        for system in &mut self.systems {
+            system.system.init(world, resources);
+        }
+    }
+
     fn run_now(&mut self, world: &mut World, resources: &mut ResourceContainer) {
-        for system_fn in &self.systems {
-            system_fn(world, resources);
+        for system in &mut self.systems {
+            if !system.should_run(world, resources) {
+                continue;
+            }
+            let start = Instant::now();
+            system.system.run_alone(world, resources);
+            record_since(resources, system.name(), start);
+            if self.command_flush == CommandFlush::AfterEachSystem {
+                apply_commands(world, resources);
+            }
         }
     }
 }
@@ -1,4 +1,3 @@
-use hecs::World;
 use instant::Instant;
 use std::fmt;
 use std::sync::Arc;
@@ -8,10 +7,11 @@ use winit::{
 };
 
 use crate::application::Application;
-use crate::dispatcher::UnifiedDispatcher;
+use crate::dispatcher::Schedules;
 use crate::renderer::*;
-use crate::resources::{DeltaTime, ResourceContainer};
-use crate::{TextureAtlasAsset, TextureAtlasRegistry};
+use crate::resources::{AtlasLoaded, FrameStats, ResourceContainer, WindowResized};
+use crate::simulation::Simulation;
+use crate::{EngineConfig, TextureAtlasAsset, TextureAtlasRegistry};
 #[cfg(not(target_arch = "wasm32"))]
 use pollster::block_on;
 
@@ -19,8 +19,10 @@ use pollster::block_on;
 pub enum EngineError {
     Atlas(crate::AtlasError),
     EventLoop(winit::error::EventLoopError),
+    Recording(crate::RecordingError),
     Render(RenderError),
     Startup(String),
+    Trace(std::io::Error),
     Window(winit::error::OsError),
 }
 
@@ -29,8 +31,10 @@ impl fmt::Display for EngineError {
         match self {
             Self::Atlas(error) => error.fmt(formatter),
             Self::EventLoop(error) => write!(formatter, "event loop failed: {error}"),
+            Self::Recording(error) => error.fmt(formatter),
             Self::Render(error) => error.fmt(formatter),
             Self::Startup(message) => formatter.write_str(message),
+            Self::Trace(error) => write!(formatter, "failed to write chrome trace: {error}"),
             Self::Window(error) => write!(formatter, "window creation failed: {error}"),
         }
     }
@@ -41,8 +45,10 @@ impl std::error::Error for EngineError {
         match self {
             Self::Atlas(error) => Some(error),
             Self::EventLoop(error) => Some(error),
+            Self::Recording(error) => Some(error),
             Self::Render(error) => Some(error),
             Self::Startup(_) => None,
+            Self::Trace(error) => Some(error),
             Self::Window(error) => Some(error),
         }
     }
@@ -54,6 +60,12 @@ impl From<crate::AtlasError> for EngineError {
     }
 }
 
+impl From<crate::RecordingError> for EngineError {
+    fn from(value: crate::RecordingError) -> Self {
+        Self::Recording(value)
+    }
+}
+
 impl From<winit::error::EventLoopError> for EngineError {
     fn from(value: winit::error::EventLoopError) -> Self {
         Self::EventLoop(value)
@@ -73,22 +85,15 @@ impl From<winit::error::OsError> for EngineError {
 }
 
 pub struct Engine<A: Application> {
-    app: A,
-    world: World,
-    resources: ResourceContainer,
+    simulation: Simulation<A>,
     rs: Option<RenderState>,
     render_extractor: RenderWorldExtractor,
-    dispatcher: Box<dyn UnifiedDispatcher + 'static>,
     window: Option<Arc<Window>>,
     size: PhysicalSize<u32>,
     prev_time: Instant,
-    accumulator: f32,
-    fixed_dt: f32,
 
     // bootstrap config for ActiveEventLoop window creation
-    title: String,
-    initial_width: u32,
-    initial_height: u32,
+    config: EngineConfig,
     fatal_error: Option<EngineError>,
     #[cfg(target_arch = "wasm32")]
     wasm_pending_rs:
@@ -99,8 +104,9 @@ impl<A: Application> ApplicationHandler<()> for Engine<A> {
     fn resumed(&mut self, event_loop: &winit::event_loop::ActiveEventLoop) {
         if self.window.is_none() {
             let window_attributes = Window::default_attributes()
-                .with_title(self.title.clone())
-                .with_inner_size(LogicalSize::new(self.initial_width, self.initial_height));
+                .with_title(self.config.title.clone())
+                .with_inner_size(LogicalSize::new(self.config.width, self.config.height))
+                .with_resizable(self.config.resizable);
 
             let window = match event_loop.create_window(window_attributes) {
                 Ok(window) => Arc::new(window),
@@ -142,11 +148,14 @@ impl<A: Application> ApplicationHandler<()> for Engine<A> {
                     Rc::new(RefCell::new(None));
                 let pending_clone = Rc::clone(&pending);
                 let window_clone = window.clone();
-                let w = self.initial_width;
-                let h = self.initial_height;
+                let w = self.config.width;
+                let h = self.config.height;
+                let present_mode = self.config.present_mode;
+                let clear_color = self.config.clear_color;
                 wasm_bindgen_futures::spawn_local(async move {
                     let result = async {
-                        let mut rs = RenderState::new(window_clone, w, h).await?;
+                        let mut rs = RenderState::new(window_clone, w, h, present_mode).await?;
+                        rs.set_clear_color(clear_color);
                         rs.init_resources().await?;
                         Ok(rs)
                     }
@@ -155,15 +164,16 @@ impl<A: Application> ApplicationHandler<()> for Engine<A> {
                 });
 
                 self.wasm_pending_rs = Some(pending);
-                self.size = PhysicalSize::new(self.initial_width, self.initial_height);
+                self.size = PhysicalSize::new(self.config.width, self.config.height);
             }
 
             #[cfg(not(target_arch = "wasm32"))]
             {
                 let mut rs = match block_on(RenderState::new(
                     window.clone(),
-                    self.initial_width,
-                    self.initial_height,
+                    self.config.width,
+                    self.config.height,
+                    self.config.present_mode,
                 )) {
                     Ok(rs) => rs,
                     Err(error) => {
@@ -171,11 +181,14 @@ impl<A: Application> ApplicationHandler<()> for Engine<A> {
                         return;
                     }
                 };
+                rs.set_clear_color(self.config.clear_color);
                 if let Err(error) = block_on(rs.init_resources()) {
                     self.exit_with_error(event_loop, error.into());
                     return;
                 }
-                if let Err(error) = Self::upload_pending_atlases(&mut self.resources, &mut rs) {
+                if let Err(error) =
+                    Self::upload_pending_atlases(&mut self.simulation.resources, &mut rs)
+                {
                     self.exit_with_error(event_loop, error.into());
                     return;
                 }
@@ -204,20 +217,17 @@ impl<A: Application> ApplicationHandler<()> for Engine<A> {
     ) {
         if let Some(window) = &self.window {
             if window_id == window.id() {
+                // Replays drop live input before the application sees it
+                if self.simulation.is_replaying() && self.simulation.exit_requested(&event) {
+                    event_loop.exit();
+                    return;
+                }
                 // Try application input first
-                if !self
-                    .app
-                    .handle_input(&mut self.world, &mut self.resources, &event)
-                {
+                if !self.simulation.handle_input(&event) {
                     match event {
                         WindowEvent::CloseRequested => event_loop.exit(),
-                        WindowEvent::KeyboardInput {
-                            event: key_event, ..
-                        } if key_event.physical_key
-                            == winit::keyboard::PhysicalKey::Code(
-                                winit::keyboard::KeyCode::Escape,
-                            )
-                            && key_event.state == ElementState::Pressed =>
+                        WindowEvent::KeyboardInput { .. } | WindowEvent::MouseInput { .. }
+                            if self.simulation.exit_requested(&event) =>
                         {
                             event_loop.exit();
                         }
@@ -246,7 +256,7 @@ impl<A: Application> ApplicationHandler<()> for Engine<A> {
                                             }
                                         };
                                         if let Err(error) = Self::upload_pending_atlases(
-                                            &mut self.resources,
+                                            &mut self.simulation.resources,
                                             &mut rs,
                                         ) {
                                             self.exit_with_error(event_loop, error.into());
@@ -256,34 +266,9 @@ impl<A: Application> ApplicationHandler<()> for Engine<A> {
                                     }
                                 }
                             }
-                            let mut elapsed_time =
-                                self.prev_time.elapsed().as_millis() as f32 / 1000.0;
+                            let elapsed_time = self.prev_time.elapsed().as_millis() as f32 / 1000.0;
                             self.prev_time = Instant::now();
-
-                            if elapsed_time > 0.2 {
-                                elapsed_time = 0.2;
-                            }
-
-                            // Accumulate time for fixed-step updates
-                            self.accumulator += elapsed_time;
-                            while self.accumulator >= self.fixed_dt {
-                                // Check if app wants to run fixed updates
-                                if self.app.should_run_fixed(&self.world, &self.resources) {
-                                    // Provide fixed dt to systems and step dispatcher
-                                    self.resources.insert(DeltaTime(self.fixed_dt));
-                                    self.app.fixed_update(
-                                        &mut self.world,
-                                        &mut self.resources,
-                                        self.fixed_dt,
-                                    );
-                                    self.dispatcher
-                                        .run_now(&mut self.world, &mut self.resources);
-                                }
-                                self.accumulator -= self.fixed_dt;
-                            }
-
-                            // Variable-step update for non-physics
-                            self.update(elapsed_time);
+                            self.simulation.advance(elapsed_time);
 
                             match self.render() {
                                 Ok(_) => {}
@@ -318,9 +303,61 @@ impl<A: Application> ApplicationHandler<()> for Engine<A> {
             }
         }
     }
+
+    fn exiting(&mut self, _event_loop: &winit::event_loop::ActiveEventLoop) {
+        self.save_recording();
+        #[cfg(not(target_arch = "wasm32"))]
+        self.write_chrome_trace();
+    }
 }
 
 impl<A: Application> Engine<A> {
+    fn save_recording(&mut self) {
+        let (Some(recording), Some(path)) = (
+            self.simulation.recording(),
+            self.simulation.recording_path(),
+        ) else {
+            return;
+        };
+        match recording.save(path) {
+            Ok(()) => log::info!(
+                "saved input recording of {} frames to {}",
+                recording.frame_count(),
+                path.display()
+            ),
+            Err(error) => {
+                log::error!("{error}");
+                self.fatal_error.get_or_insert(error.into());
+            }
+        }
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    fn write_chrome_trace(&mut self) {
+        let (Some(path), Some(stats)) = (
+            &self.config.chrome_trace,
+            self.simulation.resources.get::<FrameStats>(),
+        ) else {
+            return;
+        };
+        match stats.write_chrome_trace(path) {
+            Ok(()) => {
+                log::info!("wrote chrome trace to {}", path.display());
+                if stats.dropped_trace_events() > 0 {
+                    log::warn!(
+                        "chrome trace dropped its {} oldest spans",
+                        stats.dropped_trace_events()
+                    );
+                }
+            }
+            Err(error) => {
+                let error = EngineError::Trace(error);
+                log::error!("{error}");
+                self.fatal_error.get_or_insert(error);
+            }
+        }
+    }
+
     fn exit_with_error(
         &mut self,
         event_loop: &winit::event_loop::ActiveEventLoop,
@@ -331,117 +368,54 @@ impl<A: Application> Engine<A> {
         event_loop.exit();
     }
 
-    #[cfg(not(target_arch = "wasm32"))]
-    pub async fn new<T: Into<String>>(
-        mut app: A,
-        title: T,
-        width: u32,
-        height: u32,
-        dispatcher: Box<dyn UnifiedDispatcher + 'static>,
-    ) -> (Self, EventLoop<()>) {
-        let event_loop = EventLoop::new().unwrap();
-
-        let size = PhysicalSize::new(width, height);
-        let prev_time = Instant::now();
-        let accumulator = 0.0f32;
-        let fixed_dt = 1.0 / 60.0; // 60 Hz physics
-
-        // Initialize World and Resources
-        let mut world = World::new();
-        let mut resources = ResourceContainer::new();
-
-        // Insert engine-managed resources first
-        let aspect_ratio = width as f32 / height as f32;
-        resources.insert(crate::resources::Camera::init_orthographic(
-            20.0,
-            aspect_ratio,
-        ));
-        resources.insert(DeltaTime(0.0));
-        resources.insert(TextureAtlasRegistry::default());
-
-        // Initialize application (can adjust camera via resources)
-        app.init(&mut world, &mut resources);
-
-        let engine = Self {
-            app,
-            world,
-            resources,
-            rs: None,
-            render_extractor: RenderWorldExtractor::with_capacity(8, 128),
-            dispatcher,
-            window: None,
-            size,
-            prev_time,
-            accumulator,
-            fixed_dt,
-            title: title.into(),
-            initial_width: width,
-            initial_height: height,
-            fatal_error: None,
-            #[cfg(target_arch = "wasm32")]
-            wasm_pending_rs: None,
-        };
+    /// Create the engine and its event loop without running it
+    ///
+    /// `schedules` is either a full `Schedules` or a single dispatcher, which becomes the
+    /// fixed schedule next to `default_variable_schedule`.
+    pub async fn new(
+        app: A,
+        config: EngineConfig,
+        texture_atlases: Vec<TextureAtlasAsset>,
+        schedules: impl Into<Schedules>,
+    ) -> Result<(Self, EventLoop<()>), EngineError> {
+        let event_loop = EventLoop::new()?;
+        let simulation = Simulation::new(app, &config, texture_atlases, schedules.into())?;
 
-        (engine, event_loop)
+        Ok((Self::from_simulation(simulation, config), event_loop))
     }
 
     /// Start the engine - creates and runs in one go
-    pub async fn start<T: Into<String>>(
+    pub async fn start(
         app: A,
-        title: T,
-        width: u32,
-        height: u32,
+        config: EngineConfig,
         texture_atlases: Vec<TextureAtlasAsset>,
-        dispatcher: Box<dyn UnifiedDispatcher + 'static>,
+        schedules: impl Into<Schedules>,
     ) -> Result<(), EngineError> {
-        let event_loop = EventLoop::new()?;
+        let (mut engine, event_loop) = Self::new(app, config, texture_atlases, schedules).await?;
 
-        // Initialize World and Resources
-        let mut world = World::new();
-        let mut resources = ResourceContainer::new();
-
-        // Insert engine-managed resources first
-        let aspect_ratio = width as f32 / height as f32;
-        resources.insert(crate::resources::Camera::init_orthographic(
-            20.0,
-            aspect_ratio,
-        ));
-        resources.insert(DeltaTime(0.0));
-        let mut atlas_registry = TextureAtlasRegistry::default();
-        for asset in texture_atlases {
-            atlas_registry.register(asset)?;
+        event_loop.run_app(&mut engine)?;
+        if let Some(error) = engine.fatal_error {
+            return Err(error);
         }
-        resources.insert(atlas_registry);
-
-        // Initialize application (can adjust camera via resources)
-        let mut app = app;
-        app.init(&mut world, &mut resources);
+        Ok(())
+    }
 
-        let mut engine = Self {
-            app,
-            world,
-            resources,
+    fn from_simulation(simulation: Simulation<A>, config: EngineConfig) -> Self {
+        Self {
+            simulation,
             rs: None,
-            render_extractor: RenderWorldExtractor::with_capacity(8, 128),
-            dispatcher,
+            render_extractor: RenderWorldExtractor::with_capacity(
+                config.sprite_atlas_capacity,
+                config.text_capacity,
+            ),
             window: None,
-            size: PhysicalSize::new(width, height),
+            size: PhysicalSize::new(config.width, config.height),
             prev_time: Instant::now(),
-            accumulator: 0.0,
-            fixed_dt: 1.0 / 60.0,
-            title: title.into(),
-            initial_width: width,
-            initial_height: height,
+            config,
             fatal_error: None,
             #[cfg(target_arch = "wasm32")]
             wasm_pending_rs: None,
-        };
-
-        event_loop.run_app(&mut engine)?;
-        if let Some(error) = engine.fatal_error {
-            return Err(error);
         }
-        Ok(())
     }
 
     /// Run the engine with the provided event loop
@@ -454,11 +428,10 @@ impl<A: Application> Engine<A> {
         if let Some(rs) = &mut self.rs {
             rs.resize(new_size);
         }
-    }
-
-    fn update(&mut self, dt: f32) {
-        self.resources.insert(DeltaTime(dt));
-        self.app.update(&mut self.world, &mut self.resources, dt);
+        self.simulation.resources.send_event(WindowResized {
+            width: new_size.width,
+            height: new_size.height,
+        });
     }
 
     fn upload_pending_atlases(
@@ -479,6 +452,7 @@ impl<A: Application> Engine<A> {
                 .get_mut::<TextureAtlasRegistry>()
                 .expect("TextureAtlasRegistry resource not found")
                 .mark_loaded(asset.id().clone());
+            resources.send_event(AtlasLoaded(asset.id().clone()));
         }
         Ok(())
     }
@@ -488,10 +462,15 @@ impl<A: Application> Engine<A> {
             Some(rs) => rs,
             None => return Ok(()),
         };
-        Self::upload_pending_atlases(&mut self.resources, rs)?;
+        Self::upload_pending_atlases(&mut self.simulation.resources, rs)?;
+        let extract_start = Instant::now();
         let frame = self
             .render_extractor
-            .extract(&self.world, &self.resources)?;
-        rs.render_frame(&frame)
+            .extract(&self.simulation.world, &self.simulation.resources)?;
+        let mut stats = self.simulation.resources.get_mut::<FrameStats>();
+        if let Some(stats) = stats.as_deref_mut() {
+            stats.record("render/extract", extract_start, extract_start.elapsed());
+        }
+        rs.render_frame(&frame, stats)
     }
 }
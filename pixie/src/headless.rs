@@ -0,0 +1,684 @@
+use hecs::World;
+use winit::event::WindowEvent;
+
+use crate::application::Application;
+use crate::dispatcher::Schedules;
+use crate::recording::InputRecording;
+use crate::renderer::{RenderFrame, RenderWorldExtractor};
+use crate::resources::{AtlasLoaded, InputEvent, ResourceContainer};
+use crate::simulation::Simulation;
+use crate::{AtlasError, EngineConfig, EngineError, TextureAtlasAsset, TextureAtlasRegistry};
+
+/// Window-less runner that drives an `Application` with a synthetic clock.
+///
+/// Uses the same fixed-step accumulator as `Engine`, but time only advances when
+/// `step` is called. Rendering stops at `RenderWorldExtractor`, so no GPU is needed.
+pub struct HeadlessRunner<A: Application> {
+    simulation: Simulation<A>,
+    render_extractor: RenderWorldExtractor,
+    frame_count: u64,
+}
+
+impl<A: Application> HeadlessRunner<A> {
+    pub fn new(
+        app: A,
+        config: &EngineConfig,
+        texture_atlases: Vec<TextureAtlasAsset>,
+        schedules: impl Into<Schedules>,
+    ) -> Result<Self, EngineError> {
+        let simulation = Simulation::new(app, config, texture_atlases, schedules.into())?;
+
+        Ok(Self {
+            simulation,
+            render_extractor: RenderWorldExtractor::with_capacity(
+                config.sprite_atlas_capacity,
+                config.text_capacity,
+            ),
+            frame_count: 0,
+        })
+    }
+
+    /// Advances one frame as if `elapsed` seconds passed since the previous one.
+    pub fn step(&mut self, elapsed: f32) {
+        self.simulation.advance(elapsed);
+        self.frame_count += 1;
+    }
+
+    /// Advances `frames` frames of `elapsed` seconds each.
+    pub fn run_frames(&mut self, frames: usize, elapsed: f32) {
+        for _ in 0..frames {
+            self.step(elapsed);
+        }
+    }
+
+    /// Advances exactly one fixed step worth of time.
+    pub fn step_fixed(&mut self) {
+        self.step(self.simulation.fixed_dt());
+    }
+
+    /// Forwards a window event to the application, returning whether it was consumed.
+    pub fn handle_input(&mut self, event: &WindowEvent) -> bool {
+        self.simulation.handle_input(event)
+    }
+
+    /// Injects an input event as if the window had delivered it between frames.
+    ///
+    /// Captured in record mode and ignored while a replay is running.
+    pub fn send_input(&mut self, event: InputEvent) {
+        self.simulation.send_input(event);
+    }
+
+    /// The recording captured so far when running with `InputMode::Record`.
+    pub fn recording(&self) -> Option<&InputRecording> {
+        self.simulation.recording()
+    }
+
+    /// Whether recorded frames are still being replayed.
+    pub fn is_replaying(&self) -> bool {
+        self.simulation.is_replaying()
+    }
+
+    /// Builds the render input the windowed engine would draw for the current state.
+    ///
+    /// Registered atlases are treated as loaded since there is no GPU upload step.
+    pub fn extract_frame(&mut self) -> Result<RenderFrame<'_>, AtlasError> {
+        let registry = self
+            .simulation
+            .resources
+            .get_mut::<TextureAtlasRegistry>()
+            .expect("TextureAtlasRegistry resource not found");
+        if let Some(error) = registry.take_error() {
+            return Err(error);
+        }
+        let pending = registry.take_pending();
+        for asset in &pending {
+            registry.mark_loaded(asset.id().clone());
+        }
+        for asset in pending {
+            self.simulation
+                .resources
+                .send_event(AtlasLoaded(asset.id().clone()));
+        }
+
+        self.render_extractor
+            .extract(&self.simulation.world, &self.simulation.resources)
+    }
+
+    pub fn frame_count(&self) -> u64 {
+        self.frame_count
+    }
+
+    pub fn app(&self) -> &A {
+        &self.simulation.app
+    }
+
+    pub fn app_mut(&mut self) -> &mut A {
+        &mut self.simulation.app
+    }
+
+    pub fn world(&self) -> &World {
+        &self.simulation.world
+    }
+
+    pub fn world_mut(&mut self) -> &mut World {
+        &mut self.simulation.world
+    }
+
+    pub fn resources(&self) -> &ResourceContainer {
+        &self.simulation.resources
+    }
+
+    pub fn resources_mut(&mut self) -> &mut ResourceContainer {
+        &mut self.simulation.resources
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::components::{Force, Name, RigidBody, Sprite, Tags, Transform, Velocity};
+    use crate::dispatcher::UnifiedDispatcher;
+    use crate::resources::{
+        ActionMap, Binding, Commands, EventReader, Events, FrameStats, GameRng, Input,
+        InterpolationAlpha, Time, WorldNames, EXIT_ACTION,
+    };
+    use crate::systems::{apply_gravity, update_physics, Gravity};
+    use crate::{rollback, InputMode, RecordEntry, SnapshotHistory};
+    use rand::Rng;
+    use winit::event::MouseButton;
+    use winit::keyboard::KeyCode;
+
+    #[derive(Default)]
+    struct CountingApp {
+        fixed_updates: usize,
+        updates: usize,
+        last_dt: f32,
+        run_fixed: bool,
+        fixed_jumps: usize,
+    }
+
+    impl Application for CountingApp {
+        fn init(&mut self, world: &mut World, _resources: &mut ResourceContainer) {
+            self.run_fixed = true;
+            world.spawn((
+                Transform::new([1.0, 2.0, 0.0], [1.0, 1.0]),
+                Sprite {
+                    atlas: "main".into(),
+                    uv: [0.0, 1.0, 0.0, 1.0],
+                },
+            ));
+        }
+
+        fn update(&mut self, _world: &mut World, _resources: &mut ResourceContainer, dt: f32) {
+            self.updates += 1;
+            self.last_dt = dt;
+        }
+
+        fn fixed_update(
+            &mut self,
+            _world: &mut World,
+            resources: &mut ResourceContainer,
+            _fixed_dt: f32,
+        ) {
+            self.fixed_updates += 1;
+            if resources
+                .get::<Input>()
+                .is_some_and(|input| input.key_just_pressed(KeyCode::Space))
+            {
+                self.fixed_jumps += 1;
+            }
+        }
+
+        fn handle_input(
+            &mut self,
+            _world: &mut World,
+            _resources: &mut ResourceContainer,
+            _event: &WindowEvent,
+        ) -> bool {
+            false
+        }
+
+        fn should_run_fixed(&self, _world: &World, _resources: &ResourceContainer) -> bool {
+            self.run_fixed
+        }
+    }
+
+    struct RecordingDispatcher;
+
+    #[derive(Default)]
+    struct DispatchCount(usize);
+
+    impl UnifiedDispatcher for RecordingDispatcher {
+        fn run_now(&mut self, world: &mut World, resources: &mut ResourceContainer) {
+            let dt = resources.get::<Time>().unwrap().delta();
+            for (_, transform) in world.query_mut::<&mut Transform>() {
+                transform.position[0] += dt;
+            }
+            if !resources.contains::<DispatchCount>() {
+                resources.insert(DispatchCount::default());
+            }
+            let count = resources.get_mut::<DispatchCount>().unwrap();
+            count.0 += 1;
+            let event = DispatchCount(count.0);
+            resources.send_event(event);
+        }
+    }
+
+    fn runner() -> HeadlessRunner<CountingApp> {
+        runner_with_config(&EngineConfig::new("test", 800, 600))
+    }
+
+    fn runner_with_config(config: &EngineConfig) -> HeadlessRunner<CountingApp> {
+        HeadlessRunner::new(
+            CountingApp::default(),
+            config,
+            vec![TextureAtlasAsset::from_static("main", b"unused")],
+            Schedules::new(Box::new(RecordingDispatcher)),
+        )
+        .unwrap()
+    }
+
+    #[derive(Default)]
+    struct FrameCount(usize);
+
+    struct FrameDispatcher;
+
+    impl UnifiedDispatcher for FrameDispatcher {
+        fn run_now(&mut self, _world: &mut World, resources: &mut ResourceContainer) {
+            if !resources.contains::<FrameCount>() {
+                resources.insert(FrameCount::default());
+            }
+            resources.get_mut::<FrameCount>().unwrap().0 += 1;
+        }
+    }
+
+    #[test]
+    fn accumulates_fixed_steps_across_frames() {
+        let mut runner = runner();
+
+        // Two 1/120s frames add up to one 60 Hz fixed step.
+        runner.run_frames(2, 1.0 / 120.0 + 1.0e-6);
+
+        assert_eq!(runner.frame_count(), 2);
+        assert_eq!(runner.app().updates, 2);
+        assert_eq!(runner.app().fixed_updates, 1);
+        assert_eq!(runner.resources().get::<DispatchCount>().unwrap().0, 1);
+    }
+
+    #[test]
+    fn times_systems_and_frames_in_frame_stats() {
+        let config = EngineConfig::new("test", 800, 600).with_chrome_trace("unused.json");
+        let mut runner = runner_with_config(&config);
+
+        runner.run_frames(3, 0.01);
+
+        let stats = runner.resources().get::<FrameStats>().unwrap();
+        let animation = std::any::type_name_of_val(&crate::systems::update_animation);
+        assert_eq!(stats.summary(animation).unwrap().samples, 3);
+        assert_eq!(stats.summary("simulation").unwrap().samples, 3);
+        assert!(stats.is_tracing());
+        assert!(stats.chrome_trace_json().contains(r#""name":"simulation""#));
+    }
+
+    #[test]
+    fn runs_variable_schedule_every_frame() {
+        let schedules =
+            Schedules::new(Box::new(RecordingDispatcher)).with_variable(Box::new(FrameDispatcher));
+        let mut runner = HeadlessRunner::new(
+            CountingApp::default(),
+            &EngineConfig::new("test", 800, 600),
+            Vec::new(),
+            schedules,
+        )
+        .unwrap();
+
+        runner.run_frames(3, 1.0 / 120.0 + 1.0e-6);
+
+        assert_eq!(runner.resources().get::<FrameCount>().unwrap().0, 3);
+        assert_eq!(runner.resources().get::<DispatchCount>().unwrap().0, 1);
+    }
+
+    #[test]
+    fn clamps_long_frames() {
+        let mut runner = runner();
+
+        runner.step(5.0);
+
+        assert_eq!(runner.app().last_dt, 0.2);
+        assert_eq!(runner.app().fixed_updates, 12);
+    }
+
+    #[test]
+    fn drops_fixed_steps_beyond_per_frame_limit() {
+        let config = EngineConfig::new("test", 800, 600).with_max_fixed_steps_per_frame(3);
+        let mut runner = runner_with_config(&config);
+
+        runner.step(0.1);
+        assert_eq!(runner.app().fixed_updates, 3);
+
+        // The dropped backlog must not spill into the next frame.
+        runner.step(0.0);
+        assert_eq!(runner.app().fixed_updates, 3);
+    }
+
+    #[test]
+    fn time_scale_slows_and_pauses_fixed_steps() {
+        let mut runner = runner();
+        runner
+            .resources_mut()
+            .get_mut::<Time>()
+            .unwrap()
+            .set_scale(0.5);
+
+        runner.run_frames(4, 1.0 / 60.0);
+        assert_eq!(runner.app().fixed_updates, 2);
+        assert!((runner.app().last_dt - 0.5 / 60.0).abs() < 1.0e-6);
+
+        runner.resources_mut().get_mut::<Time>().unwrap().pause();
+        runner.run_frames(4, 1.0 / 60.0);
+        assert_eq!(runner.app().fixed_updates, 2);
+        assert_eq!(runner.app().last_dt, 0.0);
+
+        let time = runner.resources().get::<Time>().unwrap();
+        assert_eq!(time.frame_count(), 8);
+        assert_eq!(time.fixed_tick(), 2);
+        assert_eq!(time.unscaled_delta(), 1.0 / 60.0);
+        assert!((time.elapsed() - 2.0 / 60.0).abs() < 1.0e-6);
+    }
+
+    #[test]
+    fn publishes_leftover_accumulator_as_interpolation_alpha() {
+        let mut runner = runner();
+
+        runner.step(1.5 / 60.0);
+
+        let alpha = runner.resources().get::<InterpolationAlpha>().unwrap().0;
+        assert!((alpha - 0.5).abs() < 1.0e-4);
+    }
+
+    #[test]
+    fn fixed_update_sees_press_once_even_after_frame_without_fixed_step() {
+        let mut runner = runner();
+        runner
+            .resources_mut()
+            .get_mut::<Input>()
+            .unwrap()
+            .press_key(KeyCode::Space);
+
+        // The first frame is too short for a fixed step.
+        runner.step(0.25 / 60.0);
+        assert_eq!(runner.app().fixed_updates, 0);
+
+        runner.run_frames(3, 1.0 / 60.0);
+        assert_eq!(runner.app().fixed_updates, 3);
+        assert_eq!(runner.app().fixed_jumps, 1);
+    }
+
+    #[test]
+    fn skips_fixed_steps_when_application_pauses() {
+        let mut runner = runner();
+        runner.app_mut().run_fixed = false;
+
+        runner.run_frames(10, 1.0 / 60.0);
+
+        assert_eq!(runner.app().fixed_updates, 0);
+        assert_eq!(runner.app().updates, 10);
+        assert!(!runner.resources().contains::<DispatchCount>());
+    }
+
+    #[test]
+    fn fixed_step_events_reach_frame_update_once() {
+        let mut runner = runner();
+        let mut reader = EventReader::<DispatchCount>::default();
+
+        // Three fixed steps in one frame, each sending an event.
+        runner.step(3.0 / 60.0 + 1.0e-4);
+        let events = runner.resources().get::<Events<DispatchCount>>().unwrap();
+        let counts = events
+            .read(&mut reader)
+            .map(|count| count.0)
+            .collect::<Vec<_>>();
+        assert_eq!(counts, [1, 2, 3]);
+
+        // Read events are dropped after the next frame.
+        runner.step(1.0 / 60.0);
+        let events = runner.resources().get::<Events<DispatchCount>>().unwrap();
+        let counts = events
+            .read(&mut reader)
+            .map(|count| count.0)
+            .collect::<Vec<_>>();
+        assert_eq!(counts, [4]);
+        assert!(events.iter().all(|count| count.0 > 3));
+    }
+
+    #[test]
+    fn reports_loaded_atlases_as_events() {
+        let mut runner = runner();
+        runner.extract_frame().unwrap();
+
+        let loaded = runner.resources().get::<Events<AtlasLoaded>>().unwrap();
+        let ids = loaded
+            .iter()
+            .map(|event| event.0.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(ids, ["main"]);
+    }
+
+    #[test]
+    fn extracts_render_frame_without_gpu() {
+        let mut runner = runner();
+        runner.step_fixed();
+
+        let frame = runner.extract_frame().unwrap();
+        let batches = frame.sprite_batches().collect::<Vec<_>>();
+
+        assert_eq!(batches.len(), 1);
+        assert_eq!(batches[0].0.as_str(), "main");
+        assert!((batches[0].1[0].position[0] - (1.0 + 1.0 / 60.0)).abs() < 1.0e-6);
+    }
+
+    #[test]
+    fn keeps_world_names_in_sync_with_commands_and_clear() {
+        let mut runner = runner();
+        let probe = {
+            let world = runner.world();
+            let mut commands = runner.resources().fetch_mut::<Commands>();
+            commands.spawn_reserved(world, (Name::new("probe"), Tags::new(["hud"])))
+        };
+        runner.step(1.0 / 60.0);
+        let names = runner.resources().get::<WorldNames>().unwrap();
+        assert_eq!(names.find(runner.world(), "probe"), Some(probe));
+        assert_eq!(names.tagged(runner.world(), "hud"), [probe]);
+
+        runner.resources().fetch_mut::<Commands>().despawn(probe);
+        runner.step(1.0 / 60.0);
+        assert_eq!(
+            runner
+                .resources()
+                .get::<WorldNames>()
+                .unwrap()
+                .find(runner.world(), "probe"),
+            None
+        );
+
+        runner.world_mut().spawn((Name::new("probe"),));
+        runner.world_mut().clear();
+        runner.step(1.0 / 60.0);
+        let names = runner.resources().get::<WorldNames>().unwrap();
+        assert_eq!(names.find(runner.world(), "probe"), None);
+        assert!(names.tagged(runner.world(), "hud").is_empty());
+    }
+
+    #[derive(Default)]
+    struct ScatterApp;
+
+    impl Application for ScatterApp {
+        fn init(&mut self, world: &mut World, resources: &mut ResourceContainer) {
+            resources.insert(Gravity::default());
+            for _ in 0..4 {
+                spawn_random_body(world, resources);
+            }
+        }
+
+        fn update(&mut self, _world: &mut World, _resources: &mut ResourceContainer, _dt: f32) {}
+
+        fn fixed_update(
+            &mut self,
+            world: &mut World,
+            resources: &mut ResourceContainer,
+            _fixed_dt: f32,
+        ) {
+            let input = resources.get::<Input>().unwrap();
+            let jump = input.key_just_pressed(KeyCode::Space);
+            let spawn = input.mouse_just_pressed(MouseButton::Left);
+            if jump {
+                for (_, velocity) in world.query_mut::<&mut Velocity>() {
+                    velocity.linear[1] += 5.0;
+                }
+            }
+            if spawn {
+                spawn_random_body(world, resources);
+            }
+        }
+
+        fn handle_input(
+            &mut self,
+            _world: &mut World,
+            _resources: &mut ResourceContainer,
+            _event: &WindowEvent,
+        ) -> bool {
+            false
+        }
+    }
+
+    fn spawn_random_body(world: &mut World, resources: &mut ResourceContainer) {
+        let rng = resources.get_mut::<GameRng>().unwrap();
+        let position = [rng.gen_range(-5.0..5.0), rng.gen_range(0.0..10.0), 0.0];
+        let velocity = Velocity {
+            linear: [rng.gen_range(-1.0..1.0), 0.0],
+            angular: rng.gen_range(-1.0..1.0),
+        };
+        world.spawn((
+            Transform::new(position, [1.0, 1.0]),
+            velocity,
+            Force::default(),
+            RigidBody::default(),
+        ));
+    }
+
+    struct PhysicsDispatcher;
+
+    impl UnifiedDispatcher for PhysicsDispatcher {
+        fn run_now(&mut self, world: &mut World, resources: &mut ResourceContainer) {
+            apply_gravity(world, resources);
+            update_physics(world, resources);
+        }
+    }
+
+    fn scatter_runner(config: &EngineConfig) -> HeadlessRunner<ScatterApp> {
+        let schedules = Schedules::new(Box::new(PhysicsDispatcher));
+        HeadlessRunner::new(ScatterApp, config, Vec::new(), schedules).unwrap()
+    }
+
+    fn body_bits(world: &World) -> Vec<[u32; 6]> {
+        let mut query = world.query::<(&Transform, &Velocity)>();
+        let mut bodies = query
+            .iter()
+            .map(|(entity, (transform, velocity))| {
+                (
+                    entity.id(),
+                    [
+                        transform.position[0].to_bits(),
+                        transform.position[1].to_bits(),
+                        transform.rotation.to_bits(),
+                        velocity.linear[0].to_bits(),
+                        velocity.linear[1].to_bits(),
+                        velocity.angular.to_bits(),
+                    ],
+                )
+            })
+            .collect::<Vec<_>>();
+        bodies.sort_by_key(|(id, _)| *id);
+        bodies.into_iter().map(|(_, bits)| bits).collect()
+    }
+
+    #[test]
+    fn replay_reproduces_recorded_world_bit_for_bit() {
+        let config = EngineConfig::new("test", 800, 600)
+            .with_rng_seed(11)
+            .with_input_mode(InputMode::Record { path: None });
+        let mut recorder = scatter_runner(&config);
+        let frame_times = [0.007, 0.021, 0.016, 0.05, 0.003, 0.033, 0.016, 0.012];
+        for (frame, elapsed) in frame_times.into_iter().enumerate() {
+            match frame {
+                1 => recorder.send_input(InputEvent::KeyPressed(KeyCode::Space)),
+                2 => recorder.send_input(InputEvent::KeyReleased(KeyCode::Space)),
+                4 => recorder.send_input(InputEvent::MousePressed(MouseButton::Left)),
+                5 => recorder.send_input(InputEvent::MouseReleased(MouseButton::Left)),
+                _ => {}
+            }
+            recorder.step(elapsed);
+        }
+        let recording =
+            InputRecording::from_bytes(&recorder.recording().unwrap().to_bytes()).unwrap();
+        assert_eq!(recording.frame_count(), frame_times.len());
+
+        // Live input and wall-clock time are ignored while the recording plays back.
+        let config = EngineConfig::new("test", 800, 600)
+            .with_rng_seed(99)
+            .with_input_mode(InputMode::Replay(recording));
+        let mut replayer = scatter_runner(&config);
+        replayer.send_input(InputEvent::KeyPressed(KeyCode::Space));
+        replayer.run_frames(frame_times.len(), 0.1);
+
+        assert_eq!(replayer.world().len(), 5);
+        assert_eq!(body_bits(replayer.world()), body_bits(recorder.world()));
+
+        replayer.step(0.1);
+        assert!(!replayer.is_replaying());
+    }
+
+    #[test]
+    fn exit_binding_is_read_from_raw_events_while_replaying() {
+        let config = EngineConfig::new("test", 800, 600)
+            .with_input_mode(InputMode::Replay(InputRecording::new(0)));
+        let mut runner = scatter_runner(&config);
+        assert!(runner.is_replaying());
+        let simulation = &runner.simulation;
+        assert!(simulation.is_exit_press(InputEvent::KeyPressed(KeyCode::Escape)));
+        assert!(!simulation.is_exit_press(InputEvent::KeyReleased(KeyCode::Escape)));
+
+        runner
+            .resources_mut()
+            .get_mut::<ActionMap>()
+            .unwrap()
+            .rebind(EXIT_ACTION, vec![Binding::Mouse(MouseButton::Right)]);
+        let simulation = &runner.simulation;
+        assert!(!simulation.is_exit_press(InputEvent::KeyPressed(KeyCode::Escape)));
+        assert!(simulation.is_exit_press(InputEvent::MousePressed(MouseButton::Right)));
+    }
+
+    #[test]
+    fn rollback_resimulates_to_the_same_state() {
+        let config = EngineConfig::new("test", 800, 600)
+            .with_rng_seed(5)
+            .with_snapshot_history(16);
+        let mut runner = scatter_runner(&config);
+        for _ in 0..10 {
+            runner.step_fixed();
+        }
+        let at_tick_10 = body_bits(runner.world());
+
+        let simulation = &mut runner.simulation;
+        spawn_random_body(&mut simulation.world, &mut simulation.resources);
+        for _ in 0..5 {
+            runner.step_fixed();
+        }
+        let ahead = body_bits(runner.world());
+        assert_eq!(
+            runner.resources().fetch::<SnapshotHistory>().latest_tick(),
+            Some(15)
+        );
+
+        let simulation = &mut runner.simulation;
+        rollback(&mut simulation.world, &mut simulation.resources, 10).unwrap();
+        assert_eq!(runner.world().len(), 4);
+        assert_eq!(body_bits(runner.world()), at_tick_10);
+        assert_eq!(runner.resources().fetch::<Time>().fixed_tick(), 10);
+        assert_eq!(
+            runner.resources().fetch::<SnapshotHistory>().latest_tick(),
+            Some(10)
+        );
+
+        let simulation = &mut runner.simulation;
+        spawn_random_body(&mut simulation.world, &mut simulation.resources);
+        for _ in 0..5 {
+            runner.step_fixed();
+        }
+        assert_eq!(body_bits(runner.world()), ahead);
+    }
+
+    #[test]
+    fn records_inputs_at_the_rolled_back_tick() {
+        let config = EngineConfig::new("test", 800, 600)
+            .with_rng_seed(5)
+            .with_snapshot_history(16)
+            .with_input_mode(InputMode::Record { path: None });
+        let mut runner = scatter_runner(&config);
+        for _ in 0..10 {
+            runner.step_fixed();
+        }
+
+        let simulation = &mut runner.simulation;
+        rollback(&mut simulation.world, &mut simulation.resources, 4).unwrap();
+        runner.send_input(InputEvent::KeyPressed(KeyCode::Space));
+
+        assert_eq!(
+            runner.recording().unwrap().entries().last(),
+            Some(&RecordEntry::Input {
+                tick: 4,
+                event: InputEvent::KeyPressed(KeyCode::Space),
+            })
+        );
+    }
+}
@@ -0,0 +1,62 @@
+use std::collections::HashMap;
+
+use hecs::{Entity, NoSuchEntity, World};
+
+use crate::components::Parent;
+
+/// Every entity below `entity` in the `Parent` hierarchy, parents before their children
+///
+/// Follows `Parent` components rather than `Children`, so it also sees children added
+/// since `propagate_transforms` last ran.
+pub fn descendants(world: &World, entity: Entity) -> Vec<Entity> {
+    let mut children: HashMap<Entity, Vec<Entity>> = HashMap::new();
+    for (child, parent) in world.query::<&Parent>().iter() {
+        children.entry(parent.0).or_default().push(child);
+    }
+
+    // `remove` visits each parent once, so `Parent` cycles end the walk
+    let mut found = Vec::new();
+    let mut pending = vec![entity];
+    while let Some(parent) = pending.pop() {
+        if let Some(list) = children.remove(&parent) {
+            found.extend(&list);
+            pending.extend(list);
+        }
+    }
+    found.retain(|descendant| *descendant != entity);
+    found
+}
+
+/// Despawn `entity` together with all of its descendants
+pub fn despawn_recursive(world: &mut World, entity: Entity) -> Result<(), NoSuchEntity> {
+    let descendants = descendants(world, entity);
+    world.despawn(entity)?;
+    for descendant in descendants {
+        let _ = world.despawn(descendant);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn despawns_descendants_but_not_siblings() {
+        let mut world = World::new();
+        let root = world.spawn((0u32,));
+        let child = world.spawn((1u32, Parent(root)));
+        let grandchild = world.spawn((2u32, Parent(child)));
+        let sibling = world.spawn((3u32,));
+        let nephew = world.spawn((4u32, Parent(sibling)));
+
+        assert_eq!(descendants(&world, root), [child, grandchild]);
+        despawn_recursive(&mut world, root).unwrap();
+
+        assert!(!world.contains(child));
+        assert!(!world.contains(grandchild));
+        assert!(world.contains(sibling));
+        assert!(world.contains(nephew));
+        assert!(despawn_recursive(&mut world, root).is_err());
+    }
+}
@@ -1,17 +1,39 @@
 pub mod application;
 pub mod atlas;
+pub mod change_detection;
 pub mod components;
+pub mod config;
 pub mod dispatcher;
 pub mod engine;
+pub mod headless;
+pub mod hierarchy;
+pub mod pool;
+pub mod prefab;
+pub mod recording;
 pub mod renderer;
 pub mod resources;
+pub mod save;
+#[doc(hidden)]
+pub mod scenes;
+pub mod snapshot;
 pub mod systems;
 
+mod simulation;
+
 // Re-export commonly used items
 pub use application::*;
 pub use atlas::*;
+pub use change_detection::*;
 pub use components::*;
+pub use config::*;
 pub use dispatcher::*;
 pub use engine::*;
+pub use headless::*;
+pub use hierarchy::*;
+pub use pool::*;
+pub use prefab::*;
+pub use recording::*;
 pub use resources::*;
+pub use save::*;
+pub use snapshot::*;
 pub use systems::*;
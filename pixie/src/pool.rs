@@ -0,0 +1,232 @@
+use std::collections::HashMap;
+
+use hecs::{DynamicBundle, Entity, NoSuchEntity, World};
+use serde::{Deserialize, Serialize};
+
+use crate::components::{Disabled, PreviousTransform};
+use crate::hierarchy::{descendants, despawn_recursive};
+use crate::save::{EntityMap, MapEntities};
+
+/// Deactivated entities of one kind, kept to be reused instead of respawned
+///
+/// Released entities get the `Disabled` marker, together with their descendants, so the
+/// built-in systems and the renderer skip them until `acquire` hands them out again.
+/// Releasing more than `max_size` entities despawns the extras.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct EntityPool {
+    free: Vec<Entity>,
+    max_size: usize,
+}
+
+impl EntityPool {
+    /// Pool that keeps any number of released entities
+    pub fn new() -> Self {
+        Self::with_max_size(usize::MAX)
+    }
+
+    /// Pool that keeps at most `max_size` released entities
+    pub fn with_max_size(max_size: usize) -> Self {
+        Self {
+            free: Vec::new(),
+            max_size,
+        }
+    }
+
+    pub fn max_size(&self) -> usize {
+        self.max_size
+    }
+
+    /// Number of disabled entities waiting to be reused
+    pub fn free_len(&self) -> usize {
+        self.free.len()
+    }
+
+    /// Spawn entities with `spawn` and disable them until the pool holds `count`
+    ///
+    /// `count` is capped at `max_size`.
+    pub fn prewarm(
+        &mut self,
+        world: &mut World,
+        count: usize,
+        mut spawn: impl FnMut(&mut World) -> Entity,
+    ) {
+        while self.free.len() < count.min(self.max_size) {
+            let entity = spawn(world);
+            disable_recursive(world, entity);
+            self.free.push(entity);
+        }
+    }
+
+    /// Re-enable a pooled entity and its descendants, or `None` if the pool is empty
+    ///
+    /// The entity keeps the components it had when released; callers overwrite the ones
+    /// that differ, such as `Transform`. The `PreviousTransform`s of the entity and its
+    /// descendants are dropped so they do not interpolate from where they were released.
+    pub fn acquire(&mut self, world: &mut World) -> Option<Entity> {
+        while let Some(entity) = self.free.pop() {
+            // Skip entities despawned or cleared from the world while pooled
+            if !world.satisfies::<&Disabled>(entity).unwrap_or(false) {
+                continue;
+            }
+            for entity in std::iter::once(entity).chain(descendants(world, entity)) {
+                let _ = world.remove_one::<Disabled>(entity);
+                let _ = world.remove_one::<PreviousTransform>(entity);
+            }
+            return Some(entity);
+        }
+        None
+    }
+
+    /// Reuse a pooled entity with `components` inserted, or spawn a new one from them
+    pub fn spawn(&mut self, world: &mut World, components: impl DynamicBundle) -> Entity {
+        match self.acquire(world) {
+            Some(entity) => {
+                world
+                    .insert(entity, components)
+                    .expect("acquired entities are alive");
+                entity
+            }
+            None => world.spawn(components),
+        }
+    }
+
+    /// Disable `entity` and its descendants and keep them for reuse
+    ///
+    /// Once the pool holds `max_size` entities, released ones are despawned instead.
+    pub fn release(&mut self, world: &mut World, entity: Entity) -> Result<(), NoSuchEntity> {
+        if !world.contains(entity) {
+            return Err(NoSuchEntity);
+        }
+        if self.free.contains(&entity) {
+            return Ok(());
+        }
+        if self.free.len() >= self.max_size {
+            return despawn_recursive(world, entity);
+        }
+        disable_recursive(world, entity);
+        self.free.push(entity);
+        Ok(())
+    }
+
+    /// Forget every pooled entity without despawning it, e.g. after `World::clear`
+    pub fn clear(&mut self) {
+        self.free.clear();
+    }
+}
+
+impl Default for EntityPool {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn disable_recursive(world: &mut World, entity: Entity) {
+    for entity in std::iter::once(entity).chain(descendants(world, entity)) {
+        let _ = world.insert_one(entity, Disabled);
+    }
+}
+
+/// Engine-managed set of named `EntityPool`s
+///
+/// `SaveRegistry` saves it with the world and remaps the pooled entities on load.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct EntityPools {
+    pools: HashMap<String, EntityPool>,
+}
+
+impl EntityPools {
+    /// Add `pool` under `name`, replacing any pool of that name, and return it
+    pub fn insert(&mut self, name: impl Into<String>, pool: EntityPool) -> &mut EntityPool {
+        let name = name.into();
+        self.pools.insert(name.clone(), pool);
+        self.pools.get_mut(&name).expect("pool was just inserted")
+    }
+
+    pub fn get(&self, name: &str) -> Option<&EntityPool> {
+        self.pools.get(name)
+    }
+
+    pub fn get_mut(&mut self, name: &str) -> Option<&mut EntityPool> {
+        self.pools.get_mut(name)
+    }
+
+    /// Pool named `name`, created without a size limit if it does not exist yet
+    pub fn pool(&mut self, name: &str) -> &mut EntityPool {
+        self.pools.entry(name.to_string()).or_default()
+    }
+
+    /// Forget the entities of every pool, e.g. after `World::clear`
+    pub fn clear(&mut self) {
+        for pool in self.pools.values_mut() {
+            pool.clear();
+        }
+    }
+}
+
+impl MapEntities for EntityPools {
+    fn map_entities(&mut self, map: &EntityMap) {
+        for pool in self.pools.values_mut() {
+            pool.free.retain_mut(|entity| match map.get(*entity) {
+                Some(loaded) => {
+                    *entity = loaded;
+                    true
+                }
+                None => false,
+            });
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::components::{Parent, Transform};
+
+    #[test]
+    fn reuses_released_entities_with_their_children() {
+        let mut world = World::new();
+        let mut pool = EntityPool::new();
+        let root = pool.spawn(&mut world, (Transform::new([1.0, 0.0, 0.0], [1.0, 1.0]),));
+        let child = world.spawn((Transform::default(), Parent(root)));
+        for entity in [root, child] {
+            world
+                .insert_one(entity, PreviousTransform::from(&Transform::default()))
+                .unwrap();
+        }
+
+        pool.release(&mut world, root).unwrap();
+        pool.release(&mut world, root).unwrap();
+        assert_eq!(pool.free_len(), 1);
+        assert!(world.satisfies::<&Disabled>(root).unwrap());
+        assert!(world.satisfies::<&Disabled>(child).unwrap());
+
+        let position = Transform::new([5.0, 2.0, 0.0], [1.0, 1.0]);
+        assert_eq!(pool.spawn(&mut world, (position.clone(),)), root);
+        assert_eq!(*world.get::<&Transform>(root).unwrap(), position);
+        assert!(!world.satisfies::<&Disabled>(root).unwrap());
+        assert!(!world.satisfies::<&Disabled>(child).unwrap());
+        assert!(!world.satisfies::<&PreviousTransform>(root).unwrap());
+        assert!(!world.satisfies::<&PreviousTransform>(child).unwrap());
+        assert_eq!(world.len(), 2);
+        assert_eq!(pool.free_len(), 0);
+    }
+
+    #[test]
+    fn prewarms_and_caps_at_max_size() {
+        let mut world = World::new();
+        let mut pool = EntityPool::with_max_size(3);
+        pool.prewarm(&mut world, 5, |world| world.spawn((Transform::default(),)));
+        assert_eq!(pool.free_len(), 3);
+        assert_eq!(world.query::<&Disabled>().iter().count(), 3);
+
+        let extra = world.spawn((Transform::default(),));
+        pool.release(&mut world, extra).unwrap();
+        assert!(!world.contains(extra));
+        assert_eq!(pool.free_len(), 3);
+
+        // Entities cleared from the world are skipped rather than handed out
+        world.clear();
+        assert_eq!(pool.acquire(&mut world), None);
+        assert!(pool.release(&mut world, extra).is_err());
+    }
+}
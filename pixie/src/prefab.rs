@@ -0,0 +1,405 @@
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+
+use hecs::{Component, DynamicBundle, Entity, EntityBuilder, World};
+use serde::de::DeserializeOwned;
+use serde::Deserialize;
+use serde_json::Value;
+
+use crate::components::{
+    Animation, BoxCollider, CircleCollider, Collider, Force, Name, NoInterpolation, RigidBody,
+    Sprite, Tags, Text, TextStyle, Transform, Velocity,
+};
+use crate::resources::ResourceContainer;
+
+type ComponentLoader = fn(Value, &mut EntityBuilder) -> Result<(), serde_json::Error>;
+
+#[derive(Debug)]
+pub enum PrefabError {
+    Io(std::io::Error),
+    Parse(serde_json::Error),
+    UnknownPrefab(String),
+    UnknownComponent {
+        prefab: String,
+        component: String,
+    },
+    InvalidComponent {
+        prefab: String,
+        component: String,
+        error: serde_json::Error,
+    },
+    /// Prefabs that extend each other in a loop, starting with the spawned one
+    InheritanceCycle(Vec<String>),
+    /// `spawn_prefab` found no `PrefabRegistry` resource
+    NoRegistry,
+}
+
+impl fmt::Display for PrefabError {
+    fn fmt(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Io(error) => write!(formatter, "failed to read prefab file: {error}"),
+            Self::Parse(error) => write!(formatter, "invalid prefab file: {error}"),
+            Self::UnknownPrefab(name) => write!(formatter, "unknown prefab `{name}`"),
+            Self::UnknownComponent { prefab, component } => write!(
+                formatter,
+                "prefab `{prefab}` uses unregistered component `{component}`"
+            ),
+            Self::InvalidComponent {
+                prefab,
+                component,
+                error,
+            } => write!(
+                formatter,
+                "invalid `{component}` in prefab `{prefab}`: {error}"
+            ),
+            Self::InheritanceCycle(names) => {
+                write!(
+                    formatter,
+                    "prefab inheritance cycle: {}",
+                    names.join(" -> ")
+                )
+            }
+            Self::NoRegistry => write!(formatter, "no `PrefabRegistry` resource"),
+        }
+    }
+}
+
+impl std::error::Error for PrefabError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Io(error) => Some(error),
+            Self::Parse(error) => Some(error),
+            Self::InvalidComponent { error, .. } => Some(error),
+            _ => None,
+        }
+    }
+}
+
+impl From<std::io::Error> for PrefabError {
+    fn from(value: std::io::Error) -> Self {
+        Self::Io(value)
+    }
+}
+
+impl From<serde_json::Error> for PrefabError {
+    fn from(value: serde_json::Error) -> Self {
+        Self::Parse(value)
+    }
+}
+
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(default)]
+struct Prefab {
+    extends: Option<String>,
+    components: BTreeMap<String, Value>,
+}
+
+/// Named entity templates loaded from JSON, spawned with `spawn_prefab`.
+///
+/// A prefab file maps prefab names to their components, keyed by registered name:
+///
+/// ```json
+/// {
+///     "pipe_part": {
+///         "components": {
+///             "Transform": { "position": [0.0, 0.0, 0.3], "size": [2.0, 2.0] },
+///             "Sprite": { "uv": [0.0, 0.25, 0.0, 0.25], "atlas": "tile" }
+///         }
+///     },
+///     "top_cap": {
+///         "extends": "pipe_part",
+///         "components": { "PipeTarget": {} }
+///     }
+/// }
+/// ```
+///
+/// A prefab with `extends` starts from its parent's components; struct components it
+/// lists again are merged field by field. Omitted fields take the component's default,
+/// and unit variants and marker components are written as `"Dynamic"` and `null`.
+/// The built-in pixie components are registered under their type names, and games add
+/// their own with `register_component`.
+pub struct PrefabRegistry {
+    prefabs: HashMap<String, Prefab>,
+    loaders: HashMap<String, ComponentLoader>,
+}
+
+impl PrefabRegistry {
+    pub fn new() -> Self {
+        let mut registry = Self {
+            prefabs: HashMap::new(),
+            loaders: HashMap::new(),
+        };
+        registry.register_component::<Transform>("Transform");
+        registry.register_component::<Sprite>("Sprite");
+        registry.register_component::<Animation>("Animation");
+        registry.register_component::<RigidBody>("RigidBody");
+        registry.register_component::<Velocity>("Velocity");
+        registry.register_component::<Force>("Force");
+        registry.register_component::<Collider>("Collider");
+        registry.register_component::<CircleCollider>("CircleCollider");
+        registry.register_component::<BoxCollider>("BoxCollider");
+        registry.register_component::<Text>("Text");
+        registry.register_component::<TextStyle>("TextStyle");
+        registry.register_component::<NoInterpolation>("NoInterpolation");
+        registry.register_component::<Name>("Name");
+        registry.register_component::<Tags>("Tags");
+        registry
+    }
+
+    /// Let prefabs use `T` under `name`, replacing any component registered as `name`.
+    pub fn register_component<T: Component + DeserializeOwned>(&mut self, name: impl Into<String>) {
+        self.loaders.insert(name.into(), load_component::<T>);
+    }
+
+    /// Add the prefabs of a JSON prefab file, replacing ones with the same name.
+    pub fn load_str(&mut self, source: &str) -> Result<(), PrefabError> {
+        let prefabs: HashMap<String, Prefab> = serde_json::from_str(source)?;
+        self.prefabs.extend(prefabs);
+        Ok(())
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn load_file(&mut self, path: impl AsRef<std::path::Path>) -> Result<(), PrefabError> {
+        self.load_str(&std::fs::read_to_string(path)?)
+    }
+
+    pub fn contains(&self, name: &str) -> bool {
+        self.prefabs.contains_key(name)
+    }
+
+    /// Build the components of prefab `name`, with inheritance applied.
+    pub fn builder(&self, name: &str) -> Result<EntityBuilder, PrefabError> {
+        let mut builder = EntityBuilder::new();
+        for (component, value) in self.resolve(name)? {
+            let load =
+                self.loaders
+                    .get(&component)
+                    .ok_or_else(|| PrefabError::UnknownComponent {
+                        prefab: name.to_string(),
+                        component: component.clone(),
+                    })?;
+            load(value, &mut builder).map_err(|error| PrefabError::InvalidComponent {
+                prefab: name.to_string(),
+                component,
+                error,
+            })?;
+        }
+        Ok(builder)
+    }
+
+    /// Spawn prefab `name`; components in `overrides` replace the prefab's own.
+    pub fn spawn_prefab(
+        &self,
+        world: &mut World,
+        name: &str,
+        overrides: impl DynamicBundle,
+    ) -> Result<Entity, PrefabError> {
+        let mut builder = self.builder(name)?;
+        builder.add_bundle(overrides);
+        Ok(world.spawn(builder.build()))
+    }
+
+    /// Components of `name` merged over those of the prefabs it extends.
+    fn resolve(&self, name: &str) -> Result<BTreeMap<String, Value>, PrefabError> {
+        let mut chain: Vec<&str> = Vec::new();
+        let mut next = Some(name);
+        while let Some(current) = next {
+            if chain.contains(&current) {
+                let mut names: Vec<String> = chain.iter().map(|name| name.to_string()).collect();
+                names.push(current.to_string());
+                return Err(PrefabError::InheritanceCycle(names));
+            }
+            let prefab = self
+                .prefabs
+                .get(current)
+                .ok_or_else(|| PrefabError::UnknownPrefab(current.to_string()))?;
+            chain.push(current);
+            next = prefab.extends.as_deref();
+        }
+
+        let mut components = BTreeMap::new();
+        for prefab in chain.iter().rev().map(|name| &self.prefabs[*name]) {
+            for (component, value) in &prefab.components {
+                match components.get_mut(component) {
+                    Some(inherited) => merge(inherited, value.clone()),
+                    None => {
+                        components.insert(component.clone(), value.clone());
+                    }
+                }
+            }
+        }
+        Ok(components)
+    }
+}
+
+impl Default for PrefabRegistry {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Spawn prefab `name` from the engine-managed `PrefabRegistry` resource
+///
+/// Shorthand for `PrefabRegistry::spawn_prefab` when only the resources are at hand.
+pub fn spawn_prefab(
+    world: &mut World,
+    resources: &ResourceContainer,
+    name: &str,
+    overrides: impl DynamicBundle,
+) -> Result<Entity, PrefabError> {
+    resources
+        .get::<PrefabRegistry>()
+        .ok_or(PrefabError::NoRegistry)?
+        .spawn_prefab(world, name, overrides)
+}
+
+/// Deserialize a `T` and add it to `builder`; shared with `SaveRegistry`
+pub(crate) fn load_component<T: Component + DeserializeOwned>(
+    value: Value,
+    builder: &mut EntityBuilder,
+) -> Result<(), serde_json::Error> {
+    builder.add(serde_json::from_value::<T>(value)?);
+    Ok(())
+}
+
+/// Overlay `value` onto `inherited`, merging struct fields and replacing anything else.
+fn merge(inherited: &mut Value, value: Value) {
+    match (inherited, value) {
+        (Value::Object(inherited), Value::Object(fields)) => {
+            for (field, value) in fields {
+                match inherited.get_mut(&field) {
+                    Some(existing) => merge(existing, value),
+                    None => {
+                        inherited.insert(field, value);
+                    }
+                }
+            }
+        }
+        (inherited, value) => *inherited = value,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::components::{BodyType, Parent};
+
+    #[derive(Clone, Debug, Deserialize, PartialEq)]
+    struct Health {
+        current: u32,
+        max: u32,
+    }
+
+    const PREFABS: &str = r#"{
+        "ball": {
+            "components": {
+                "Transform": { "position": [0.0, 5.0, 0.5], "size": [1.0, 1.0] },
+                "Sprite": { "uv": [0.0, 1.0, 0.0, 1.0], "atlas": "ball" },
+                "RigidBody": { "body_type": "Dynamic" },
+                "Health": { "current": 3, "max": 3 }
+            }
+        },
+        "big_ball": {
+            "extends": "ball",
+            "components": {
+                "Transform": { "size": [2.0, 2.0] },
+                "CircleCollider": { "radius": 1.0 },
+                "NoInterpolation": null
+            }
+        }
+    }"#;
+
+    fn registry() -> PrefabRegistry {
+        let mut registry = PrefabRegistry::new();
+        registry.register_component::<Health>("Health");
+        registry.load_str(PREFABS).unwrap();
+        registry
+    }
+
+    #[test]
+    fn spawns_inherited_components_with_overrides() {
+        let registry = registry();
+        let mut world = World::new();
+        let parent = world.spawn(());
+
+        let entity = registry
+            .spawn_prefab(
+                &mut world,
+                "big_ball",
+                (Health { current: 1, max: 3 }, Parent(parent)),
+            )
+            .unwrap();
+
+        let transform = world.get::<&Transform>(entity).unwrap();
+        assert_eq!(transform.position, [0.0, 5.0, 0.5]);
+        assert_eq!(transform.size, [2.0, 2.0]);
+        assert_eq!(world.get::<&Sprite>(entity).unwrap().atlas.as_str(), "ball");
+        assert_eq!(world.get::<&CircleCollider>(entity).unwrap().radius, 1.0);
+        assert_eq!(
+            world.get::<&RigidBody>(entity).unwrap().body_type,
+            BodyType::Dynamic
+        );
+        assert!(world.satisfies::<&NoInterpolation>(entity).unwrap());
+        assert_eq!(world.get::<&Health>(entity).unwrap().current, 1);
+        assert_eq!(world.get::<&Parent>(entity).unwrap().0, parent);
+    }
+
+    #[test]
+    fn spawns_from_the_registry_resource() {
+        let mut world = World::new();
+        let mut resources = ResourceContainer::new();
+        assert!(matches!(
+            spawn_prefab(&mut world, &resources, "ball", ()),
+            Err(PrefabError::NoRegistry)
+        ));
+
+        resources.insert(registry());
+        let entity = spawn_prefab(
+            &mut world,
+            &resources,
+            "ball",
+            (Health { current: 2, max: 3 },),
+        )
+        .unwrap();
+        assert_eq!(world.get::<&Health>(entity).unwrap().current, 2);
+        assert_eq!(world.len(), 1);
+    }
+
+    #[test]
+    fn reports_unknown_names_and_cycles() {
+        let mut registry = registry();
+        registry
+            .load_str(
+                r#"{
+                    "loop_a": { "extends": "loop_b" },
+                    "loop_b": { "extends": "loop_a" },
+                    "broken": { "components": { "Mana": { "current": 1 } } },
+                    "bad_field": { "components": { "Health": { "current": "full", "max": 1 } } }
+                }"#,
+            )
+            .unwrap();
+        let mut world = World::new();
+
+        assert!(matches!(
+            registry.spawn_prefab(&mut world, "missing", ()),
+            Err(PrefabError::UnknownPrefab(name)) if name == "missing"
+        ));
+        assert!(matches!(
+            registry.spawn_prefab(&mut world, "loop_a", ()),
+            Err(PrefabError::InheritanceCycle(names)) if names == ["loop_a", "loop_b", "loop_a"]
+        ));
+        assert!(matches!(
+            registry.spawn_prefab(&mut world, "broken", ()),
+            Err(PrefabError::UnknownComponent { component, .. }) if component == "Mana"
+        ));
+        assert!(matches!(
+            registry.spawn_prefab(&mut world, "bad_field", ()),
+            Err(PrefabError::InvalidComponent { component, .. }) if component == "Health"
+        ));
+        assert_eq!(world.len(), 0);
+        assert!(matches!(
+            registry.load_str("{ \"oops\": { }"),
+            Err(PrefabError::Parse(_))
+        ));
+    }
+}
@@ -0,0 +1,383 @@
+use std::fmt;
+use std::path::Path;
+
+use winit::event::MouseButton;
+
+use crate::resources::{key_from_id, key_id, InputEvent};
+
+const MAGIC: &[u8; 4] = b"PXIR";
+const VERSION: u8 = 2;
+
+const TAG_FRAME: u8 = 0;
+const TAG_KEY_PRESSED: u8 = 1;
+const TAG_KEY_RELEASED: u8 = 2;
+const TAG_MOUSE_PRESSED: u8 = 3;
+const TAG_MOUSE_RELEASED: u8 = 4;
+const TAG_CURSOR_MOVED: u8 = 5;
+const TAG_CURSOR_LEFT: u8 = 6;
+const TAG_WHEEL: u8 = 7;
+const TAG_FOCUS_LOST: u8 = 8;
+
+/// One entry of an input recording, in the order the engine saw it.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum RecordEntry {
+    /// A frame advanced by `elapsed` seconds after the preceding inputs arrived.
+    Frame { elapsed: f32 },
+    /// An input that arrived after `tick` fixed steps had run.
+    Input { tick: u64, event: InputEvent },
+}
+
+#[derive(Debug)]
+pub enum RecordingError {
+    Io(std::io::Error),
+    InvalidHeader,
+    UnsupportedVersion(u8),
+    Truncated,
+    UnknownEntry(u8),
+    UnknownKey(u16),
+    UnknownMouseButton(u32),
+}
+
+impl fmt::Display for RecordingError {
+    fn fmt(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Io(error) => write!(formatter, "failed to access input recording: {error}"),
+            Self::InvalidHeader => formatter.write_str("file is not an input recording"),
+            Self::UnsupportedVersion(version) => {
+                write!(formatter, "unsupported input recording version {version}")
+            }
+            Self::Truncated => formatter.write_str("input recording ends mid-entry"),
+            Self::UnknownEntry(tag) => write!(formatter, "unknown input recording entry {tag}"),
+            Self::UnknownKey(id) => write!(formatter, "unknown key id {id} in input recording"),
+            Self::UnknownMouseButton(id) => {
+                write!(formatter, "unknown mouse button id {id} in input recording")
+            }
+        }
+    }
+}
+
+impl std::error::Error for RecordingError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Io(error) => Some(error),
+            _ => None,
+        }
+    }
+}
+
+impl From<std::io::Error> for RecordingError {
+    fn from(value: std::io::Error) -> Self {
+        Self::Io(value)
+    }
+}
+
+/// Frame timings and input events captured by the engine, plus the `GameRng` seed.
+///
+/// Replaying a recording feeds the same inputs on the same frames, so a simulation that
+/// only reads `Input` and `GameRng` ends in the same `World`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct InputRecording {
+    seed: u64,
+    entries: Vec<RecordEntry>,
+}
+
+impl InputRecording {
+    pub fn new(seed: u64) -> Self {
+        Self {
+            seed,
+            entries: Vec::new(),
+        }
+    }
+
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    pub fn entries(&self) -> &[RecordEntry] {
+        &self.entries
+    }
+
+    pub fn frame_count(&self) -> usize {
+        self.entries
+            .iter()
+            .filter(|entry| matches!(entry, RecordEntry::Frame { .. }))
+            .count()
+    }
+
+    pub(crate) fn push(&mut self, entry: RecordEntry) {
+        self.entries.push(entry);
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(13 + self.entries.len() * 12);
+        bytes.extend_from_slice(MAGIC);
+        bytes.push(VERSION);
+        bytes.extend_from_slice(&self.seed.to_le_bytes());
+
+        for entry in &self.entries {
+            match *entry {
+                RecordEntry::Frame { elapsed } => {
+                    bytes.push(TAG_FRAME);
+                    bytes.extend_from_slice(&elapsed.to_le_bytes());
+                }
+                RecordEntry::Input { tick, event } => {
+                    // The simulation never records events that cannot be encoded
+                    let Some((tag, payload)) = encode_event(event) else {
+                        continue;
+                    };
+                    bytes.push(tag);
+                    bytes.extend_from_slice(&tick.to_le_bytes());
+                    bytes.extend_from_slice(&payload);
+                }
+            }
+        }
+        bytes
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, RecordingError> {
+        let mut reader = Reader { bytes };
+        if reader.take(4)? != MAGIC {
+            return Err(RecordingError::InvalidHeader);
+        }
+        let version = reader.u8()?;
+        if version != VERSION {
+            return Err(RecordingError::UnsupportedVersion(version));
+        }
+        let seed = reader.u64()?;
+
+        let mut entries = Vec::new();
+        while !reader.bytes.is_empty() {
+            let tag = reader.u8()?;
+            if tag == TAG_FRAME {
+                entries.push(RecordEntry::Frame {
+                    elapsed: reader.f32()?,
+                });
+                continue;
+            }
+
+            let tick = reader.u64()?;
+            let event = match tag {
+                TAG_KEY_PRESSED => InputEvent::KeyPressed(reader.key()?),
+                TAG_KEY_RELEASED => InputEvent::KeyReleased(reader.key()?),
+                TAG_MOUSE_PRESSED => InputEvent::MousePressed(mouse_from_id(reader.u32()?)?),
+                TAG_MOUSE_RELEASED => InputEvent::MouseReleased(mouse_from_id(reader.u32()?)?),
+                TAG_CURSOR_MOVED => InputEvent::CursorMoved([reader.f32()?, reader.f32()?]),
+                TAG_CURSOR_LEFT => InputEvent::CursorLeft,
+                TAG_WHEEL => InputEvent::Wheel([reader.f32()?, reader.f32()?]),
+                TAG_FOCUS_LOST => InputEvent::FocusLost,
+                other => return Err(RecordingError::UnknownEntry(other)),
+            };
+            entries.push(RecordEntry::Input { tick, event });
+        }
+
+        Ok(Self { seed, entries })
+    }
+
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<(), RecordingError> {
+        std::fs::write(path, self.to_bytes())?;
+        Ok(())
+    }
+
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, RecordingError> {
+        Self::from_bytes(&std::fs::read(path)?)
+    }
+}
+
+/// Replay position inside a recording.
+pub(crate) struct ReplayCursor {
+    recording: InputRecording,
+    next: usize,
+}
+
+impl ReplayCursor {
+    pub(crate) fn new(recording: InputRecording) -> Self {
+        Self { recording, next: 0 }
+    }
+
+    /// Returns the inputs recorded before the next frame and that frame's elapsed time,
+    /// or `None` once the recording is exhausted.
+    pub(crate) fn next_frame(&mut self) -> Option<(Vec<(u64, InputEvent)>, f32)> {
+        let mut inputs = Vec::new();
+        while let Some(entry) = self.recording.entries.get(self.next) {
+            self.next += 1;
+            match *entry {
+                RecordEntry::Input { tick, event } => inputs.push((tick, event)),
+                RecordEntry::Frame { elapsed } => return Some((inputs, elapsed)),
+            }
+        }
+        None
+    }
+}
+
+/// Whether `event` can be written to a recording.
+///
+/// Key codes added by a newer winit have no id yet; the simulation drops them before they
+/// reach `Input`, so live and replayed runs see the same events.
+pub(crate) fn is_recordable(event: &InputEvent) -> bool {
+    encode_event(*event).is_some()
+}
+
+fn encode_event(event: InputEvent) -> Option<(u8, Vec<u8>)> {
+    let floats = |values: [f32; 2]| {
+        let mut payload = values[0].to_le_bytes().to_vec();
+        payload.extend_from_slice(&values[1].to_le_bytes());
+        payload
+    };
+    let key = |key| key_id(key).map(|id| id.to_le_bytes().to_vec());
+    let mouse = |button| mouse_id(button).to_le_bytes().to_vec();
+
+    Some(match event {
+        InputEvent::KeyPressed(code) => (TAG_KEY_PRESSED, key(code)?),
+        InputEvent::KeyReleased(code) => (TAG_KEY_RELEASED, key(code)?),
+        InputEvent::MousePressed(button) => (TAG_MOUSE_PRESSED, mouse(button)),
+        InputEvent::MouseReleased(button) => (TAG_MOUSE_RELEASED, mouse(button)),
+        InputEvent::CursorMoved(position) => (TAG_CURSOR_MOVED, floats(position)),
+        InputEvent::CursorLeft => (TAG_CURSOR_LEFT, Vec::new()),
+        InputEvent::Wheel(delta) => (TAG_WHEEL, floats(delta)),
+        InputEvent::FocusLost => (TAG_FOCUS_LOST, Vec::new()),
+    })
+}
+
+fn mouse_id(button: MouseButton) -> u32 {
+    match button {
+        MouseButton::Left => 0,
+        MouseButton::Right => 1,
+        MouseButton::Middle => 2,
+        MouseButton::Back => 3,
+        MouseButton::Forward => 4,
+        MouseButton::Other(index) => u32::from(index) + 5,
+    }
+}
+
+fn mouse_from_id(id: u32) -> Result<MouseButton, RecordingError> {
+    Ok(match id {
+        0 => MouseButton::Left,
+        1 => MouseButton::Right,
+        2 => MouseButton::Middle,
+        3 => MouseButton::Back,
+        4 => MouseButton::Forward,
+        other => MouseButton::Other(
+            u16::try_from(other - 5).map_err(|_| RecordingError::UnknownMouseButton(other))?,
+        ),
+    })
+}
+
+struct Reader<'a> {
+    bytes: &'a [u8],
+}
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, count: usize) -> Result<&'a [u8], RecordingError> {
+        if self.bytes.len() < count {
+            return Err(RecordingError::Truncated);
+        }
+        let (taken, rest) = self.bytes.split_at(count);
+        self.bytes = rest;
+        Ok(taken)
+    }
+
+    fn u8(&mut self) -> Result<u8, RecordingError> {
+        Ok(self.take(1)?[0])
+    }
+
+    fn u16(&mut self) -> Result<u16, RecordingError> {
+        Ok(u16::from_le_bytes(self.take(2)?.try_into().unwrap()))
+    }
+
+    fn u32(&mut self) -> Result<u32, RecordingError> {
+        Ok(u32::from_le_bytes(self.take(4)?.try_into().unwrap()))
+    }
+
+    fn u64(&mut self) -> Result<u64, RecordingError> {
+        Ok(u64::from_le_bytes(self.take(8)?.try_into().unwrap()))
+    }
+
+    fn f32(&mut self) -> Result<f32, RecordingError> {
+        Ok(f32::from_le_bytes(self.take(4)?.try_into().unwrap()))
+    }
+
+    fn key(&mut self) -> Result<winit::keyboard::KeyCode, RecordingError> {
+        let id = self.u16()?;
+        key_from_id(id).ok_or(RecordingError::UnknownKey(id))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use winit::keyboard::KeyCode;
+
+    fn sample_recording() -> InputRecording {
+        let mut recording = InputRecording::new(7);
+        recording.push(RecordEntry::Input {
+            tick: 0,
+            event: InputEvent::KeyPressed(KeyCode::Space),
+        });
+        recording.push(RecordEntry::Frame { elapsed: 0.016 });
+        recording.push(RecordEntry::Input {
+            tick: 1,
+            event: InputEvent::CursorMoved([10.5, 20.0]),
+        });
+        recording.push(RecordEntry::Input {
+            tick: 1,
+            event: InputEvent::MousePressed(MouseButton::Other(2)),
+        });
+        recording.push(RecordEntry::Input {
+            tick: 1,
+            event: InputEvent::MouseReleased(MouseButton::Other(u16::MAX)),
+        });
+        recording.push(RecordEntry::Input {
+            tick: 1,
+            event: InputEvent::KeyReleased(KeyCode::F35),
+        });
+        recording.push(RecordEntry::Input {
+            tick: 1,
+            event: InputEvent::Wheel([0.0, -1.0]),
+        });
+        recording.push(RecordEntry::Input {
+            tick: 2,
+            event: InputEvent::FocusLost,
+        });
+        recording.push(RecordEntry::Frame { elapsed: 0.02 });
+        recording
+    }
+
+    #[test]
+    fn bytes_round_trip() {
+        let recording = sample_recording();
+
+        let decoded = InputRecording::from_bytes(&recording.to_bytes()).unwrap();
+
+        assert_eq!(decoded, recording);
+        assert_eq!(decoded.frame_count(), 2);
+    }
+
+    #[test]
+    fn rejects_foreign_and_truncated_data() {
+        assert!(matches!(
+            InputRecording::from_bytes(b"nope"),
+            Err(RecordingError::InvalidHeader)
+        ));
+
+        let bytes = sample_recording().to_bytes();
+        assert!(matches!(
+            InputRecording::from_bytes(&bytes[..bytes.len() - 2]),
+            Err(RecordingError::Truncated)
+        ));
+    }
+
+    #[test]
+    fn replay_cursor_groups_inputs_by_frame() {
+        let mut cursor = ReplayCursor::new(sample_recording());
+
+        let (inputs, elapsed) = cursor.next_frame().unwrap();
+        assert_eq!(inputs, [(0, InputEvent::KeyPressed(KeyCode::Space))]);
+        assert_eq!(elapsed, 0.016);
+
+        let (inputs, elapsed) = cursor.next_frame().unwrap();
+        assert_eq!(inputs.len(), 6);
+        assert_eq!(elapsed, 0.02);
+
+        assert!(cursor.next_frame().is_none());
+    }
+}
@@ -2,6 +2,7 @@ use std::collections::HashMap;
 use std::iter;
 use std::sync::Arc;
 
+use instant::Instant;
 use winit::window::Window;
 
 use crate::renderer::font_manager::FontManager;
@@ -12,6 +13,7 @@ use crate::renderer::pipeline_manager::PipelineManager;
 use crate::renderer::render_input_data::*;
 use crate::renderer::texture;
 use crate::renderer::RenderError;
+use crate::resources::FrameStats;
 use crate::AtlasId;
 
 #[derive(Default)]
@@ -48,7 +50,12 @@ pub struct RenderState {
     viewport_data: [f32; 6],
 }
 impl RenderState {
-    pub async fn new(window: Arc<Window>, width: u32, height: u32) -> Result<Self, RenderError> {
+    pub async fn new(
+        window: Arc<Window>,
+        width: u32,
+        height: u32,
+        requested_present_mode: Option<wgpu::PresentMode>,
+    ) -> Result<Self, RenderError> {
         // The instance is a handle to our GPU
         // BackendBit::PRIMARY => Vulkan + Metal + DX12 + Browser WebGPU
         let instance = wgpu::Instance::new(&wgpu::InstanceDescriptor::default());
@@ -96,9 +103,18 @@ impl RenderState {
             .ok_or(RenderError::SurfaceConfiguration(
                 "adapter reported no supported surface formats",
             ))?;
-        let present_mode = surface_caps.present_modes.first().copied().ok_or(
-            RenderError::SurfaceConfiguration("adapter reported no supported present modes"),
-        )?;
+        let present_mode = requested_present_mode
+            .filter(|mode| {
+                let supported = surface_caps.present_modes.contains(mode);
+                if !supported {
+                    log::warn!("present mode {mode:?} is not supported, using adapter default");
+                }
+                supported
+            })
+            .or_else(|| surface_caps.present_modes.first().copied())
+            .ok_or(RenderError::SurfaceConfiguration(
+                "adapter reported no supported present modes",
+            ))?;
         let alpha_mode =
             surface_caps
                 .alpha_modes
@@ -183,7 +199,6 @@ impl RenderState {
         Ok(())
     }
 
-    #[allow(dead_code)]
     pub fn set_clear_color(&mut self, color: wgpu::Color) {
         self.color = color;
     }
@@ -271,8 +286,18 @@ impl RenderState {
         );
     }
 
-    fn render(&self, frame: &RenderFrame<'_>) -> Result<(), RenderError> {
+    fn render(
+        &self,
+        frame: &RenderFrame<'_>,
+        mut stats: Option<&mut FrameStats>,
+    ) -> Result<(), RenderError> {
+        let acquire_start = Instant::now();
         let output = self.surface.get_current_texture()?;
+        if let Some(stats) = stats.as_deref_mut() {
+            stats.record("render/acquire", acquire_start, acquire_start.elapsed());
+        }
+
+        let submit_start = Instant::now();
         let view = output
             .texture
             .create_view(&wgpu::TextureViewDescriptor::default());
@@ -327,13 +352,31 @@ impl RenderState {
         }
 
         self.queue.submit(iter::once(encoder.finish()));
+        if let Some(stats) = stats.as_deref_mut() {
+            stats.record("render/submit", submit_start, submit_start.elapsed());
+        }
+
+        let present_start = Instant::now();
         output.present();
+        if let Some(stats) = stats {
+            stats.record("render/present", present_start, present_start.elapsed());
+        }
         Ok(())
     }
 
-    pub fn render_frame(&mut self, frame: &RenderFrame<'_>) -> Result<(), RenderError> {
+    /// Upload `frame` and draw it, recording `render/upload`, `render/acquire`,
+    /// `render/submit` and `render/present` timings into `stats` when given.
+    pub fn render_frame(
+        &mut self,
+        frame: &RenderFrame<'_>,
+        mut stats: Option<&mut FrameStats>,
+    ) -> Result<(), RenderError> {
+        let upload_start = Instant::now();
         self.update_frame(frame)?;
-        self.render(frame)
+        if let Some(stats) = stats.as_deref_mut() {
+            stats.record("render/upload", upload_start, upload_start.elapsed());
+        }
+        self.render(frame, stats)
     }
 }
 
@@ -1,11 +1,15 @@
 use std::collections::{HashMap, HashSet};
 use std::sync::Arc;
 
-use hecs::World;
+use hecs::{Entity, Satisfies, World};
 
-use crate::components::{Sprite, Text, TextStyle, Transform};
+use crate::change_detection::{ChangeTick, RemovedComponents};
+use crate::components::{
+    Disabled, GlobalTransform, NoInterpolation, Parent, PreviousTransform, Sprite, Text, TextStyle,
+    Transform,
+};
 use crate::renderer::{RenderFrame, SpriteRenderData, TextRenderData};
-use crate::resources::ResourceContainer;
+use crate::resources::{InterpolationAlpha, ResourceContainer};
 use crate::{AtlasError, AtlasId, TextureAtlasRegistry};
 
 #[derive(Default)]
@@ -14,6 +18,12 @@ pub struct RenderWorldExtractor {
     sprite_atlases: Vec<AtlasId>,
     active_sprite_atlases: HashSet<AtlasId>,
     text_render_buffer: Vec<TextRenderData>,
+    /// Shared text contents by entity, reused while `Text` holds the same string
+    text_contents: HashMap<Entity, Arc<String>>,
+    /// Change tick the contents were last synced at
+    text_tick: Option<ChangeTick>,
+    /// World transforms of parented entities and their ancestors for this frame
+    interpolated: HashMap<Entity, GlobalTransform>,
 }
 
 impl RenderWorldExtractor {
@@ -23,6 +33,9 @@ impl RenderWorldExtractor {
             sprite_atlases: Vec::with_capacity(sprite_atlas_count),
             active_sprite_atlases: HashSet::with_capacity(sprite_atlas_count),
             text_render_buffer: Vec::with_capacity(text_count),
+            text_contents: HashMap::with_capacity(text_count),
+            text_tick: None,
+            interpolated: HashMap::new(),
         }
     }
 
@@ -36,8 +49,9 @@ impl RenderWorldExtractor {
             .expect("Camera resource not found")
             .get_view_proj();
 
+        self.interpolated.clear();
         self.extract_sprites(world, resources)?;
-        self.extract_texts(world);
+        self.extract_texts(world, resources);
 
         Ok(RenderFrame::new(
             camera_uniform,
@@ -62,7 +76,22 @@ impl RenderWorldExtractor {
         self.sprite_atlases.clear();
         self.active_sprite_atlases.clear();
 
-        for (entity, (transform, sprite)) in world.query::<(&Transform, &Sprite)>().iter() {
+        let alpha = resources
+            .get::<InterpolationAlpha>()
+            .map_or(1.0, |alpha| alpha.0);
+
+        for (entity, (transform, global, sprite, previous, no_interpolation, has_parent)) in world
+            .query::<(
+                &Transform,
+                Option<&GlobalTransform>,
+                &Sprite,
+                Option<&PreviousTransform>,
+                Satisfies<&NoInterpolation>,
+                Satisfies<&Parent>,
+            )>()
+            .without::<&Disabled>()
+            .iter()
+        {
             if !registry.is_loaded(&sprite.atlas) {
                 return Err(AtlasError::MissingAtlas {
                     atlas: sprite.atlas.clone(),
@@ -77,13 +106,29 @@ impl RenderWorldExtractor {
                 self.sprite_render_data.entry(atlas).or_default();
             }
 
+            // Blend physics-driven roots between the last two fixed steps
+            let (position, size, rotation) = match (previous, global) {
+                _ if has_parent => {
+                    let global = self
+                        .interpolated_global(world, entity, alpha)
+                        .unwrap_or_else(|| GlobalTransform::from(transform));
+                    (global.position, global.size, global.rotation)
+                }
+                (Some(previous), _) if !no_interpolation => {
+                    let (position, rotation) = previous.lerp(transform, alpha);
+                    (position, transform.size, rotation)
+                }
+                (_, Some(global)) => (global.position, global.size, global.rotation),
+                _ => (transform.position, transform.size, transform.rotation),
+            };
+
             self.sprite_render_data
                 .get_mut(&sprite.atlas)
                 .expect("active sprite atlas must have a render batch")
                 .push(SpriteRenderData {
-                    position: transform.position,
-                    size: transform.size,
-                    rotation: transform.rotation,
+                    position,
+                    size,
+                    rotation,
                     uv: sprite.uv,
                 });
         }
@@ -91,14 +136,112 @@ impl RenderWorldExtractor {
         Ok(())
     }
 
-    fn extract_texts(&mut self, world: &World) {
+    /// World transform to draw `entity` at, following its ancestors up to the root
+    ///
+    /// An interpolated root is blended between the last two fixed steps like a root
+    /// sprite, and each child is placed relative to its parent's blended transform rather
+    /// than the fixed-step `GlobalTransform`.
+    fn interpolated_global(
+        &mut self,
+        world: &World,
+        entity: Entity,
+        alpha: f32,
+    ) -> Option<GlobalTransform> {
+        if let Some(global) = self.interpolated.get(&entity) {
+            return Some(*global);
+        }
+        let (transform, parent, previous, no_interpolation) = {
+            let mut query = world
+                .query_one::<(
+                    &Transform,
+                    Option<&Parent>,
+                    Option<&PreviousTransform>,
+                    Satisfies<&NoInterpolation>,
+                )>(entity)
+                .ok()?;
+            let (transform, parent, previous, no_interpolation) = query.get()?;
+            (
+                transform.clone(),
+                parent.map(|parent| parent.0),
+                previous.cloned(),
+                no_interpolation,
+            )
+        };
+        // Entities in a `Parent` cycle stop at the transform they were first reached with
+        self.interpolated
+            .insert(entity, GlobalTransform::from(&transform));
+
+        let parent =
+            parent.filter(|parent| world.satisfies::<&Transform>(*parent).unwrap_or(false));
+        let global = match (parent, previous) {
+            (Some(parent), _) => self
+                .interpolated_global(world, parent, alpha)?
+                .mul_transform(&transform),
+            (None, Some(previous)) if !no_interpolation => {
+                let (position, rotation) = previous.lerp(&transform, alpha);
+                GlobalTransform {
+                    position,
+                    size: transform.size,
+                    rotation,
+                }
+            }
+            (None, _) => GlobalTransform::from(&transform),
+        };
+        self.interpolated.insert(entity, global);
+        Some(global)
+    }
+
+    fn extract_texts(&mut self, world: &World, resources: &ResourceContainer) {
         self.text_render_buffer.clear();
 
-        for (_, (transform, text, style)) in world.query::<(&Transform, &Text, &TextStyle)>().iter()
+        // Contents can only be reused when no removal was missed
+        let removed = resources.get::<RemovedComponents<Text>>();
+        let since = self.text_tick.unwrap_or_default();
+        match removed.and_then(|removed| removed.since(since)) {
+            Some(entities) if self.text_tick.is_some() => {
+                for entity in entities {
+                    self.text_contents.remove(&entity);
+                }
+            }
+            _ => self.text_contents.clear(),
+        }
+        // Extraction only reads, so it remembers the clock without advancing it
+        self.text_tick = removed.map(|_| resources.change_tick());
+
+        let alpha = resources
+            .get::<InterpolationAlpha>()
+            .map_or(1.0, |alpha| alpha.0);
+
+        for (entity, (transform, global, text, style, has_parent)) in world
+            .query::<(
+                &Transform,
+                Option<&GlobalTransform>,
+                &Text,
+                &TextStyle,
+                Satisfies<&Parent>,
+            )>()
+            .without::<&Disabled>()
+            .iter()
         {
+            // Comparing also catches writes that bypassed `Mut`, and costs less than a copy
+            let content = match self.text_contents.get(&entity) {
+                Some(content) if content.as_str() == text.content => content.clone(),
+                _ => {
+                    let content = Arc::new(text.content.clone());
+                    if removed.is_some() {
+                        self.text_contents.insert(entity, content.clone());
+                    }
+                    content
+                }
+            };
+            let global = match global {
+                _ if has_parent => self.interpolated_global(world, entity, alpha),
+                global => global.copied(),
+            };
+            let position = global.map_or(transform.position, |global| global.position);
             self.text_render_buffer.push(TextRenderData {
-                content: Arc::new(text.content.clone()),
-                position: [transform.position[0], transform.position[1], style.z_index],
+                content,
+                position: [position[0], position[1], style.z_index],
                 size: style.size,
                 color: style.color,
             });
@@ -109,6 +252,7 @@ impl RenderWorldExtractor {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::change_detection::get_tracked_mut;
     use crate::resources::Camera;
 
     fn resources_with_camera() -> ResourceContainer {
@@ -216,7 +360,10 @@ mod tests {
                 z_index: 0.5,
             },
         ));
-        let resources = resources_with_camera();
+        // Tracked like the engine does, while the write below bypasses `Mut`
+        let mut resources = resources_with_camera();
+        resources.track_changes::<Text>();
+        resources.detect_changes(&mut world);
         let mut extractor = RenderWorldExtractor::default();
 
         extractor.extract(&world, &resources).unwrap();
@@ -229,6 +376,7 @@ mod tests {
             style.color = [0.1, 0.2, 0.3];
             style.z_index = 0.75;
         }
+        resources.detect_changes(&mut world);
 
         let frame = extractor.extract(&world, &resources).unwrap();
         let text = &frame.texts()[0];
@@ -239,6 +387,123 @@ mod tests {
         assert_eq!(text.color, [0.1, 0.2, 0.3]);
     }
 
+    #[test]
+    fn reuses_text_contents_until_text_changes() {
+        let mut world = World::new();
+        let style = TextStyle {
+            size: [1.0, 1.0],
+            color: [1.0, 1.0, 1.0],
+            z_index: 0.5,
+        };
+        let label = |content: &str| Text {
+            content: content.to_string(),
+        };
+        let still = world.spawn((Transform::default(), label("still"), style.clone()));
+        let edited = world.spawn((Transform::default(), label("before"), style.clone()));
+        let mut resources = resources_with_camera();
+        resources.track_changes::<Text>();
+        resources.detect_changes(&mut world);
+        let mut extractor = RenderWorldExtractor::default();
+
+        fn contents(
+            extractor: &mut RenderWorldExtractor,
+            world: &World,
+            resources: &ResourceContainer,
+        ) -> Vec<Arc<String>> {
+            let frame = extractor.extract(world, resources).unwrap();
+            frame
+                .texts()
+                .iter()
+                .map(|text| text.content.clone())
+                .collect()
+        }
+        let tick = resources.change_tick();
+        let first = contents(&mut extractor, &world, &resources);
+        assert_eq!(resources.change_tick(), tick);
+
+        get_tracked_mut::<Text>(&mut world, &resources, edited)
+            .unwrap()
+            .content = "after".to_string();
+        resources.detect_changes(&mut world);
+        let second = contents(&mut extractor, &world, &resources);
+
+        let find = |texts: &[Arc<String>], content: &str| {
+            texts
+                .iter()
+                .find(|text| text.as_str() == content)
+                .unwrap()
+                .clone()
+        };
+        assert!(Arc::ptr_eq(&find(&first, "still"), &find(&second, "still")));
+        assert_eq!(find(&second, "after").as_str(), "after");
+
+        world.despawn(still).unwrap();
+        resources.detect_changes(&mut world);
+        contents(&mut extractor, &world, &resources);
+        assert!(!extractor.text_contents.contains_key(&still));
+        assert!(extractor.text_contents.contains_key(&edited));
+    }
+
+    #[test]
+    fn renders_children_at_their_global_transform() {
+        let mut world = World::new();
+        let mut resources = resources_with_camera();
+        let parent = world.spawn((Transform::new([4.0, 1.0, 0.0], [2.0, 2.0]),));
+        world.spawn((
+            Transform::new([1.0, 0.0, 0.5], [0.5, 0.5]),
+            Sprite {
+                atlas: "main".into(),
+                uv: [0.0, 1.0, 0.0, 1.0],
+            },
+            PreviousTransform {
+                position: [0.0, 0.0, 0.0],
+                rotation: 0.0,
+            },
+            Parent(parent),
+        ));
+        crate::systems::propagate_transforms(&mut world, &mut resources);
+        resources.insert(InterpolationAlpha(0.5));
+        let mut extractor = RenderWorldExtractor::default();
+
+        let frame = extractor.extract(&world, &resources).unwrap();
+        let (_, sprites) = frame.sprite_batches().next().unwrap();
+
+        assert_eq!(sprites[0].position, [6.0, 1.0, 0.5]);
+        assert_eq!(sprites[0].size, [1.0, 1.0]);
+    }
+
+    #[test]
+    fn children_follow_their_interpolated_root() {
+        let mut world = World::new();
+        let mut resources = resources_with_camera();
+        let root = world.spawn((
+            Transform::new([4.0, 1.0, 0.0], [2.0, 2.0]),
+            PreviousTransform {
+                position: [0.0, 1.0, 0.0],
+                rotation: 0.0,
+            },
+        ));
+        let child = world.spawn((Transform::new([1.0, 0.0, 0.5], [0.5, 0.5]), Parent(root)));
+        world.spawn((
+            Transform::new([0.0, 1.0, 0.0], [1.0, 1.0]),
+            Sprite {
+                atlas: "main".into(),
+                uv: [0.0, 1.0, 0.0, 1.0],
+            },
+            Parent(child),
+        ));
+        crate::systems::propagate_transforms(&mut world, &mut resources);
+        resources.insert(InterpolationAlpha(0.5));
+        let mut extractor = RenderWorldExtractor::default();
+
+        let frame = extractor.extract(&world, &resources).unwrap();
+        let (_, sprites) = frame.sprite_batches().next().unwrap();
+
+        // The root is drawn halfway between x = 0 and x = 4
+        assert_eq!(sprites[0].position, [4.0, 2.0, 0.5]);
+        assert_eq!(sprites[0].size, [1.0, 1.0]);
+    }
+
     #[test]
     fn groups_sprites_into_atlas_batches() {
         let mut world = World::new();
@@ -264,6 +529,37 @@ mod tests {
         assert_eq!(batch_sizes["second"], 1);
     }
 
+    #[test]
+    fn skips_disabled_entities() {
+        let mut world = World::new();
+        world.spawn((
+            Transform::default(),
+            Sprite {
+                atlas: "main".into(),
+                uv: [0.0, 1.0, 0.0, 1.0],
+            },
+            Disabled,
+        ));
+        world.spawn((
+            Transform::default(),
+            Text {
+                content: "pooled".to_string(),
+            },
+            TextStyle {
+                size: [1.0, 1.0],
+                color: [1.0, 1.0, 1.0],
+                z_index: 0.0,
+            },
+            Disabled,
+        ));
+        let resources = resources_with_camera();
+        let mut extractor = RenderWorldExtractor::default();
+        let frame = extractor.extract(&world, &resources).unwrap();
+
+        assert_eq!(frame.sprite_batches().count(), 0);
+        assert!(frame.texts().is_empty());
+    }
+
     #[test]
     fn empty_world_produces_empty_draw_input() {
         let world = World::new();
@@ -280,6 +576,49 @@ mod tests {
         );
     }
 
+    #[test]
+    fn interpolates_sprites_with_previous_transform() {
+        let mut world = World::new();
+        let sprite = Sprite {
+            atlas: "main".into(),
+            uv: [0.0, 1.0, 0.0, 1.0],
+        };
+        let previous = PreviousTransform {
+            position: [0.0, 0.0, 0.0],
+            rotation: 0.0,
+        };
+        world.spawn((
+            Transform::with_rotation([4.0, 2.0, 0.0], [1.0, 1.0], 1.0),
+            sprite.clone(),
+            previous.clone(),
+        ));
+        world.spawn((
+            Transform::with_rotation([8.0, 0.0, 0.0], [1.0, 1.0], 1.0),
+            sprite,
+            previous,
+            NoInterpolation,
+        ));
+        let mut resources = resources_with_camera();
+        resources.insert(InterpolationAlpha(0.25));
+        let mut extractor = RenderWorldExtractor::default();
+
+        let frame = extractor.extract(&world, &resources).unwrap();
+        let (_, sprites) = frame.sprite_batches().next().unwrap();
+        let blended = sprites
+            .iter()
+            .find(|sprite| sprite.position[0] < 5.0)
+            .unwrap();
+        let snapped = sprites
+            .iter()
+            .find(|sprite| sprite.position[0] > 5.0)
+            .unwrap();
+
+        assert_eq!(blended.position, [1.0, 0.5, 0.0]);
+        assert_eq!(blended.rotation, 0.25);
+        assert_eq!(snapped.position, [8.0, 0.0, 0.0]);
+        assert_eq!(snapped.rotation, 1.0);
+    }
+
     #[test]
     fn missing_atlas_reports_entity_and_name() {
         let mut world = World::new();
@@ -0,0 +1,379 @@
+use std::collections::BTreeMap;
+use std::fmt;
+use std::path::Path;
+
+use winit::event::MouseButton;
+use winit::keyboard::KeyCode;
+
+use crate::resources::input::key_from_name;
+use crate::resources::Input;
+
+/// Action bound to Escape by default; the engine exits when it is pressed.
+pub const EXIT_ACTION: &str = "exit";
+
+/// A single physical input that can trigger an action.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum Binding {
+    Key(KeyCode),
+    Mouse(MouseButton),
+}
+
+impl Binding {
+    fn pressed(&self, input: &Input) -> bool {
+        match *self {
+            Binding::Key(key) => input.key_pressed(key),
+            Binding::Mouse(button) => input.mouse_pressed(button),
+        }
+    }
+
+    fn just_pressed(&self, input: &Input) -> bool {
+        match *self {
+            Binding::Key(key) => input.key_just_pressed(key),
+            Binding::Mouse(button) => input.mouse_just_pressed(button),
+        }
+    }
+
+    fn just_released(&self, input: &Input) -> bool {
+        match *self {
+            Binding::Key(key) => input.key_just_released(key),
+            Binding::Mouse(button) => input.mouse_just_released(button),
+        }
+    }
+
+    fn parse(text: &str) -> Option<Self> {
+        let (kind, name) = text.trim().split_once(':')?;
+        match kind.trim() {
+            "Key" => key_from_name(name.trim()).map(Binding::Key),
+            "Mouse" => mouse_from_name(name.trim()).map(Binding::Mouse),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for Binding {
+    fn fmt(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Binding::Key(key) => write!(formatter, "Key:{key:?}"),
+            Binding::Mouse(MouseButton::Other(index)) => write!(formatter, "Mouse:{index}"),
+            Binding::Mouse(button) => write!(formatter, "Mouse:{button:?}"),
+        }
+    }
+}
+
+/// Pair of bindings that drive an axis towards -1.0 and +1.0.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct AxisBinding {
+    pub negative: Binding,
+    pub positive: Binding,
+}
+
+#[derive(Debug)]
+pub enum ActionMapError {
+    Io(std::io::Error),
+    Parse { line: usize, message: String },
+}
+
+impl fmt::Display for ActionMapError {
+    fn fmt(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Io(error) => write!(formatter, "failed to access binding config: {error}"),
+            Self::Parse { line, message } => {
+                write!(
+                    formatter,
+                    "invalid binding config at line {line}: {message}"
+                )
+            }
+        }
+    }
+}
+
+impl std::error::Error for ActionMapError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Io(error) => Some(error),
+            Self::Parse { .. } => None,
+        }
+    }
+}
+
+impl From<std::io::Error> for ActionMapError {
+    fn from(value: std::io::Error) -> Self {
+        Self::Io(value)
+    }
+}
+
+/// Named actions mapped to keys and mouse buttons, queried against `Input`.
+///
+/// The text config has one action per line:
+///
+/// ```text
+/// # comment
+/// pause = Key:KeyP, Key:Escape
+/// fire = Mouse:Left
+/// axis move_x = Key:KeyA / Key:KeyD, Key:ArrowLeft / Key:ArrowRight
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct ActionMap {
+    buttons: BTreeMap<String, Vec<Binding>>,
+    axes: BTreeMap<String, Vec<AxisBinding>>,
+}
+
+impl ActionMap {
+    /// Adds a binding to `action`, keeping any bindings it already has.
+    pub fn bind(&mut self, action: impl Into<String>, binding: Binding) {
+        let bindings = self.buttons.entry(action.into()).or_default();
+        if !bindings.contains(&binding) {
+            bindings.push(binding);
+        }
+    }
+
+    /// Replaces all bindings of `action`.
+    pub fn rebind(&mut self, action: impl Into<String>, bindings: Vec<Binding>) {
+        self.buttons.insert(action.into(), bindings);
+    }
+
+    pub fn bind_axis(&mut self, axis: impl Into<String>, negative: Binding, positive: Binding) {
+        self.axes
+            .entry(axis.into())
+            .or_default()
+            .push(AxisBinding { negative, positive });
+    }
+
+    /// Removes an action or axis and all of its bindings.
+    pub fn unbind(&mut self, action: &str) {
+        self.buttons.remove(action);
+        self.axes.remove(action);
+    }
+
+    pub fn bindings(&self, action: &str) -> &[Binding] {
+        self.buttons.get(action).map_or(&[], Vec::as_slice)
+    }
+
+    pub fn axis_bindings(&self, axis: &str) -> &[AxisBinding] {
+        self.axes.get(axis).map_or(&[], Vec::as_slice)
+    }
+
+    /// Whether `binding` triggers `action`, for matching against a single window event.
+    pub fn binds(&self, action: &str, binding: Binding) -> bool {
+        self.bindings(action).contains(&binding)
+    }
+
+    pub fn pressed(&self, input: &Input, action: &str) -> bool {
+        self.bindings(action)
+            .iter()
+            .any(|binding| binding.pressed(input))
+    }
+
+    pub fn just_pressed(&self, input: &Input, action: &str) -> bool {
+        self.bindings(action)
+            .iter()
+            .any(|binding| binding.just_pressed(input))
+    }
+
+    pub fn just_released(&self, input: &Input, action: &str) -> bool {
+        self.bindings(action)
+            .iter()
+            .any(|binding| binding.just_released(input))
+    }
+
+    /// Axis value in `[-1.0, 1.0]` summed over all of the axis' key pairs.
+    pub fn axis(&self, input: &Input, axis: &str) -> f32 {
+        self.axis_bindings(axis)
+            .iter()
+            .map(|pair| {
+                pair.positive.pressed(input) as i32 as f32
+                    - pair.negative.pressed(input) as i32 as f32
+            })
+            .sum::<f32>()
+            .clamp(-1.0, 1.0)
+    }
+
+    /// Replaces the bindings of every action and axis defined in `overrides`.
+    pub fn merge(&mut self, overrides: ActionMap) {
+        self.buttons.extend(overrides.buttons);
+        self.axes.extend(overrides.axes);
+    }
+
+    pub fn from_config(config: &str) -> Result<Self, ActionMapError> {
+        let mut actions = ActionMap::default();
+
+        for (index, line) in config.lines().enumerate() {
+            let line_number = index + 1;
+            let parse_error = |message: String| ActionMapError::Parse {
+                line: line_number,
+                message,
+            };
+
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let (name, bindings) = line
+                .split_once('=')
+                .ok_or_else(|| parse_error("expected 'action = bindings'".to_string()))?;
+            let bindings = bindings.split(',').map(str::trim).filter(|b| !b.is_empty());
+
+            if let Some(axis) = name.trim().strip_prefix("axis ") {
+                let axis = axis.trim();
+                let mut pairs = Vec::new();
+                for pair in bindings {
+                    let (negative, positive) = pair.split_once('/').ok_or_else(|| {
+                        parse_error(format!(
+                            "axis binding '{pair}' must be 'negative / positive'"
+                        ))
+                    })?;
+                    let negative = Binding::parse(negative)
+                        .ok_or_else(|| parse_error(format!("unknown binding '{negative}'")))?;
+                    let positive = Binding::parse(positive)
+                        .ok_or_else(|| parse_error(format!("unknown binding '{positive}'")))?;
+                    pairs.push(AxisBinding { negative, positive });
+                }
+                actions.axes.insert(axis.to_string(), pairs);
+            } else {
+                let bindings = bindings
+                    .map(|binding| {
+                        Binding::parse(binding)
+                            .ok_or_else(|| parse_error(format!("unknown binding '{binding}'")))
+                    })
+                    .collect::<Result<Vec<_>, _>>()?;
+                actions.buttons.insert(name.trim().to_string(), bindings);
+            }
+        }
+
+        Ok(actions)
+    }
+
+    pub fn to_config(&self) -> String {
+        let mut config = String::new();
+        for (action, bindings) in &self.buttons {
+            let bindings = bindings
+                .iter()
+                .map(Binding::to_string)
+                .collect::<Vec<_>>()
+                .join(", ");
+            config.push_str(&format!("{action} = {bindings}\n"));
+        }
+        for (axis, pairs) in &self.axes {
+            let pairs = pairs
+                .iter()
+                .map(|pair| format!("{} / {}", pair.negative, pair.positive))
+                .collect::<Vec<_>>()
+                .join(", ");
+            config.push_str(&format!("axis {axis} = {pairs}\n"));
+        }
+        config
+    }
+
+    pub fn load_file(path: impl AsRef<Path>) -> Result<Self, ActionMapError> {
+        Self::from_config(&std::fs::read_to_string(path)?)
+    }
+
+    pub fn save_file(&self, path: impl AsRef<Path>) -> Result<(), ActionMapError> {
+        std::fs::write(path, self.to_config())?;
+        Ok(())
+    }
+}
+
+fn mouse_from_name(name: &str) -> Option<MouseButton> {
+    match name {
+        "Left" => Some(MouseButton::Left),
+        "Right" => Some(MouseButton::Right),
+        "Middle" => Some(MouseButton::Middle),
+        "Back" => Some(MouseButton::Back),
+        "Forward" => Some(MouseButton::Forward),
+        other => other.parse().ok().map(MouseButton::Other),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn action_triggers_from_any_binding() {
+        let mut actions = ActionMap::default();
+        actions.bind("jump", Binding::Key(KeyCode::Space));
+        actions.bind("jump", Binding::Mouse(MouseButton::Left));
+        let mut input = Input::default();
+
+        input.press_mouse(MouseButton::Left);
+
+        assert!(actions.pressed(&input, "jump"));
+        assert!(actions.just_pressed(&input, "jump"));
+        assert!(!actions.pressed(&input, "missing"));
+    }
+
+    #[test]
+    fn axis_combines_key_pairs() {
+        let mut actions = ActionMap::default();
+        actions.bind_axis(
+            "move_x",
+            Binding::Key(KeyCode::KeyA),
+            Binding::Key(KeyCode::KeyD),
+        );
+        actions.bind_axis(
+            "move_x",
+            Binding::Key(KeyCode::ArrowLeft),
+            Binding::Key(KeyCode::ArrowRight),
+        );
+        let mut input = Input::default();
+
+        input.press_key(KeyCode::KeyD);
+        input.press_key(KeyCode::ArrowRight);
+        assert_eq!(actions.axis(&input, "move_x"), 1.0);
+
+        input.press_key(KeyCode::KeyA);
+        assert_eq!(actions.axis(&input, "move_x"), 1.0);
+        input.press_key(KeyCode::ArrowLeft);
+        assert_eq!(actions.axis(&input, "move_x"), 0.0);
+    }
+
+    #[test]
+    fn config_round_trips() {
+        let mut actions = ActionMap::default();
+        actions.bind("pause", Binding::Key(KeyCode::KeyP));
+        actions.bind("pause", Binding::Key(KeyCode::Escape));
+        actions.bind("fire", Binding::Mouse(MouseButton::Other(4)));
+        actions.bind_axis(
+            "move_x",
+            Binding::Key(KeyCode::KeyA),
+            Binding::Key(KeyCode::KeyD),
+        );
+
+        let parsed = ActionMap::from_config(&actions.to_config()).unwrap();
+
+        assert_eq!(
+            parsed.bindings("pause"),
+            [Binding::Key(KeyCode::KeyP), Binding::Key(KeyCode::Escape)]
+        );
+        assert_eq!(
+            parsed.bindings("fire"),
+            [Binding::Mouse(MouseButton::Other(4))]
+        );
+        assert_eq!(
+            parsed.axis_bindings("move_x"),
+            actions.axis_bindings("move_x")
+        );
+    }
+
+    #[test]
+    fn parse_errors_report_line() {
+        let error =
+            ActionMap::from_config("# bindings\npause = Key:KeyP\nreset = Key:Nope\n").unwrap_err();
+
+        assert!(matches!(error, ActionMapError::Parse { line: 3, .. }));
+    }
+
+    #[test]
+    fn merge_replaces_only_overridden_actions() {
+        let mut actions = ActionMap::default();
+        actions.bind("pause", Binding::Key(KeyCode::KeyP));
+        actions.bind("reset", Binding::Key(KeyCode::KeyR));
+
+        actions.merge(ActionMap::from_config("pause = Key:Space").unwrap());
+
+        assert!(actions.binds("pause", Binding::Key(KeyCode::Space)));
+        assert!(!actions.binds("pause", Binding::Key(KeyCode::KeyP)));
+        assert!(actions.binds("reset", Binding::Key(KeyCode::KeyR)));
+    }
+}
@@ -0,0 +1,134 @@
+use hecs::{Bundle, CommandBuffer, Component, DynamicBundle, Entity, World};
+
+/// Queue of structural world changes recorded by systems.
+///
+/// Systems iterating with `query_mut` cannot spawn or despawn entities, so they record
+/// the change here instead. The dispatcher applies the queue after each system or at
+/// explicit `apply_commands` sync points, and the engine flushes whatever is left at the
+/// end of every fixed step and frame.
+#[derive(Default)]
+pub struct Commands {
+    buffer: CommandBuffer,
+    queued: usize,
+}
+
+impl Commands {
+    /// Queue a new entity. Use `spawn_reserved` when its id is needed right away.
+    pub fn spawn(&mut self, components: impl DynamicBundle) {
+        self.buffer.spawn(components);
+        self.queued += 1;
+    }
+
+    /// Reserve an entity id now and queue its components for the next flush.
+    ///
+    /// Only needs a shared `World`, so it works inside `World::query` loops.
+    pub fn spawn_reserved(&mut self, world: &World, components: impl DynamicBundle) -> Entity {
+        let entity = world.reserve_entity();
+        self.insert(entity, components);
+        entity
+    }
+
+    /// Queue components to add to `entity`, replacing existing ones of the same type.
+    pub fn insert(&mut self, entity: Entity, components: impl DynamicBundle) {
+        self.buffer.insert(entity, components);
+        self.queued += 1;
+    }
+
+    pub fn insert_one(&mut self, entity: Entity, component: impl Component) {
+        self.buffer.insert_one(entity, component);
+        self.queued += 1;
+    }
+
+    /// Queue removal of the bundle `T` from `entity`.
+    pub fn remove<T: Bundle + 'static>(&mut self, entity: Entity) {
+        self.buffer.remove::<T>(entity);
+        self.queued += 1;
+    }
+
+    pub fn remove_one<T: Component>(&mut self, entity: Entity) {
+        self.buffer.remove_one::<T>(entity);
+        self.queued += 1;
+    }
+
+    pub fn despawn(&mut self, entity: Entity) {
+        self.buffer.despawn(entity);
+        self.queued += 1;
+    }
+
+    /// Queue despawning `entity` and every descendant it has right now.
+    pub fn despawn_recursive(&mut self, world: &World, entity: Entity) {
+        for descendant in crate::hierarchy::descendants(world, entity) {
+            self.despawn(descendant);
+        }
+        self.despawn(entity);
+    }
+
+    /// Number of operations waiting for the next flush.
+    pub fn len(&self) -> usize {
+        self.queued
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.queued == 0
+    }
+
+    /// Apply every queued operation in order. Operations on despawned entities are skipped.
+    pub fn apply(&mut self, world: &mut World) {
+        self.buffer.run_on(world);
+        self.queued = 0;
+    }
+
+    /// Drop every queued operation without applying it.
+    pub fn clear(&mut self) {
+        self.buffer.clear();
+        self.queued = 0;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn applies_queued_operations_in_order() {
+        let mut world = World::new();
+        let doomed = world.spawn((1u32,));
+        let kept = world.spawn((2u32, 'k'));
+        let mut commands = Commands::default();
+
+        commands.despawn(doomed);
+        commands.insert_one(kept, 3.0f32);
+        commands.remove_one::<char>(kept);
+        commands.spawn((4u32,));
+        // Inserting into an entity despawned earlier in the queue is ignored.
+        commands.insert_one(doomed, 'x');
+        assert_eq!(commands.len(), 5);
+
+        commands.apply(&mut world);
+
+        assert!(commands.is_empty());
+        assert!(!world.contains(doomed));
+        assert_eq!(*world.get::<&f32>(kept).unwrap(), 3.0);
+        assert!(world.get::<&char>(kept).is_err());
+        assert_eq!(world.query_mut::<&u32>().into_iter().count(), 2);
+    }
+
+    #[test]
+    fn reserved_entities_receive_components_on_apply() {
+        let mut world = World::new();
+        let mut commands = Commands::default();
+        let parent = world.spawn((0u32,));
+
+        let child = {
+            let mut query = world.query::<&u32>();
+            let (_, value) = query.iter().next().unwrap();
+            commands.spawn_reserved(&world, (*value + 1, parent))
+        };
+        assert!(world.get::<&u32>(child).is_err());
+
+        commands.apply(&mut world);
+
+        assert_eq!(*world.get::<&u32>(child).unwrap(), 1);
+        assert_eq!(*world.get::<&Entity>(child).unwrap(), parent);
+    }
+}
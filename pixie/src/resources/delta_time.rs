@@ -1,2 +1,4 @@
+/// Mirror of `Time::delta()` inserted by the engine for systems written before `Time`.
+/// Built-in systems read `Time`, which also offers unscaled and elapsed clocks.
 #[derive(Default)]
-pub struct DeltaTime(pub f32);
\ No newline at end of file
+pub struct DeltaTime(pub f32);
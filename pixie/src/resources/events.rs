@@ -0,0 +1,215 @@
+use std::collections::VecDeque;
+use std::marker::PhantomData;
+
+use crate::atlas::AtlasId;
+
+/// Point in the frame loop where the engine swaps event buffers.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum EventBoundary {
+    FixedStep,
+    Frame,
+}
+
+/// Ids of the first event sent after each of the last two boundaries of one kind.
+#[derive(Clone, Copy, Default)]
+struct BufferMarks {
+    previous: usize,
+    current: usize,
+}
+
+impl BufferMarks {
+    fn swap(&mut self, next_id: usize) {
+        self.previous = self.current;
+        self.current = next_id;
+    }
+}
+
+/// Typed, double-buffered event queue stored in the `ResourceContainer`.
+///
+/// Register a channel with `ResourceContainer::add_events` so the engine swaps it.
+/// Buffers are swapped after every fixed step and every frame, and an event is only
+/// dropped once it has outlived two swaps of both kinds. Fixed-step systems therefore
+/// see events sent from `update`, and `update` sees every event sent by fixed steps,
+/// even when a frame runs several of them.
+pub struct Events<T> {
+    events: VecDeque<T>,
+    /// Id of `events[0]`.
+    first_id: usize,
+    fixed_marks: BufferMarks,
+    frame_marks: BufferMarks,
+}
+
+impl<T> Events<T> {
+    pub fn send(&mut self, event: T) {
+        self.events.push_back(event);
+    }
+
+    pub fn send_batch(&mut self, events: impl IntoIterator<Item = T>) {
+        self.events.extend(events);
+    }
+
+    /// A reader that only sees events sent from now on.
+    pub fn reader(&self) -> EventReader<T> {
+        EventReader {
+            next_id: self.next_id(),
+            marker: PhantomData,
+        }
+    }
+
+    /// Events the reader has not seen yet. Advances the reader past all of them.
+    pub fn read<'a>(&'a self, reader: &mut EventReader<T>) -> impl Iterator<Item = &'a T> + 'a {
+        let start = reader.next_id.max(self.first_id) - self.first_id;
+        reader.next_id = self.next_id();
+        self.events.range(start..)
+    }
+
+    /// All retained events, oldest first.
+    pub fn iter(&self) -> impl Iterator<Item = &T> + '_ {
+        self.events.iter()
+    }
+
+    pub fn len(&self) -> usize {
+        self.events.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.events.is_empty()
+    }
+
+    pub fn clear(&mut self) {
+        self.first_id = self.next_id();
+        self.events.clear();
+    }
+
+    pub(crate) fn swap_buffers(&mut self, boundary: EventBoundary) {
+        let next_id = self.next_id();
+        match boundary {
+            EventBoundary::FixedStep => self.fixed_marks.swap(next_id),
+            EventBoundary::Frame => self.frame_marks.swap(next_id),
+        }
+
+        let keep_from = self.fixed_marks.previous.min(self.frame_marks.previous);
+        let expired = keep_from.saturating_sub(self.first_id);
+        self.events.drain(..expired);
+        self.first_id += expired;
+    }
+
+    fn next_id(&self) -> usize {
+        self.first_id + self.events.len()
+    }
+}
+
+impl<T> Default for Events<T> {
+    fn default() -> Self {
+        Self {
+            events: VecDeque::new(),
+            first_id: 0,
+            fixed_marks: BufferMarks::default(),
+            frame_marks: BufferMarks::default(),
+        }
+    }
+}
+
+/// Read cursor into an `Events<T>` channel.
+///
+/// Keep one per consumer, e.g. as an application field. A default reader starts with
+/// every event still retained by the channel.
+pub struct EventReader<T> {
+    next_id: usize,
+    marker: PhantomData<fn() -> T>,
+}
+
+impl<T> Default for EventReader<T> {
+    fn default() -> Self {
+        Self {
+            next_id: 0,
+            marker: PhantomData,
+        }
+    }
+}
+
+impl<T> Clone for EventReader<T> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<T> Copy for EventReader<T> {}
+
+/// Sent by the windowed engine when the surface changes size, in physical pixels.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct WindowResized {
+    pub width: u32,
+    pub height: u32,
+}
+
+/// Sent when the window gains (`true`) or loses (`false`) keyboard focus.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct WindowFocused(pub bool);
+
+/// Sent once a registered texture atlas has been uploaded and can be drawn.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct AtlasLoaded(pub AtlasId);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn read_all(events: &Events<u32>, reader: &mut EventReader<u32>) -> Vec<u32> {
+        events.read(reader).copied().collect()
+    }
+
+    #[test]
+    fn readers_see_each_event_once() {
+        let mut events = Events::default();
+        let mut early = EventReader::default();
+        events.send(1);
+        let mut late = events.reader();
+        events.send_batch([2, 3]);
+
+        assert_eq!(read_all(&events, &mut early), [1, 2, 3]);
+        assert_eq!(read_all(&events, &mut late), [2, 3]);
+        assert!(read_all(&events, &mut early).is_empty());
+    }
+
+    #[test]
+    fn fixed_step_events_survive_until_frame_update() {
+        let mut events = Events::default();
+        let mut frame_reader = EventReader::default();
+
+        // Two fixed steps in one frame, each sending one event.
+        events.send(1);
+        events.swap_buffers(EventBoundary::FixedStep);
+        events.send(2);
+        events.swap_buffers(EventBoundary::FixedStep);
+        assert_eq!(read_all(&events, &mut frame_reader), [1, 2]);
+        events.swap_buffers(EventBoundary::Frame);
+
+        // Gone after a second frame and fixed step have passed.
+        events.swap_buffers(EventBoundary::FixedStep);
+        events.swap_buffers(EventBoundary::Frame);
+        assert!(events.is_empty());
+    }
+
+    #[test]
+    fn frame_events_wait_for_next_fixed_step() {
+        let mut events = Events::default();
+        let mut fixed_reader = EventReader::default();
+
+        events.send(7);
+        // Frames without a fixed step keep the event alive.
+        events.swap_buffers(EventBoundary::Frame);
+        events.swap_buffers(EventBoundary::Frame);
+        events.swap_buffers(EventBoundary::Frame);
+        assert_eq!(read_all(&events, &mut fixed_reader), [7]);
+
+        events.swap_buffers(EventBoundary::FixedStep);
+        events.swap_buffers(EventBoundary::FixedStep);
+        assert!(events.is_empty());
+
+        // Readers that fell behind skip what was dropped.
+        events.send(8);
+        let mut stale = EventReader::default();
+        assert_eq!(read_all(&events, &mut stale), [8]);
+    }
+}
@@ -0,0 +1,323 @@
+use std::collections::{BTreeMap, HashMap, VecDeque};
+use std::fmt::Write;
+use std::time::Duration;
+
+use instant::Instant;
+
+use super::ResourceContainer;
+
+/// Rolling timing summary of one system or engine phase.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct TimingSummary {
+    /// Samples in the window, at most `FrameStats::window`.
+    pub samples: usize,
+    pub average: Duration,
+    pub p50: Duration,
+    pub p95: Duration,
+    pub p99: Duration,
+    pub max: Duration,
+}
+
+/// Spans kept by `FrameStats::start_trace` when no capacity is given, about four minutes
+/// of a schedule with twenty systems at 60 Hz.
+pub const DEFAULT_TRACE_CAPACITY: usize = 300_000;
+
+/// One span of a Chrome trace capture.
+#[derive(Clone, Copy, Debug, PartialEq)]
+struct TraceEvent {
+    /// Index into `Trace::names`.
+    name: usize,
+    /// Offset from `FrameStats::origin`.
+    start: Duration,
+    duration: Duration,
+    thread: usize,
+}
+
+/// A running trace capture: the newest `capacity` spans, with each span name stored once.
+struct Trace {
+    names: Vec<String>,
+    name_ids: HashMap<String, usize>,
+    events: VecDeque<TraceEvent>,
+    capacity: usize,
+    dropped: usize,
+}
+
+impl Trace {
+    fn new(capacity: usize) -> Self {
+        Self {
+            names: Vec::new(),
+            name_ids: HashMap::new(),
+            events: VecDeque::new(),
+            capacity: capacity.max(1),
+            dropped: 0,
+        }
+    }
+
+    fn intern(&mut self, name: &str) -> usize {
+        if let Some(&id) = self.name_ids.get(name) {
+            return id;
+        }
+        let id = self.names.len();
+        self.names.push(name.to_string());
+        self.name_ids.insert(name.to_string(), id);
+        id
+    }
+
+    fn push(&mut self, event: TraceEvent) {
+        if self.events.len() == self.capacity {
+            self.events.pop_front();
+            self.dropped += 1;
+        }
+        self.events.push_back(event);
+    }
+}
+
+/// Wall-clock timings of every dispatcher system and engine phase.
+///
+/// The dispatchers record each system under its label or name, and the engine records
+/// `simulation`, `render/extract`, `render/upload`, `render/acquire`, `render/submit` and
+/// `render/present`. Only the last `window` samples of each name are kept. While a trace
+/// capture is running, the newest spans up to its capacity are also kept for
+/// `chrome_trace_json`.
+pub struct FrameStats {
+    window: usize,
+    timings: BTreeMap<String, VecDeque<Duration>>,
+    origin: Instant,
+    trace: Option<Trace>,
+}
+
+impl FrameStats {
+    pub fn new(window: usize) -> Self {
+        Self {
+            window: window.max(1),
+            timings: BTreeMap::new(),
+            origin: Instant::now(),
+            trace: None,
+        }
+    }
+
+    pub fn window(&self) -> usize {
+        self.window
+    }
+
+    /// Add a span that started at `start` on the main thread.
+    pub fn record(&mut self, name: &str, start: Instant, duration: Duration) {
+        self.record_on_thread(name, start, duration, 0);
+    }
+
+    /// Add a span; `thread` becomes the trace row it is drawn on.
+    pub(crate) fn record_on_thread(
+        &mut self,
+        name: &str,
+        start: Instant,
+        duration: Duration,
+        thread: usize,
+    ) {
+        let samples = match self.timings.get_mut(name) {
+            Some(samples) => samples,
+            None => self.timings.entry(name.to_string()).or_default(),
+        };
+        if samples.len() == self.window {
+            samples.pop_front();
+        }
+        samples.push_back(duration);
+
+        if let Some(trace) = &mut self.trace {
+            let name = trace.intern(name);
+            trace.push(TraceEvent {
+                name,
+                start: start.saturating_duration_since(self.origin),
+                duration,
+                thread,
+            });
+        }
+    }
+
+    /// Names with at least one sample, in alphabetical order.
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.timings.keys().map(String::as_str)
+    }
+
+    pub fn average(&self, name: &str) -> Option<Duration> {
+        let samples = self.timings.get(name)?;
+        Some(samples.iter().sum::<Duration>() / samples.len() as u32)
+    }
+
+    /// Nearest-rank percentile of the window, `percentile` from 0 to 100.
+    pub fn percentile(&self, name: &str, percentile: f32) -> Option<Duration> {
+        let mut sorted: Vec<Duration> = self.timings.get(name)?.iter().copied().collect();
+        sorted.sort_unstable();
+        Some(nearest_rank(&sorted, percentile))
+    }
+
+    pub fn summary(&self, name: &str) -> Option<TimingSummary> {
+        let mut sorted: Vec<Duration> = self.timings.get(name)?.iter().copied().collect();
+        sorted.sort_unstable();
+        Some(TimingSummary {
+            samples: sorted.len(),
+            average: sorted.iter().sum::<Duration>() / sorted.len() as u32,
+            p50: nearest_rank(&sorted, 50.0),
+            p95: nearest_rank(&sorted, 95.0),
+            p99: nearest_rank(&sorted, 99.0),
+            max: *sorted.last().expect("names always have a sample"),
+        })
+    }
+
+    /// Drop every sample but keep the trace capture running.
+    pub fn clear(&mut self) {
+        self.timings.clear();
+    }
+
+    /// Start keeping spans for `chrome_trace_json`, dropping any earlier capture.
+    /// Keeps the newest `DEFAULT_TRACE_CAPACITY` spans.
+    pub fn start_trace(&mut self) {
+        self.start_trace_with_capacity(DEFAULT_TRACE_CAPACITY);
+    }
+
+    /// `start_trace` that keeps the newest `capacity` spans; older ones are dropped.
+    pub fn start_trace_with_capacity(&mut self, capacity: usize) {
+        self.trace = Some(Trace::new(capacity));
+    }
+
+    pub fn stop_trace(&mut self) {
+        self.trace = None;
+    }
+
+    pub fn is_tracing(&self) -> bool {
+        self.trace.is_some()
+    }
+
+    /// Spans the running capture dropped to stay within its capacity.
+    pub fn dropped_trace_events(&self) -> usize {
+        self.trace.as_ref().map_or(0, |trace| trace.dropped)
+    }
+
+    /// The captured spans in Chrome's trace-event format, viewable in `chrome://tracing`
+    /// or Perfetto. Empty when no capture is running.
+    pub fn chrome_trace_json(&self) -> String {
+        let mut json = String::from("{\"traceEvents\":[");
+        let events = self.trace.iter().flat_map(|trace| {
+            trace
+                .events
+                .iter()
+                .map(|event| (trace.names[event.name].as_str(), event))
+        });
+        for (index, (name, event)) in events.enumerate() {
+            if index > 0 {
+                json.push(',');
+            }
+            json.push_str("\n{\"name\":\"");
+            escape_json(&mut json, name);
+            let _ = write!(
+                json,
+                "\",\"ph\":\"X\",\"ts\":{:.3},\"dur\":{:.3},\"pid\":1,\"tid\":{}}}",
+                event.start.as_secs_f64() * 1.0e6,
+                event.duration.as_secs_f64() * 1.0e6,
+                event.thread
+            );
+        }
+        json.push_str("\n],\"displayTimeUnit\":\"ms\"}\n");
+        json
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn write_chrome_trace(&self, path: impl AsRef<std::path::Path>) -> std::io::Result<()> {
+        std::fs::write(path, self.chrome_trace_json())
+    }
+}
+
+impl Default for FrameStats {
+    /// Two seconds of samples at 60 Hz.
+    fn default() -> Self {
+        FrameStats::new(120)
+    }
+}
+
+/// Record the time since `start` if the container holds `FrameStats`.
+pub(crate) fn record_since(resources: &mut ResourceContainer, name: &str, start: Instant) {
+    let duration = start.elapsed();
+    if let Some(stats) = resources.get_mut::<FrameStats>() {
+        stats.record(name, start, duration);
+    }
+}
+
+fn nearest_rank(sorted: &[Duration], percentile: f32) -> Duration {
+    let rank = (percentile.clamp(0.0, 100.0) / 100.0 * sorted.len() as f32).ceil() as usize;
+    sorted[rank.clamp(1, sorted.len()) - 1]
+}
+
+fn escape_json(out: &mut String, text: &str) {
+    for character in text.chars() {
+        match character {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            character if character.is_control() => {
+                let _ = write!(out, "\\u{:04x}", character as u32);
+            }
+            character => out.push(character),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ms(value: u64) -> Duration {
+        Duration::from_millis(value)
+    }
+
+    #[test]
+    fn summarizes_a_rolling_window() {
+        let mut stats = FrameStats::new(4);
+        let now = Instant::now();
+        for value in [100, 1, 2, 3, 4] {
+            stats.record("physics", now, ms(value));
+        }
+
+        // The 100 ms sample fell out of the window
+        let summary = stats.summary("physics").unwrap();
+        assert_eq!(summary.samples, 4);
+        assert_eq!(summary.average, Duration::from_micros(2500));
+        assert_eq!(summary.p50, ms(2));
+        assert_eq!(summary.p99, ms(4));
+        assert_eq!(stats.percentile("physics", 0.0), Some(ms(1)));
+        assert_eq!(stats.average("missing"), None);
+    }
+
+    #[test]
+    fn exports_spans_as_chrome_trace_events() {
+        let mut stats = FrameStats::default();
+        let start = stats.origin + ms(2);
+        stats.record("before capture", start, ms(1));
+        stats.start_trace();
+        stats.record("say \"hi\"", start, Duration::from_micros(1500));
+        stats.record_on_thread("worker", start, ms(1), 3);
+
+        let json = stats.chrome_trace_json();
+
+        assert!(!json.contains("before capture"));
+        assert!(json.contains(
+            r#"{"name":"say \"hi\"","ph":"X","ts":2000.000,"dur":1500.000,"pid":1,"tid":0}"#
+        ));
+        assert!(json.contains(r#""tid":3"#));
+        assert!(json.starts_with(r#"{"traceEvents":["#));
+    }
+
+    #[test]
+    fn trace_keeps_the_newest_spans_up_to_its_capacity() {
+        let mut stats = FrameStats::default();
+        stats.start_trace_with_capacity(2);
+        let start = stats.origin;
+        for name in ["first", "second", "third", "second"] {
+            stats.record(name, start, ms(1));
+        }
+
+        let json = stats.chrome_trace_json();
+
+        assert_eq!(stats.dropped_trace_events(), 2);
+        assert!(!json.contains("first"));
+        assert_eq!(json.matches("\"name\":").count(), 2);
+        assert_eq!(stats.trace.as_ref().unwrap().names.len(), 3);
+    }
+}
@@ -0,0 +1,61 @@
+use rand::rngs::StdRng;
+use rand::{RngCore, SeedableRng};
+
+/// Engine-owned random number generator, seeded from `EngineConfig::rng_seed`.
+///
+/// Input recordings store the seed, so gameplay that draws from this resource
+/// makes the same choices on replay.
+#[derive(Clone)]
+pub struct GameRng {
+    seed: u64,
+    rng: StdRng,
+}
+
+impl GameRng {
+    pub fn new(seed: u64) -> Self {
+        Self {
+            seed,
+            rng: StdRng::seed_from_u64(seed),
+        }
+    }
+
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+}
+
+impl RngCore for GameRng {
+    fn next_u32(&mut self) -> u32 {
+        self.rng.next_u32()
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        self.rng.next_u64()
+    }
+
+    fn fill_bytes(&mut self, dest: &mut [u8]) {
+        self.rng.fill_bytes(dest);
+    }
+
+    fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), rand::Error> {
+        self.rng.try_fill_bytes(dest)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::Rng;
+
+    #[test]
+    fn same_seed_draws_same_numbers() {
+        let mut first = GameRng::new(42);
+        let mut second = GameRng::new(42);
+
+        let first_draws: Vec<f32> = (0..8).map(|_| first.gen_range(0.0..1.0)).collect();
+        let second_draws: Vec<f32> = (0..8).map(|_| second.gen_range(0.0..1.0)).collect();
+
+        assert_eq!(first_draws, second_draws);
+        assert_eq!(first.seed(), 42);
+    }
+}
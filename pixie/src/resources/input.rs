@@ -0,0 +1,577 @@
+use std::collections::HashSet;
+use std::hash::Hash;
+
+use winit::event::{ElementState, MouseButton, MouseScrollDelta, WindowEvent};
+use winit::keyboard::{KeyCode, PhysicalKey};
+
+/// Pixels per line used to convert touchpad scrolling into wheel lines.
+const PIXELS_PER_LINE: f32 = 16.0;
+
+/// Pressed state and edges for one kind of button.
+///
+/// Edges are tracked twice: once per frame and once per fixed step, so a press that
+/// arrives on a frame without a fixed step is still seen by the next fixed-step system.
+pub struct ButtonInput<T> {
+    pressed: HashSet<T>,
+    just_pressed: HashSet<T>,
+    just_released: HashSet<T>,
+    fixed_just_pressed: HashSet<T>,
+    fixed_just_released: HashSet<T>,
+}
+
+impl<T: Copy + Eq + Hash> ButtonInput<T> {
+    pub fn pressed(&self, button: T) -> bool {
+        self.pressed.contains(&button)
+    }
+
+    pub fn any_pressed(&self) -> bool {
+        !self.pressed.is_empty()
+    }
+
+    pub fn iter_pressed(&self) -> impl Iterator<Item = &T> + '_ {
+        self.pressed.iter()
+    }
+
+    fn press(&mut self, button: T) {
+        if self.pressed.insert(button) {
+            self.just_pressed.insert(button);
+            self.fixed_just_pressed.insert(button);
+        }
+    }
+
+    fn release(&mut self, button: T) {
+        if self.pressed.remove(&button) {
+            self.just_released.insert(button);
+            self.fixed_just_released.insert(button);
+        }
+    }
+
+    fn release_all(&mut self) {
+        let pressed: Vec<T> = self.pressed.iter().copied().collect();
+        for button in pressed {
+            self.release(button);
+        }
+    }
+
+    fn clear_frame(&mut self) {
+        self.just_pressed.clear();
+        self.just_released.clear();
+    }
+
+    fn clear_fixed_step(&mut self) {
+        self.fixed_just_pressed.clear();
+        self.fixed_just_released.clear();
+    }
+}
+
+impl<T> Default for ButtonInput<T> {
+    fn default() -> Self {
+        Self {
+            pressed: HashSet::new(),
+            just_pressed: HashSet::new(),
+            just_released: HashSet::new(),
+            fixed_just_pressed: HashSet::new(),
+            fixed_just_released: HashSet::new(),
+        }
+    }
+}
+
+/// Backend-independent input change applied to `Input`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum InputEvent {
+    KeyPressed(KeyCode),
+    KeyReleased(KeyCode),
+    MousePressed(MouseButton),
+    MouseReleased(MouseButton),
+    /// Cursor position in physical pixels.
+    CursorMoved([f32; 2]),
+    CursorLeft,
+    /// Scroll in lines.
+    Wheel([f32; 2]),
+    FocusLost,
+}
+
+impl InputEvent {
+    pub fn from_window_event(event: &WindowEvent) -> Option<Self> {
+        match event {
+            WindowEvent::KeyboardInput {
+                event: key_event, ..
+            } => match (key_event.physical_key, key_event.state) {
+                (PhysicalKey::Code(key), ElementState::Pressed) => Some(Self::KeyPressed(key)),
+                (PhysicalKey::Code(key), ElementState::Released) => Some(Self::KeyReleased(key)),
+                _ => None,
+            },
+            WindowEvent::MouseInput { state, button, .. } => match state {
+                ElementState::Pressed => Some(Self::MousePressed(*button)),
+                ElementState::Released => Some(Self::MouseReleased(*button)),
+            },
+            WindowEvent::CursorMoved { position, .. } => {
+                Some(Self::CursorMoved([position.x as f32, position.y as f32]))
+            }
+            WindowEvent::CursorLeft { .. } => Some(Self::CursorLeft),
+            WindowEvent::MouseWheel { delta, .. } => Some(Self::Wheel(match delta {
+                MouseScrollDelta::LineDelta(x, y) => [*x, *y],
+                MouseScrollDelta::PixelDelta(position) => [
+                    position.x as f32 / PIXELS_PER_LINE,
+                    position.y as f32 / PIXELS_PER_LINE,
+                ],
+            })),
+            WindowEvent::Focused(false) => Some(Self::FocusLost),
+            _ => None,
+        }
+    }
+}
+
+/// Keyboard and mouse state built by the engine from window events.
+///
+/// `just_pressed`/`just_released` report edges since the previous frame when read from
+/// `Application::update`, and since the previous fixed step when read from fixed-step systems.
+#[derive(Default)]
+pub struct Input {
+    keys: ButtonInput<KeyCode>,
+    mouse_buttons: ButtonInput<MouseButton>,
+    /// Cursor position in physical pixels, `None` while outside the window.
+    cursor_position: Option<[f32; 2]>,
+    wheel_delta: [f32; 2],
+    fixed_wheel_delta: [f32; 2],
+    in_fixed_step: bool,
+}
+
+impl Input {
+    pub fn key_pressed(&self, key: KeyCode) -> bool {
+        self.keys.pressed(key)
+    }
+
+    pub fn key_just_pressed(&self, key: KeyCode) -> bool {
+        if self.in_fixed_step {
+            self.keys.fixed_just_pressed.contains(&key)
+        } else {
+            self.keys.just_pressed.contains(&key)
+        }
+    }
+
+    pub fn key_just_released(&self, key: KeyCode) -> bool {
+        if self.in_fixed_step {
+            self.keys.fixed_just_released.contains(&key)
+        } else {
+            self.keys.just_released.contains(&key)
+        }
+    }
+
+    /// Whether any key was released since the last frame or fixed step
+    pub fn any_key_just_released(&self) -> bool {
+        if self.in_fixed_step {
+            !self.keys.fixed_just_released.is_empty()
+        } else {
+            !self.keys.just_released.is_empty()
+        }
+    }
+
+    pub fn mouse_pressed(&self, button: MouseButton) -> bool {
+        self.mouse_buttons.pressed(button)
+    }
+
+    pub fn mouse_just_pressed(&self, button: MouseButton) -> bool {
+        if self.in_fixed_step {
+            self.mouse_buttons.fixed_just_pressed.contains(&button)
+        } else {
+            self.mouse_buttons.just_pressed.contains(&button)
+        }
+    }
+
+    pub fn mouse_just_released(&self, button: MouseButton) -> bool {
+        if self.in_fixed_step {
+            self.mouse_buttons.fixed_just_released.contains(&button)
+        } else {
+            self.mouse_buttons.just_released.contains(&button)
+        }
+    }
+
+    pub fn keys(&self) -> &ButtonInput<KeyCode> {
+        &self.keys
+    }
+
+    pub fn mouse_buttons(&self) -> &ButtonInput<MouseButton> {
+        &self.mouse_buttons
+    }
+
+    pub fn cursor_position(&self) -> Option<[f32; 2]> {
+        self.cursor_position
+    }
+
+    /// Scroll accumulated since the last frame or fixed step, in lines.
+    pub fn wheel_delta(&self) -> [f32; 2] {
+        if self.in_fixed_step {
+            self.fixed_wheel_delta
+        } else {
+            self.wheel_delta
+        }
+    }
+
+    pub fn press_key(&mut self, key: KeyCode) {
+        self.keys.press(key);
+    }
+
+    pub fn release_key(&mut self, key: KeyCode) {
+        self.keys.release(key);
+    }
+
+    pub fn press_mouse(&mut self, button: MouseButton) {
+        self.mouse_buttons.press(button);
+    }
+
+    pub fn release_mouse(&mut self, button: MouseButton) {
+        self.mouse_buttons.release(button);
+    }
+
+    /// Updates the state from a window event. Unrelated events are ignored.
+    pub fn handle_event(&mut self, event: &WindowEvent) {
+        if let Some(event) = InputEvent::from_window_event(event) {
+            self.apply(event);
+        }
+    }
+
+    pub fn apply(&mut self, event: InputEvent) {
+        match event {
+            InputEvent::KeyPressed(key) => self.press_key(key),
+            InputEvent::KeyReleased(key) => self.release_key(key),
+            InputEvent::MousePressed(button) => self.press_mouse(button),
+            InputEvent::MouseReleased(button) => self.release_mouse(button),
+            InputEvent::CursorMoved(position) => self.cursor_position = Some(position),
+            InputEvent::CursorLeft => self.cursor_position = None,
+            InputEvent::Wheel(delta) => {
+                for total in [&mut self.wheel_delta, &mut self.fixed_wheel_delta] {
+                    total[0] += delta[0];
+                    total[1] += delta[1];
+                }
+            }
+            // Release everything on focus loss so keys don't stick while unfocused
+            InputEvent::FocusLost => {
+                self.keys.release_all();
+                self.mouse_buttons.release_all();
+            }
+        }
+    }
+
+    pub(crate) fn begin_fixed_step(&mut self) {
+        self.in_fixed_step = true;
+    }
+
+    pub(crate) fn end_fixed_step(&mut self) {
+        self.in_fixed_step = false;
+        self.keys.clear_fixed_step();
+        self.mouse_buttons.clear_fixed_step();
+        self.fixed_wheel_delta = [0.0, 0.0];
+    }
+
+    pub(crate) fn end_frame(&mut self) {
+        self.keys.clear_frame();
+        self.mouse_buttons.clear_frame();
+        self.wheel_delta = [0.0, 0.0];
+    }
+}
+
+macro_rules! named_keys {
+    ( $( $key:ident ),* $(,)? ) => {
+        /// Every key code winit reports, with their `Debug` names.
+        ///
+        /// The position in this table is the key's id in input recordings, so only append.
+        pub(crate) const NAMED_KEYS: &[(&str, KeyCode)] = &[
+            $( (stringify!($key), KeyCode::$key), )*
+        ];
+    };
+}
+
+named_keys!(
+    KeyA,
+    KeyB,
+    KeyC,
+    KeyD,
+    KeyE,
+    KeyF,
+    KeyG,
+    KeyH,
+    KeyI,
+    KeyJ,
+    KeyK,
+    KeyL,
+    KeyM,
+    KeyN,
+    KeyO,
+    KeyP,
+    KeyQ,
+    KeyR,
+    KeyS,
+    KeyT,
+    KeyU,
+    KeyV,
+    KeyW,
+    KeyX,
+    KeyY,
+    KeyZ,
+    Digit0,
+    Digit1,
+    Digit2,
+    Digit3,
+    Digit4,
+    Digit5,
+    Digit6,
+    Digit7,
+    Digit8,
+    Digit9,
+    Numpad0,
+    Numpad1,
+    Numpad2,
+    Numpad3,
+    Numpad4,
+    Numpad5,
+    Numpad6,
+    Numpad7,
+    Numpad8,
+    Numpad9,
+    NumpadAdd,
+    NumpadSubtract,
+    NumpadMultiply,
+    NumpadDivide,
+    NumpadDecimal,
+    NumpadEnter,
+    F1,
+    F2,
+    F3,
+    F4,
+    F5,
+    F6,
+    F7,
+    F8,
+    F9,
+    F10,
+    F11,
+    F12,
+    ArrowUp,
+    ArrowDown,
+    ArrowLeft,
+    ArrowRight,
+    Space,
+    Enter,
+    Escape,
+    Tab,
+    Backspace,
+    Delete,
+    Insert,
+    Home,
+    End,
+    PageUp,
+    PageDown,
+    ShiftLeft,
+    ShiftRight,
+    ControlLeft,
+    ControlRight,
+    AltLeft,
+    AltRight,
+    SuperLeft,
+    SuperRight,
+    CapsLock,
+    Minus,
+    Equal,
+    BracketLeft,
+    BracketRight,
+    Backslash,
+    Semicolon,
+    Quote,
+    Backquote,
+    Comma,
+    Period,
+    Slash,
+    // Remaining winit key codes, so recordings capture every key
+    IntlBackslash,
+    IntlRo,
+    IntlYen,
+    ContextMenu,
+    Convert,
+    KanaMode,
+    Lang1,
+    Lang2,
+    Lang3,
+    Lang4,
+    Lang5,
+    NonConvert,
+    Help,
+    NumLock,
+    NumpadBackspace,
+    NumpadClear,
+    NumpadClearEntry,
+    NumpadComma,
+    NumpadEqual,
+    NumpadHash,
+    NumpadMemoryAdd,
+    NumpadMemoryClear,
+    NumpadMemoryRecall,
+    NumpadMemoryStore,
+    NumpadMemorySubtract,
+    NumpadParenLeft,
+    NumpadParenRight,
+    NumpadStar,
+    Fn,
+    FnLock,
+    PrintScreen,
+    ScrollLock,
+    Pause,
+    BrowserBack,
+    BrowserFavorites,
+    BrowserForward,
+    BrowserHome,
+    BrowserRefresh,
+    BrowserSearch,
+    BrowserStop,
+    Eject,
+    LaunchApp1,
+    LaunchApp2,
+    LaunchMail,
+    MediaPlayPause,
+    MediaSelect,
+    MediaStop,
+    MediaTrackNext,
+    MediaTrackPrevious,
+    Power,
+    Sleep,
+    AudioVolumeDown,
+    AudioVolumeMute,
+    AudioVolumeUp,
+    WakeUp,
+    Meta,
+    Hyper,
+    Turbo,
+    Abort,
+    Resume,
+    Suspend,
+    Again,
+    Copy,
+    Cut,
+    Find,
+    Open,
+    Paste,
+    Props,
+    Select,
+    Undo,
+    Hiragana,
+    Katakana,
+    F13,
+    F14,
+    F15,
+    F16,
+    F17,
+    F18,
+    F19,
+    F20,
+    F21,
+    F22,
+    F23,
+    F24,
+    F25,
+    F26,
+    F27,
+    F28,
+    F29,
+    F30,
+    F31,
+    F32,
+    F33,
+    F34,
+    F35,
+);
+
+pub(crate) fn key_from_name(name: &str) -> Option<KeyCode> {
+    NAMED_KEYS
+        .iter()
+        .find(|(key_name, _)| *key_name == name)
+        .map(|(_, key)| *key)
+}
+
+pub(crate) fn key_from_id(id: u16) -> Option<KeyCode> {
+    NAMED_KEYS.get(id as usize).map(|(_, key)| *key)
+}
+
+pub(crate) fn key_id(key: KeyCode) -> Option<u16> {
+    NAMED_KEYS
+        .iter()
+        .position(|(_, named)| *named == key)
+        .map(|id| id as u16)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tracks_key_edges_until_frame_end() {
+        let mut input = Input::default();
+
+        input.press_key(KeyCode::Space);
+        assert!(input.key_pressed(KeyCode::Space));
+        assert!(input.key_just_pressed(KeyCode::Space));
+
+        input.end_frame();
+        assert!(input.key_pressed(KeyCode::Space));
+        assert!(!input.key_just_pressed(KeyCode::Space));
+
+        input.release_key(KeyCode::Space);
+        assert!(!input.key_pressed(KeyCode::Space));
+        assert!(input.key_just_released(KeyCode::Space));
+    }
+
+    #[test]
+    fn repeated_press_is_not_a_new_edge() {
+        let mut input = Input::default();
+        input.press_key(KeyCode::KeyA);
+        input.end_frame();
+
+        input.press_key(KeyCode::KeyA);
+
+        assert!(!input.key_just_pressed(KeyCode::KeyA));
+    }
+
+    #[test]
+    fn fixed_step_sees_press_from_frame_without_fixed_step() {
+        let mut input = Input::default();
+        input.press_mouse(MouseButton::Left);
+        input.end_frame();
+
+        input.begin_fixed_step();
+        assert!(input.mouse_just_pressed(MouseButton::Left));
+        input.end_fixed_step();
+
+        input.begin_fixed_step();
+        assert!(!input.mouse_just_pressed(MouseButton::Left));
+        input.end_fixed_step();
+    }
+
+    #[test]
+    fn focus_loss_releases_held_buttons() {
+        let mut input = Input::default();
+        input.press_key(KeyCode::KeyW);
+        input.press_mouse(MouseButton::Right);
+
+        input.handle_event(&WindowEvent::Focused(false));
+
+        assert!(!input.key_pressed(KeyCode::KeyW));
+        assert!(!input.mouse_pressed(MouseButton::Right));
+        assert!(input.key_just_released(KeyCode::KeyW));
+    }
+
+    #[test]
+    fn accumulates_wheel_delta_per_frame() {
+        let mut input = Input::default();
+        let wheel = |delta| WindowEvent::MouseWheel {
+            device_id: winit::event::DeviceId::dummy(),
+            delta,
+            phase: winit::event::TouchPhase::Moved,
+        };
+
+        input.handle_event(&wheel(MouseScrollDelta::LineDelta(0.0, 1.0)));
+        input.handle_event(&wheel(MouseScrollDelta::LineDelta(0.5, 2.0)));
+        assert_eq!(input.wheel_delta(), [0.5, 3.0]);
+
+        input.end_frame();
+        assert_eq!(input.wheel_delta(), [0.0, 0.0]);
+    }
+}
@@ -0,0 +1,10 @@
+/// Fraction of a fixed step left in the accumulator after the last fixed update.
+///
+/// Written by the engine every frame and used to blend `PreviousTransform` towards `Transform`.
+pub struct InterpolationAlpha(pub f32);
+
+impl Default for InterpolationAlpha {
+    fn default() -> Self {
+        InterpolationAlpha(1.0)
+    }
+}
@@ -1,7 +1,30 @@
+pub use action_map::{ActionMap, ActionMapError, AxisBinding, Binding, EXIT_ACTION};
 pub use camera::Camera;
+pub use commands::Commands;
 pub use delta_time::DeltaTime;
-pub use resource_container::ResourceContainer;
+pub(crate) use events::EventBoundary;
+pub use events::{AtlasLoaded, EventReader, Events, WindowFocused, WindowResized};
+pub(crate) use frame_stats::record_since;
+pub use frame_stats::{FrameStats, TimingSummary, DEFAULT_TRACE_CAPACITY};
+pub use game_rng::GameRng;
+pub(crate) use input::{key_from_id, key_id};
+pub use input::{ButtonInput, Input, InputEvent};
+pub use interpolation_alpha::InterpolationAlpha;
+pub use resource_container::{ResourceContainer, ResourceError};
+pub use time::Time;
+pub use timers::{Timer, TimerFired, TimerMode, Timers};
+pub use world_names::WorldNames;
 
+mod action_map;
 mod camera;
+mod commands;
 mod delta_time;
+mod events;
+mod frame_stats;
+mod game_rng;
+mod input;
+mod interpolation_alpha;
 mod resource_container;
+mod time;
+mod timers;
+mod world_names;
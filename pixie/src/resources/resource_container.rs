@@ -1,37 +1,222 @@
-use std::any::{Any, TypeId};
+use std::any::{type_name, Any, TypeId};
+use std::cell::{Cell, Ref, RefCell, RefMut};
+use std::collections::hash_map::Entry;
 use std::collections::HashMap;
+use std::fmt;
+
+use hecs::{Component, World};
+
+use super::events::{EventBoundary, Events};
+use crate::change_detection::{maintain_component_ticks, ChangeTick, RemovedComponents};
+
+type Resource = Box<dyn Any + Send + Sync>;
+type EventSwapFn = fn(&mut ResourceContainer, EventBoundary);
+type ChangeDetectFn = fn(&mut World, &mut ResourceContainer);
+
+/// Why a resource could not be fetched
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ResourceError {
+    Missing(&'static str),
+    /// A `fetch_mut` guard for the resource is still alive
+    BorrowedMutably(&'static str),
+    /// A `fetch` or `fetch_mut` guard for the resource is still alive
+    Borrowed(&'static str),
+}
+
+impl fmt::Display for ResourceError {
+    fn fmt(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Missing(name) => write!(formatter, "resource `{name}` not found"),
+            Self::BorrowedMutably(name) => {
+                write!(formatter, "resource `{name}` is already borrowed mutably")
+            }
+            Self::Borrowed(name) => write!(formatter, "resource `{name}` is already borrowed"),
+        }
+    }
+}
+
+impl std::error::Error for ResourceError {}
 
 /// Resource container for hecs
 /// Since hecs doesn't have built-in resource management like specs,
 /// we implement a simple type-map based resource storage
+///
+/// `fetch` and `fetch_mut` only need `&self`, so a system can hold several resources at
+/// once. Their borrows are checked at runtime like a `RefCell`, and a conflicting fetch
+/// panics with the resource type name.
+///
+/// `get` hands out plain references that count as shared borrows until the container is
+/// next used through `&mut self`; a `fetch_mut` of the same resource in between fails.
+///
+/// Resources must be `Send + Sync` so the native dispatcher can share them with parallel
+/// systems.
+///
+/// The container also keeps the change clock. Inserts and mutable access through `get_mut`,
+/// `fetch_mut` or a parallel system's write declaration stamp a resource with the current
+/// `ChangeTick`; `is_changed` reports it for the last simulation frame and
+/// `is_changed_since` for any earlier tick.
 pub struct ResourceContainer {
-    resources: HashMap<TypeId, Box<dyn Any>>,
+    resources: HashMap<TypeId, ResourceSlot>,
+    /// Bumped by every `&mut self` method, which ends all references handed out by `get`
+    epoch: u64,
+    /// Registered event channels, swapped by the engine at each boundary
+    event_channels: Vec<(TypeId, EventSwapFn)>,
+    /// Component types whose ticks the engine maintains at the end of each frame
+    tracked_components: Vec<(TypeId, ChangeDetectFn)>,
+    change_tick: Cell<ChangeTick>,
+    /// First tick of the frame `detect_changes` will close next
+    frame_start: ChangeTick,
+}
+
+struct ResourceSlot {
+    value: RefCell<Resource>,
+    /// Container epoch of the latest `get`; the reference may live until the epoch ends
+    shared_in: Cell<u64>,
+    added_tick: ChangeTick,
+    /// Tick of the latest insert or mutable access
+    changed_tick: Cell<ChangeTick>,
+    /// Flags reported for the last frame
+    changed: bool,
+    added: bool,
+}
+
+impl ResourceSlot {
+    fn new(value: Resource, tick: ChangeTick) -> Self {
+        Self {
+            value: RefCell::new(value),
+            shared_in: Cell::new(0),
+            added_tick: tick,
+            changed_tick: Cell::new(tick),
+            changed: false,
+            added: false,
+        }
+    }
+
+    fn value_mut(&mut self, tick: ChangeTick) -> &mut dyn Any {
+        self.changed_tick.set(tick);
+        &mut **self.value.get_mut()
+    }
 }
 
 impl ResourceContainer {
     pub fn new() -> Self {
         Self {
             resources: HashMap::new(),
+            epoch: 1,
+            event_channels: Vec::new(),
+            tracked_components: Vec::new(),
+            change_tick: Cell::new(ChangeTick::FIRST),
+            frame_start: ChangeTick::FIRST,
         }
     }
 
     /// Insert a resource
-    pub fn insert<T: 'static>(&mut self, resource: T) {
-        self.resources.insert(TypeId::of::<T>(), Box::new(resource));
+    pub fn insert<T: Send + Sync + 'static>(&mut self, resource: T) {
+        self.epoch += 1;
+        let tick = self.change_tick();
+        match self.resources.entry(TypeId::of::<T>()) {
+            Entry::Occupied(mut slot) => {
+                *slot.get_mut().value.get_mut() = Box::new(resource);
+                slot.get_mut().changed_tick.set(tick);
+            }
+            Entry::Vacant(slot) => {
+                slot.insert(ResourceSlot::new(Box::new(resource), tick));
+            }
+        }
     }
 
-    /// Get immutable reference to a resource
+    /// Get a reference to a resource, or `None` if it is missing
+    ///
+    /// Panics if the resource is borrowed by `fetch_mut`.
     pub fn get<T: 'static>(&self) -> Option<&T> {
-        self.resources
-            .get(&TypeId::of::<T>())
-            .and_then(|r| r.downcast_ref::<T>())
+        let slot = match self.slot::<T>() {
+            Ok(slot) => slot,
+            Err(_) => return None,
+        };
+        // SAFETY: `try_fetch_mut` refuses to borrow a slot shared in the current epoch, and
+        // the epoch only ends in `&mut self` methods, after the returned reference is gone.
+        let resource = unsafe { slot.value.try_borrow_unguarded() }
+            .unwrap_or_else(|_| panic!("{}", ResourceError::BorrowedMutably(type_name::<T>())));
+        slot.shared_in.set(self.epoch);
+        Some(
+            resource
+                .downcast_ref::<T>()
+                .expect("resource stored under another type id"),
+        )
     }
 
     /// Get mutable reference to a resource
     pub fn get_mut<T: 'static>(&mut self) -> Option<&mut T> {
+        self.epoch += 1;
+        let tick = self.change_tick();
         self.resources
             .get_mut(&TypeId::of::<T>())
-            .and_then(|r| r.downcast_mut::<T>())
+            .and_then(|r| r.value_mut(tick).downcast_mut::<T>())
+    }
+
+    /// Get a resource, inserting the one built by `create` if it is missing
+    pub fn get_or_insert_with<T: Send + Sync + 'static>(
+        &mut self,
+        create: impl FnOnce() -> T,
+    ) -> &mut T {
+        self.epoch += 1;
+        let tick = self.change_tick();
+        self.resources
+            .entry(TypeId::of::<T>())
+            .or_insert_with(|| ResourceSlot::new(Box::new(create()), tick))
+            .value_mut(tick)
+            .downcast_mut::<T>()
+            .expect("resource stored under another type id")
+    }
+
+    /// Borrow a resource while other resources are borrowed
+    ///
+    /// Panics if the resource is missing or borrowed by `fetch_mut`.
+    pub fn fetch<T: 'static>(&self) -> Ref<'_, T> {
+        self.try_fetch::<T>()
+            .unwrap_or_else(|error| panic!("{error}"))
+    }
+
+    /// Mutably borrow a resource while other resources are borrowed
+    ///
+    /// Panics if the resource is missing or borrowed by `fetch` or `fetch_mut`.
+    pub fn fetch_mut<T: 'static>(&self) -> RefMut<'_, T> {
+        self.try_fetch_mut::<T>()
+            .unwrap_or_else(|error| panic!("{error}"))
+    }
+
+    pub fn try_fetch<T: 'static>(&self) -> Result<Ref<'_, T>, ResourceError> {
+        let resource = self
+            .slot::<T>()?
+            .value
+            .try_borrow()
+            .map_err(|_| ResourceError::BorrowedMutably(type_name::<T>()))?;
+        Ok(Ref::map(resource, |r| {
+            r.downcast_ref::<T>()
+                .expect("resource stored under another type id")
+        }))
+    }
+
+    pub fn try_fetch_mut<T: 'static>(&self) -> Result<RefMut<'_, T>, ResourceError> {
+        let slot = self.slot::<T>()?;
+        if slot.shared_in.get() == self.epoch {
+            return Err(ResourceError::Borrowed(type_name::<T>()));
+        }
+        let resource = slot
+            .value
+            .try_borrow_mut()
+            .map_err(|_| ResourceError::Borrowed(type_name::<T>()))?;
+        slot.changed_tick.set(self.change_tick());
+        Ok(RefMut::map(resource, |r| {
+            r.downcast_mut::<T>()
+                .expect("resource stored under another type id")
+        }))
+    }
+
+    fn slot<T: 'static>(&self) -> Result<&ResourceSlot, ResourceError> {
+        self.resources
+            .get(&TypeId::of::<T>())
+            .ok_or(ResourceError::Missing(type_name::<T>()))
     }
 
     /// Check if a resource exists
@@ -39,13 +224,139 @@ impl ResourceContainer {
         self.resources.contains_key(&TypeId::of::<T>())
     }
 
+    /// Whether the resource was inserted or mutably accessed during the last frame
+    pub fn is_changed<T: 'static>(&self) -> bool {
+        self.slot::<T>().is_ok_and(|slot| slot.changed)
+    }
+
+    /// Whether the resource was inserted during the last frame
+    pub fn is_added<T: 'static>(&self) -> bool {
+        self.slot::<T>().is_ok_and(|slot| slot.added)
+    }
+
+    /// Whether the resource was inserted or mutably accessed at or after `since`
+    pub fn is_changed_since<T: 'static>(&self, since: ChangeTick) -> bool {
+        self.slot::<T>()
+            .is_ok_and(|slot| slot.changed_tick.get() >= since)
+    }
+
+    /// Whether the resource was inserted at or after `since`
+    pub fn is_added_since<T: 'static>(&self, since: ChangeTick) -> bool {
+        self.slot::<T>().is_ok_and(|slot| slot.added_tick >= since)
+    }
+
+    /// The tick writes are stamped with right now
+    pub fn change_tick(&self) -> ChangeTick {
+        self.change_tick.get()
+    }
+
+    /// Move the change clock on and return the new tick
+    ///
+    /// Only the end of a simulation frame moves the clock, so every reader sees the same
+    /// sequence of ticks.
+    pub(crate) fn advance_change_tick(&self) -> ChangeTick {
+        let tick = self.change_tick.get().next();
+        self.change_tick.set(tick);
+        tick
+    }
+
     /// Remove a resource
     pub fn remove<T: 'static>(&mut self) -> Option<T> {
+        self.epoch += 1;
         self.resources
             .remove(&TypeId::of::<T>())
-            .and_then(|r| r.downcast::<T>().ok())
+            .and_then(|r| r.value.into_inner().downcast::<T>().ok())
             .map(|boxed| *boxed)
     }
+
+    /// Insert an `Events<T>` channel and have the engine swap it every fixed step and frame
+    pub fn add_events<T: Send + Sync + 'static>(&mut self) {
+        let type_id = TypeId::of::<T>();
+        if self.event_channels.iter().any(|(id, _)| *id == type_id) {
+            return;
+        }
+        if !self.contains::<Events<T>>() {
+            self.insert(Events::<T>::default());
+        }
+        self.event_channels.push((type_id, swap_events::<T>));
+    }
+
+    /// Send an event, registering its channel on first use
+    pub fn send_event<T: Send + Sync + 'static>(&mut self, event: T) {
+        self.add_events::<T>();
+        self.get_mut::<Events<T>>()
+            .expect("Events channel registered without its resource")
+            .send(event);
+    }
+
+    /// Have the engine track when `T` components are added, changed and removed
+    ///
+    /// At the end of each frame new `T`s get `ComponentTicks<T>` for the `Added<T>` and
+    /// `Changed<T>` filters, and removals are listed in the `RemovedComponents<T>` resource.
+    pub fn track_changes<T: Component>(&mut self) {
+        let type_id = TypeId::of::<T>();
+        if self.tracked_components.iter().any(|(id, _)| *id == type_id) {
+            return;
+        }
+        self.insert(RemovedComponents::<T>::default());
+        self.tracked_components
+            .push((type_id, maintain_component_ticks::<T>));
+    }
+
+    /// Maintain tracked component ticks and roll resource change flags over to a new frame
+    pub(crate) fn detect_changes(&mut self, world: &mut World) {
+        self.epoch += 1;
+        for index in 0..self.tracked_components.len() {
+            let maintain = self.tracked_components[index].1;
+            maintain(world, self);
+        }
+        let frame_start = self.frame_start;
+        for slot in self.resources.values_mut() {
+            slot.changed = slot.changed_tick.get() >= frame_start;
+            slot.added = slot.added_tick >= frame_start;
+        }
+        self.frame_start = self.advance_change_tick();
+    }
+
+    /// Flag a resource written by a parallel system as changed
+    pub(crate) fn mark_changed(&mut self, type_id: TypeId) {
+        self.epoch += 1;
+        let tick = self.change_tick();
+        if let Some(slot) = self.resources.get_mut(&type_id) {
+            slot.changed_tick.set(tick);
+        }
+    }
+
+    /// Raw pointers to the requested resources, taken in one pass over the map
+    pub(crate) fn resource_pointers(
+        &mut self,
+        type_ids: impl IntoIterator<Item = TypeId>,
+    ) -> Vec<(TypeId, *mut (dyn Any + Send + Sync))> {
+        self.epoch += 1;
+        let wanted: Vec<TypeId> = type_ids.into_iter().collect();
+        self.resources
+            .iter_mut()
+            .filter(|(type_id, _)| wanted.contains(type_id))
+            .map(|(type_id, slot)| {
+                let pointer: *mut (dyn Any + Send + Sync) = &mut **slot.value.get_mut();
+                (*type_id, pointer)
+            })
+            .collect()
+    }
+
+    pub(crate) fn swap_event_buffers(&mut self, boundary: EventBoundary) {
+        self.epoch += 1;
+        for index in 0..self.event_channels.len() {
+            let swap = self.event_channels[index].1;
+            swap(self, boundary);
+        }
+    }
+}
+
+fn swap_events<T: 'static>(resources: &mut ResourceContainer, boundary: EventBoundary) {
+    if let Some(events) = resources.get_mut::<Events<T>>() {
+        events.swap_buffers(boundary);
+    }
 }
 
 impl Default for ResourceContainer {
@@ -95,6 +406,128 @@ mod tests {
         assert!(container.contains::<TestResource>());
     }
 
+    #[test]
+    fn test_fetch_several_resources_at_once() {
+        let mut container = ResourceContainer::new();
+        container.insert(TestResource { value: 42 });
+        container.insert(7i32);
+
+        {
+            let source = container.fetch::<i32>();
+            let also_source = container.fetch::<i32>();
+            let mut target = container.fetch_mut::<TestResource>();
+            target.value += *source + *also_source;
+        }
+
+        assert_eq!(container.get::<TestResource>().unwrap().value, 56);
+    }
+
+    #[test]
+    fn test_try_fetch_reports_conflicts() {
+        let mut container = ResourceContainer::new();
+        container.insert(TestResource { value: 42 });
+
+        let held = container.fetch::<TestResource>();
+        assert_eq!(
+            container.try_fetch_mut::<TestResource>().err(),
+            Some(ResourceError::Borrowed(type_name::<TestResource>()))
+        );
+        assert!(container.try_fetch::<TestResource>().is_ok());
+        drop(held);
+
+        let _held = container.fetch_mut::<TestResource>();
+        assert_eq!(
+            container.try_fetch::<TestResource>().err(),
+            Some(ResourceError::BorrowedMutably(type_name::<TestResource>()))
+        );
+        assert_eq!(
+            container.try_fetch::<u32>().err(),
+            Some(ResourceError::Missing("u32"))
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "TestResource` is already borrowed")]
+    fn test_conflicting_fetch_panics_with_type_name() {
+        let mut container = ResourceContainer::new();
+        container.insert(TestResource { value: 42 });
+
+        let _reader = container.fetch::<TestResource>();
+        let _writer = container.fetch_mut::<TestResource>();
+    }
+
+    #[test]
+    fn test_get_blocks_fetch_mut_until_the_next_mutable_use() {
+        let mut container = ResourceContainer::new();
+        container.insert(TestResource { value: 42 });
+
+        let resource: &TestResource = container.get().unwrap();
+        assert_eq!(
+            container.try_fetch_mut::<TestResource>().err(),
+            Some(ResourceError::Borrowed(type_name::<TestResource>()))
+        );
+        assert_eq!(resource.value, 42);
+
+        container.get_mut::<u32>();
+        container.fetch_mut::<TestResource>().value += 1;
+        assert_eq!(container.get::<TestResource>().unwrap().value, 43);
+    }
+
+    #[test]
+    fn test_get_or_insert_with() {
+        let mut container = ResourceContainer::new();
+        container
+            .get_or_insert_with(|| TestResource { value: 1 })
+            .value += 1;
+        container
+            .get_or_insert_with(|| TestResource { value: 10 })
+            .value += 1;
+
+        assert_eq!(container.get::<TestResource>().unwrap().value, 3);
+    }
+
+    #[test]
+    fn test_reports_resource_changes_of_the_last_frame() {
+        let mut world = World::new();
+        let mut container = ResourceContainer::new();
+        container.insert(TestResource { value: 42 });
+        container.insert(7i32);
+        container.detect_changes(&mut world);
+        assert!(container.is_added::<TestResource>());
+        assert!(container.is_changed::<i32>());
+
+        container.fetch::<TestResource>();
+        *container.fetch_mut::<i32>() += 1;
+        container.detect_changes(&mut world);
+        assert!(!container.is_changed::<TestResource>());
+        assert!(container.is_changed::<i32>());
+        assert!(!container.is_added::<i32>());
+
+        let since = container.change_tick();
+        container.detect_changes(&mut world);
+        assert!(!container.is_changed::<i32>());
+        assert!(!container.is_changed::<u64>());
+
+        container.get_mut::<TestResource>();
+        assert!(container.is_changed_since::<TestResource>(since));
+        assert!(!container.is_changed_since::<i32>(since));
+        assert!(!container.is_added_since::<TestResource>(since));
+    }
+
+    #[test]
+    fn test_registered_events_are_swapped() {
+        let mut container = ResourceContainer::new();
+        container.send_event(TestResource { value: 1 });
+        assert_eq!(container.get::<Events<TestResource>>().unwrap().len(), 1);
+
+        container.swap_event_buffers(EventBoundary::FixedStep);
+        container.swap_event_buffers(EventBoundary::Frame);
+        container.swap_event_buffers(EventBoundary::FixedStep);
+        container.swap_event_buffers(EventBoundary::Frame);
+
+        assert!(container.get::<Events<TestResource>>().unwrap().is_empty());
+    }
+
     #[test]
     fn test_remove() {
         let mut container = ResourceContainer::new();
@@ -0,0 +1,162 @@
+/// Frame and fixed-step clock maintained by the engine.
+///
+/// Gameplay reads the scaled `delta`, which slows down with `set_scale` and stops while
+/// paused. UI and other wall-clock effects read `unscaled_delta` instead. The fixed-step
+/// accumulator is fed scaled time, so slow motion runs fewer fixed steps of the same size.
+#[derive(Clone)]
+pub struct Time {
+    scale: f32,
+    paused: bool,
+    delta: f32,
+    unscaled_delta: f32,
+    fixed_delta: f32,
+    elapsed: f64,
+    unscaled_elapsed: f64,
+    fixed_tick: u64,
+    frame_count: u64,
+    in_fixed_step: bool,
+}
+
+impl Time {
+    pub fn new(fixed_delta: f32) -> Self {
+        Self {
+            scale: 1.0,
+            paused: false,
+            delta: 0.0,
+            unscaled_delta: 0.0,
+            fixed_delta,
+            elapsed: 0.0,
+            unscaled_elapsed: 0.0,
+            fixed_tick: 0,
+            frame_count: 0,
+            in_fixed_step: false,
+        }
+    }
+
+    /// Scaled seconds covered by the current fixed step or frame.
+    pub fn delta(&self) -> f32 {
+        if self.in_fixed_step {
+            self.fixed_delta
+        } else {
+            self.delta
+        }
+    }
+
+    /// Wall-clock seconds since the previous frame, ignoring scale and pause.
+    pub fn unscaled_delta(&self) -> f32 {
+        self.unscaled_delta
+    }
+
+    pub fn fixed_delta(&self) -> f32 {
+        self.fixed_delta
+    }
+
+    /// Scaled seconds since startup.
+    pub fn elapsed(&self) -> f64 {
+        self.elapsed
+    }
+
+    pub fn unscaled_elapsed(&self) -> f64 {
+        self.unscaled_elapsed
+    }
+
+    /// Fixed steps started since startup, including steps the application skipped.
+    pub fn fixed_tick(&self) -> u64 {
+        self.fixed_tick
+    }
+
+    /// Frames started since startup, including the current one.
+    pub fn frame_count(&self) -> u64 {
+        self.frame_count
+    }
+
+    pub fn in_fixed_step(&self) -> bool {
+        self.in_fixed_step
+    }
+
+    pub fn scale(&self) -> f32 {
+        self.scale
+    }
+
+    /// Multiplier applied to frame time, e.g. 0.25 for slow motion. Negative values clamp to 0.
+    pub fn set_scale(&mut self, scale: f32) {
+        self.scale = scale.max(0.0);
+    }
+
+    pub fn pause(&mut self) {
+        self.paused = true;
+    }
+
+    pub fn resume(&mut self) {
+        self.paused = false;
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.paused
+    }
+
+    /// Scale the engine applies this frame: `scale`, or 0 while paused.
+    pub fn effective_scale(&self) -> f32 {
+        if self.paused {
+            0.0
+        } else {
+            self.scale
+        }
+    }
+
+    /// Starts a frame of `unscaled_delta` wall-clock seconds, returning the scaled delta.
+    ///
+    /// The engine steps its own `Time`; tests and custom loops step theirs with this and
+    /// `begin_fixed_step`/`end_fixed_step`.
+    pub fn begin_frame(&mut self, unscaled_delta: f32) -> f32 {
+        self.unscaled_delta = unscaled_delta;
+        self.delta = unscaled_delta * self.effective_scale();
+        self.unscaled_elapsed += unscaled_delta as f64;
+        self.elapsed += self.delta as f64;
+        self.frame_count += 1;
+        self.delta
+    }
+
+    /// Enters the next fixed step, where `delta` reports `fixed_delta`
+    pub fn begin_fixed_step(&mut self) {
+        self.fixed_tick += 1;
+        self.in_fixed_step = true;
+    }
+
+    pub fn end_fixed_step(&mut self) {
+        self.in_fixed_step = false;
+    }
+}
+
+impl Default for Time {
+    fn default() -> Self {
+        Time::new(1.0 / 60.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn scales_frame_delta_but_not_fixed_delta() {
+        let mut time = Time::new(0.01);
+        time.set_scale(0.5);
+
+        assert_eq!(time.begin_frame(0.1), 0.05);
+        assert_eq!(time.delta(), 0.05);
+        assert_eq!(time.unscaled_delta(), 0.1);
+
+        time.begin_fixed_step();
+        assert_eq!(time.delta(), 0.01);
+        time.end_fixed_step();
+
+        time.pause();
+        time.begin_frame(0.1);
+        assert_eq!(time.delta(), 0.0);
+        assert!((time.elapsed() - 0.05).abs() < 1.0e-9);
+        assert!((time.unscaled_elapsed() - 0.2).abs() < 1.0e-6);
+        assert_eq!(time.frame_count(), 2);
+        assert_eq!(time.fixed_tick(), 1);
+    }
+}
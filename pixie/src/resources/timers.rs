@@ -0,0 +1,258 @@
+use std::collections::BTreeMap;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TimerMode {
+    Once,
+    Repeating,
+}
+
+/// Countdown ticked by the engine every fixed step.
+///
+/// Attach it to an entity as a component, keep it in a scheduled `Timers` entry, or
+/// own one and call `tick` yourself.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Timer {
+    duration: f32,
+    elapsed: f32,
+    mode: TimerMode,
+    paused: bool,
+    finished: bool,
+    times_finished_this_tick: u32,
+}
+
+impl Timer {
+    pub fn new(duration: f32, mode: TimerMode) -> Self {
+        Self {
+            duration: duration.max(0.0),
+            elapsed: 0.0,
+            mode,
+            paused: false,
+            finished: false,
+            times_finished_this_tick: 0,
+        }
+    }
+
+    pub fn once(duration: f32) -> Self {
+        Self::new(duration, TimerMode::Once)
+    }
+
+    pub fn repeating(interval: f32) -> Self {
+        Self::new(interval, TimerMode::Repeating)
+    }
+
+    /// Advance by `dt` seconds. Paused and finished one-shot timers do not move.
+    pub fn tick(&mut self, dt: f32) -> &Self {
+        self.times_finished_this_tick = 0;
+        if self.paused || (self.mode == TimerMode::Once && self.finished) {
+            return self;
+        }
+
+        self.elapsed += dt;
+        if self.elapsed < self.duration {
+            return self;
+        }
+
+        self.finished = true;
+        match self.mode {
+            TimerMode::Once => {
+                self.elapsed = self.duration;
+                self.times_finished_this_tick = 1;
+            }
+            // A zero interval would never drain, so it fires once per tick
+            TimerMode::Repeating if self.duration == 0.0 => {
+                self.elapsed = 0.0;
+                self.times_finished_this_tick = 1;
+            }
+            TimerMode::Repeating => {
+                let laps = (self.elapsed / self.duration).floor();
+                self.elapsed -= laps * self.duration;
+                self.times_finished_this_tick = laps as u32;
+            }
+        }
+        self
+    }
+
+    pub fn pause(&mut self) {
+        self.paused = true;
+    }
+
+    pub fn resume(&mut self) {
+        self.paused = false;
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.paused
+    }
+
+    /// Restart from zero, keeping the duration, mode and paused state.
+    pub fn reset(&mut self) {
+        self.elapsed = 0.0;
+        self.finished = false;
+        self.times_finished_this_tick = 0;
+    }
+
+    /// Whether the timer has reached its duration at least once.
+    pub fn finished(&self) -> bool {
+        self.finished
+    }
+
+    /// Whether the last `tick` reached the duration.
+    pub fn just_finished(&self) -> bool {
+        self.times_finished_this_tick > 0
+    }
+
+    /// How many intervals the last `tick` completed. Can exceed one for repeating timers.
+    pub fn times_finished_this_tick(&self) -> u32 {
+        self.times_finished_this_tick
+    }
+
+    pub fn elapsed(&self) -> f32 {
+        self.elapsed
+    }
+
+    pub fn remaining(&self) -> f32 {
+        self.duration - self.elapsed
+    }
+
+    /// Progress towards the next finish, from 0.0 to 1.0.
+    pub fn fraction(&self) -> f32 {
+        if self.duration == 0.0 {
+            1.0
+        } else {
+            self.elapsed / self.duration
+        }
+    }
+
+    pub fn duration(&self) -> f32 {
+        self.duration
+    }
+
+    pub fn set_duration(&mut self, duration: f32) {
+        self.duration = duration.max(0.0);
+    }
+
+    pub fn mode(&self) -> TimerMode {
+        self.mode
+    }
+}
+
+/// Sent through `Events<TimerFired>` each time a scheduled `Timers` entry expires.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TimerFired {
+    pub name: String,
+}
+
+/// Named delayed actions that fire `TimerFired` events when they expire.
+///
+/// One-shot entries are removed after firing; repeating ones keep going until cancelled.
+/// Scheduling a name that already exists restarts it.
+#[derive(Clone, Default)]
+pub struct Timers {
+    timers: BTreeMap<String, Timer>,
+}
+
+impl Timers {
+    pub fn schedule(&mut self, name: impl Into<String>, delay: f32) {
+        self.timers.insert(name.into(), Timer::once(delay));
+    }
+
+    pub fn schedule_repeating(&mut self, name: impl Into<String>, interval: f32) {
+        self.timers.insert(name.into(), Timer::repeating(interval));
+    }
+
+    /// Remove an entry, returning whether it was scheduled.
+    pub fn cancel(&mut self, name: &str) -> bool {
+        self.timers.remove(name).is_some()
+    }
+
+    pub fn pause(&mut self, name: &str) -> bool {
+        self.timers.get_mut(name).map(Timer::pause).is_some()
+    }
+
+    pub fn resume(&mut self, name: &str) -> bool {
+        self.timers.get_mut(name).map(Timer::resume).is_some()
+    }
+
+    pub fn is_scheduled(&self, name: &str) -> bool {
+        self.timers.contains_key(name)
+    }
+
+    pub fn get(&self, name: &str) -> Option<&Timer> {
+        self.timers.get(name)
+    }
+
+    pub fn get_mut(&mut self, name: &str) -> Option<&mut Timer> {
+        self.timers.get_mut(name)
+    }
+
+    pub fn len(&self) -> usize {
+        self.timers.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.timers.is_empty()
+    }
+
+    /// Advance every entry, returning one event per expiry in name order.
+    pub(crate) fn tick(&mut self, dt: f32) -> Vec<TimerFired> {
+        let mut fired = Vec::new();
+        self.timers.retain(|name, timer| {
+            timer.tick(dt);
+            for _ in 0..timer.times_finished_this_tick() {
+                fired.push(TimerFired { name: name.clone() });
+            }
+            !(timer.mode() == TimerMode::Once && timer.finished())
+        });
+        fired
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn one_shot_finishes_once() {
+        let mut timer = Timer::once(1.0);
+
+        assert!(!timer.tick(0.6).just_finished());
+        assert!(timer.tick(0.6).just_finished());
+        assert!(timer.finished());
+        assert_eq!(timer.remaining(), 0.0);
+        assert!(!timer.tick(0.6).just_finished());
+    }
+
+    #[test]
+    fn repeating_counts_every_lap_and_respects_pause() {
+        let mut timer = Timer::repeating(0.25);
+
+        timer.tick(0.6);
+        assert_eq!(timer.times_finished_this_tick(), 2);
+        assert!((timer.elapsed() - 0.1).abs() < 1.0e-6);
+
+        timer.pause();
+        assert!(!timer.tick(1.0).just_finished());
+        timer.resume();
+        assert_eq!(timer.tick(0.2).times_finished_this_tick(), 1);
+    }
+
+    #[test]
+    fn scheduled_actions_fire_and_expire() {
+        let mut timers = Timers::default();
+        timers.schedule("door", 0.5);
+        timers.schedule_repeating("spawn", 0.2);
+        timers.schedule("cancelled", 0.1);
+        assert!(timers.cancel("cancelled"));
+
+        let names = |fired: Vec<TimerFired>| {
+            fired
+                .into_iter()
+                .map(|event| event.name)
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(names(timers.tick(0.25)), ["spawn"]);
+        assert_eq!(names(timers.tick(0.25)), ["door", "spawn"]);
+        assert!(!timers.is_scheduled("door"));
+        assert_eq!(timers.len(), 1);
+    }
+}
@@ -0,0 +1,141 @@
+use std::collections::HashMap;
+
+use hecs::{Entity, World};
+
+use crate::components::{Name, Tags};
+
+/// Index of `Name` and `Tags` components, for finding entities without keeping handles
+///
+/// The engine refreshes it after `init`, after every fixed step and after every frame.
+/// Lookups check every indexed entity against the world, so entities despawned, renamed
+/// or untagged since the last refresh are skipped, and handles left over from before a
+/// `World::clear` never alias new entities. `find` also sees entities named since then;
+/// `tagged` picks up new tags at the next refresh.
+#[derive(Default)]
+pub struct WorldNames {
+    names: HashMap<String, Vec<Entity>>,
+    tags: HashMap<String, Vec<Entity>>,
+}
+
+impl WorldNames {
+    /// Entity in `world` named `name`; the one with the lowest ID if several share it
+    pub fn find(&self, world: &World, name: &str) -> Option<Entity> {
+        let indexed = self.names.get(name).and_then(|entities| {
+            entities.iter().copied().find(|entity| {
+                world
+                    .get::<&Name>(*entity)
+                    .is_ok_and(|found| found.as_str() == name)
+            })
+        });
+        // Named after the last refresh
+        indexed.or_else(|| {
+            world
+                .query::<&Name>()
+                .iter()
+                .filter(|(_, found)| found.as_str() == name)
+                .map(|(entity, _)| entity)
+                .min_by_key(|entity| entity.to_bits())
+        })
+    }
+
+    /// Entities in `world` tagged `tag` as of the last refresh, ordered by ID
+    pub fn tagged(&self, world: &World, tag: &str) -> Vec<Entity> {
+        self.tags
+            .get(tag)
+            .into_iter()
+            .flatten()
+            .copied()
+            .filter(|entity| {
+                world
+                    .get::<&Tags>(*entity)
+                    .is_ok_and(|tags| tags.contains(tag))
+            })
+            .collect()
+    }
+
+    /// Rebuild the index from the `Name` and `Tags` components in `world`
+    pub fn refresh(&mut self, world: &World) {
+        for entities in self.names.values_mut().chain(self.tags.values_mut()) {
+            entities.clear();
+        }
+        for (entity, name) in world.query::<&Name>().iter() {
+            index(&mut self.names, name.as_str(), entity);
+        }
+        for (entity, tags) in world.query::<&Tags>().iter() {
+            for tag in &tags.0 {
+                index(&mut self.tags, tag, entity);
+            }
+        }
+        for entities in self.names.values_mut().chain(self.tags.values_mut()) {
+            entities.sort_unstable_by_key(|entity| entity.to_bits());
+            entities.dedup();
+        }
+        self.names.retain(|_, entities| !entities.is_empty());
+        self.tags.retain(|_, entities| !entities.is_empty());
+    }
+}
+
+fn index(map: &mut HashMap<String, Vec<Entity>>, key: &str, entity: Entity) {
+    match map.get_mut(key) {
+        Some(entities) => entities.push(entity),
+        None => {
+            map.insert(key.to_string(), vec![entity]);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn follows_renames_despawns_and_clear() {
+        let mut world = World::new();
+        let mut names = WorldNames::default();
+        let stats = world.spawn((Name::new("hud_stats"), Tags::new(["hud"])));
+        let hint = world.spawn((Name::new("hud_hint"), Tags::new(["hud", "blinking"])));
+        world.spawn((Tags::new(["enemy"]),));
+        names.refresh(&world);
+
+        assert_eq!(names.find(&world, "hud_stats"), Some(stats));
+        assert_eq!(names.tagged(&world, "hud"), [stats, hint]);
+        assert_eq!(names.tagged(&world, "blinking"), [hint]);
+        assert!(names.tagged(&world, "boss").is_empty());
+
+        world.despawn(hint).unwrap();
+        world.get::<&mut Name>(stats).unwrap().0 = "score".to_string();
+        names.refresh(&world);
+        assert_eq!(names.find(&world, "hud_stats"), None);
+        assert_eq!(names.find(&world, "score"), Some(stats));
+        assert_eq!(names.tagged(&world, "hud"), [stats]);
+        assert!(names.tagged(&world, "blinking").is_empty());
+
+        // `clear` resets generations, so old handles can alias new entities
+        world.clear();
+        let replacement = world.spawn((Tags::new(["enemy"]),));
+        names.refresh(&world);
+        assert_eq!(names.find(&world, "score"), None);
+        assert_eq!(names.tagged(&world, "hud"), []);
+        assert_eq!(names.tagged(&world, "enemy"), [replacement]);
+    }
+
+    #[test]
+    fn lookups_between_refreshes_check_the_world() {
+        let mut world = World::new();
+        let mut names = WorldNames::default();
+        let stats = world.spawn((Name::new("hud_stats"), Tags::new(["hud"])));
+        world.spawn((Name::new("hud_hint"),));
+        names.refresh(&world);
+
+        world.despawn(stats).unwrap();
+        assert_eq!(names.find(&world, "hud_stats"), None);
+        assert!(names.tagged(&world, "hud").is_empty());
+
+        // The cleared world hands out the same handles again
+        world.clear();
+        let alias = world.spawn((Name::new("other"), Tags::new(["enemy"])));
+        assert_eq!(names.find(&world, "hud_hint"), None);
+        assert_eq!(names.find(&world, "other"), Some(alias));
+        assert!(names.tagged(&world, "hud").is_empty());
+    }
+}
@@ -0,0 +1,658 @@
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+
+use hecs::{Component, Entity, EntityBuilder, EntityRef, World};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::components::{
+    Animation, BoxCollider, Children, CircleCollider, Collider, Disabled, Force, GlobalTransform,
+    Name, NoInterpolation, Parent, PreviousTransform, RigidBody, Sprite, Tags, Text, TextStyle,
+    Transform, Velocity,
+};
+use crate::pool::EntityPools;
+use crate::prefab::load_component;
+use crate::resources::ResourceContainer;
+
+/// Version written to save files; files with another version are rejected on load
+pub const SAVE_FORMAT_VERSION: u32 = 1;
+
+type SaveComponentFn = fn(&EntityRef<'_>) -> Option<Result<Value, serde_json::Error>>;
+type LoadComponentFn = fn(Value, &mut EntityBuilder) -> Result<(), serde_json::Error>;
+type MapEntitiesFn = fn(&mut World, Entity, &EntityMap);
+type SaveResourceFn = fn(&ResourceContainer) -> Option<Result<Value, serde_json::Error>>;
+type LoadResourceFn = fn(Value) -> Result<PendingResource, serde_json::Error>;
+type MapResourceEntitiesFn = fn(&mut ResourceContainer, &EntityMap);
+type PendingResource = Box<dyn FnOnce(&mut ResourceContainer)>;
+
+#[derive(Debug)]
+pub enum SaveError {
+    Io(std::io::Error),
+    Json(serde_json::Error),
+    UnsupportedVersion {
+        found: u32,
+        expected: u32,
+    },
+    UnknownComponent(String),
+    UnknownResource(String),
+    InvalidComponent {
+        component: String,
+        error: serde_json::Error,
+    },
+    InvalidResource {
+        resource: String,
+        error: serde_json::Error,
+    },
+}
+
+impl fmt::Display for SaveError {
+    fn fmt(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Io(error) => write!(formatter, "failed to access save file: {error}"),
+            Self::Json(error) => write!(formatter, "invalid save file: {error}"),
+            Self::UnsupportedVersion { found, expected } => write!(
+                formatter,
+                "save file version {found} is not supported (expected {expected})"
+            ),
+            Self::UnknownComponent(name) => {
+                write!(formatter, "save file uses unregistered component `{name}`")
+            }
+            Self::UnknownResource(name) => {
+                write!(formatter, "save file uses unregistered resource `{name}`")
+            }
+            Self::InvalidComponent { component, error } => {
+                write!(formatter, "invalid component `{component}`: {error}")
+            }
+            Self::InvalidResource { resource, error } => {
+                write!(formatter, "invalid resource `{resource}`: {error}")
+            }
+        }
+    }
+}
+
+impl std::error::Error for SaveError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Io(error) => Some(error),
+            Self::Json(error) => Some(error),
+            Self::InvalidComponent { error, .. } | Self::InvalidResource { error, .. } => {
+                Some(error)
+            }
+            _ => None,
+        }
+    }
+}
+
+impl From<std::io::Error> for SaveError {
+    fn from(value: std::io::Error) -> Self {
+        Self::Io(value)
+    }
+}
+
+impl From<serde_json::Error> for SaveError {
+    fn from(value: serde_json::Error) -> Self {
+        Self::Json(value)
+    }
+}
+
+/// Components and resources that hold `Entity` references, which must be remapped when a
+/// save is loaded
+pub trait MapEntities {
+    fn map_entities(&mut self, map: &EntityMap);
+}
+
+impl MapEntities for Parent {
+    fn map_entities(&mut self, map: &EntityMap) {
+        self.0 = map.get(self.0).unwrap_or(Entity::DANGLING);
+    }
+}
+
+impl MapEntities for Children {
+    fn map_entities(&mut self, map: &EntityMap) {
+        self.0.retain_mut(|child| match map.get(*child) {
+            Some(loaded) => {
+                *child = loaded;
+                true
+            }
+            None => false,
+        });
+    }
+}
+
+/// Saved entity IDs mapped to the entities spawned for them by `SaveRegistry::load_str`
+#[derive(Clone, Debug, Default)]
+pub struct EntityMap {
+    entities: HashMap<Entity, Entity>,
+}
+
+impl EntityMap {
+    /// Entity spawned for `saved`, or `None` if the save did not contain it
+    pub fn get(&self, saved: Entity) -> Option<Entity> {
+        self.entities.get(&saved).copied()
+    }
+
+    pub fn len(&self) -> usize {
+        self.entities.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entities.is_empty()
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+struct SaveFile {
+    version: u32,
+    entities: Vec<SavedEntity>,
+    #[serde(default)]
+    resources: BTreeMap<String, Value>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct SavedEntity {
+    id: Entity,
+    components: BTreeMap<String, Value>,
+}
+
+#[derive(Deserialize)]
+struct SaveHeader {
+    version: u32,
+}
+
+struct ComponentFns {
+    save: SaveComponentFn,
+    load: LoadComponentFn,
+    map_entities: Option<MapEntitiesFn>,
+}
+
+struct ResourceFns {
+    save: SaveResourceFn,
+    load: LoadResourceFn,
+    map_entities: Option<MapResourceEntitiesFn>,
+}
+
+/// Writes the world and chosen resources to a versioned JSON save, and reads it back.
+///
+/// Only registered components and resources are saved, under their registered names;
+/// entities without any registered component are skipped. The built-in pixie components
+/// and `EntityPools` are registered under their type names. Loading spawns new entities, so
+/// references in components and resources implementing `MapEntities` (such as `Parent` or
+/// the pooled entities) are remapped, and references to entities that were not saved
+/// become `Entity::DANGLING` or are dropped.
+pub struct SaveRegistry {
+    components: BTreeMap<String, ComponentFns>,
+    resources: BTreeMap<String, ResourceFns>,
+}
+
+impl SaveRegistry {
+    pub fn new() -> Self {
+        let mut registry = Self {
+            components: BTreeMap::new(),
+            resources: BTreeMap::new(),
+        };
+        registry.register_component::<Transform>("Transform");
+        registry.register_component::<GlobalTransform>("GlobalTransform");
+        registry.register_component::<PreviousTransform>("PreviousTransform");
+        registry.register_component::<Sprite>("Sprite");
+        registry.register_component::<Animation>("Animation");
+        registry.register_component::<RigidBody>("RigidBody");
+        registry.register_component::<Velocity>("Velocity");
+        registry.register_component::<Force>("Force");
+        registry.register_component::<Collider>("Collider");
+        registry.register_component::<CircleCollider>("CircleCollider");
+        registry.register_component::<BoxCollider>("BoxCollider");
+        registry.register_component::<Text>("Text");
+        registry.register_component::<TextStyle>("TextStyle");
+        registry.register_component::<NoInterpolation>("NoInterpolation");
+        registry.register_component::<Disabled>("Disabled");
+        registry.register_component::<Name>("Name");
+        registry.register_component::<Tags>("Tags");
+        registry.register_component_with_entities::<Parent>("Parent");
+        registry.register_component_with_entities::<Children>("Children");
+        registry.register_resource_with_entities::<EntityPools>("EntityPools");
+        registry
+    }
+
+    /// Save `T` components under `name`, replacing any component registered as `name`.
+    pub fn register_component<T>(&mut self, name: impl Into<String>)
+    where
+        T: Component + Serialize + DeserializeOwned,
+    {
+        self.components.insert(
+            name.into(),
+            ComponentFns {
+                save: save_component::<T>,
+                load: load_component::<T>,
+                map_entities: None,
+            },
+        );
+    }
+
+    /// Like `register_component`, remapping the entity references of `T` on load.
+    pub fn register_component_with_entities<T>(&mut self, name: impl Into<String>)
+    where
+        T: Component + Serialize + DeserializeOwned + MapEntities,
+    {
+        self.components.insert(
+            name.into(),
+            ComponentFns {
+                save: save_component::<T>,
+                load: load_component::<T>,
+                map_entities: Some(map_component_entities::<T>),
+            },
+        );
+    }
+
+    /// Save the `T` resource under `name` when it is present.
+    pub fn register_resource<T>(&mut self, name: impl Into<String>)
+    where
+        T: Serialize + DeserializeOwned + Send + Sync + 'static,
+    {
+        self.resources.insert(
+            name.into(),
+            ResourceFns {
+                save: save_resource::<T>,
+                load: load_resource::<T>,
+                map_entities: None,
+            },
+        );
+    }
+
+    /// Like `register_resource`, remapping the entity references of `T` on load.
+    pub fn register_resource_with_entities<T>(&mut self, name: impl Into<String>)
+    where
+        T: Serialize + DeserializeOwned + MapEntities + Send + Sync + 'static,
+    {
+        self.resources.insert(
+            name.into(),
+            ResourceFns {
+                save: save_resource::<T>,
+                load: load_resource::<T>,
+                map_entities: Some(map_resource_entities::<T>),
+            },
+        );
+    }
+
+    pub fn save_to_string(
+        &self,
+        world: &World,
+        resources: &ResourceContainer,
+    ) -> Result<String, SaveError> {
+        let mut entities = Vec::new();
+        for entity in world.iter() {
+            let mut components = BTreeMap::new();
+            for (name, fns) in &self.components {
+                if let Some(value) = (fns.save)(&entity) {
+                    let value = value.map_err(|error| SaveError::InvalidComponent {
+                        component: name.clone(),
+                        error,
+                    })?;
+                    components.insert(name.clone(), value);
+                }
+            }
+            if !components.is_empty() {
+                entities.push(SavedEntity {
+                    id: entity.entity(),
+                    components,
+                });
+            }
+        }
+        // Archetype order depends on insertion history, so sort for stable output
+        entities.sort_unstable_by_key(|saved| saved.id.to_bits());
+
+        let mut saved_resources = BTreeMap::new();
+        for (name, fns) in &self.resources {
+            if let Some(value) = (fns.save)(resources) {
+                let value = value.map_err(|error| SaveError::InvalidResource {
+                    resource: name.clone(),
+                    error,
+                })?;
+                saved_resources.insert(name.clone(), value);
+            }
+        }
+
+        Ok(serde_json::to_string(&SaveFile {
+            version: SAVE_FORMAT_VERSION,
+            entities,
+            resources: saved_resources,
+        })?)
+    }
+
+    /// Spawn the saved entities into `world` and insert the saved resources.
+    ///
+    /// Existing entities are kept. Nothing is spawned or inserted if the save is invalid.
+    pub fn load_str(
+        &self,
+        source: &str,
+        world: &mut World,
+        resources: &mut ResourceContainer,
+    ) -> Result<EntityMap, SaveError> {
+        let header: SaveHeader = serde_json::from_str(source)?;
+        if header.version != SAVE_FORMAT_VERSION {
+            return Err(SaveError::UnsupportedVersion {
+                found: header.version,
+                expected: SAVE_FORMAT_VERSION,
+            });
+        }
+        let save: SaveFile = serde_json::from_str(source)?;
+
+        let mut builders = Vec::with_capacity(save.entities.len());
+        for saved in save.entities {
+            let mut builder = EntityBuilder::new();
+            for (name, value) in saved.components {
+                let fns = self
+                    .components
+                    .get(&name)
+                    .ok_or_else(|| SaveError::UnknownComponent(name.clone()))?;
+                (fns.load)(value, &mut builder).map_err(|error| SaveError::InvalidComponent {
+                    component: name,
+                    error,
+                })?;
+            }
+            builders.push((saved.id, builder));
+        }
+        let mut pending = Vec::with_capacity(save.resources.len());
+        let mut resource_entities = Vec::new();
+        for (name, value) in save.resources {
+            let fns = self
+                .resources
+                .get(&name)
+                .ok_or_else(|| SaveError::UnknownResource(name.clone()))?;
+            pending.push(
+                (fns.load)(value).map_err(|error| SaveError::InvalidResource {
+                    resource: name,
+                    error,
+                })?,
+            );
+            resource_entities.extend(fns.map_entities);
+        }
+
+        let mut map = EntityMap::default();
+        for (saved, mut builder) in builders {
+            map.entities.insert(saved, world.spawn(builder.build()));
+        }
+        for loaded in map.entities.values() {
+            for map_entities in self.components.values().filter_map(|fns| fns.map_entities) {
+                map_entities(world, *loaded, &map);
+            }
+        }
+        for insert in pending {
+            insert(resources);
+        }
+        // Only the loaded resources hold saved entity IDs
+        for map_entities in resource_entities {
+            map_entities(resources, &map);
+        }
+        Ok(map)
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn save_file(
+        &self,
+        path: impl AsRef<std::path::Path>,
+        world: &World,
+        resources: &ResourceContainer,
+    ) -> Result<(), SaveError> {
+        std::fs::write(path, self.save_to_string(world, resources)?)?;
+        Ok(())
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn load_file(
+        &self,
+        path: impl AsRef<std::path::Path>,
+        world: &mut World,
+        resources: &mut ResourceContainer,
+    ) -> Result<EntityMap, SaveError> {
+        self.load_str(&std::fs::read_to_string(path)?, world, resources)
+    }
+}
+
+impl Default for SaveRegistry {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn save_component<T: Component + Serialize>(
+    entity: &EntityRef<'_>,
+) -> Option<Result<Value, serde_json::Error>> {
+    entity
+        .get::<&T>()
+        .map(|component| serde_json::to_value(&*component))
+}
+
+fn map_component_entities<T: Component + MapEntities>(
+    world: &mut World,
+    entity: Entity,
+    map: &EntityMap,
+) {
+    if let Ok(mut component) = world.get::<&mut T>(entity) {
+        component.map_entities(map);
+    }
+}
+
+fn map_resource_entities<T: MapEntities + Send + Sync + 'static>(
+    resources: &mut ResourceContainer,
+    map: &EntityMap,
+) {
+    if let Some(resource) = resources.get_mut::<T>() {
+        resource.map_entities(map);
+    }
+}
+
+fn save_resource<T: Serialize + 'static>(
+    resources: &ResourceContainer,
+) -> Option<Result<Value, serde_json::Error>> {
+    resources.get::<T>().map(serde_json::to_value)
+}
+
+fn load_resource<T: DeserializeOwned + Send + Sync + 'static>(
+    value: Value,
+) -> Result<PendingResource, serde_json::Error> {
+    let resource: T = serde_json::from_value(value)?;
+    Ok(Box::new(move |resources: &mut ResourceContainer| {
+        resources.insert(resource)
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::components::BodyType;
+    use crate::resources::Time;
+    use crate::systems::{apply_gravity, collision_system, update_physics, Gravity};
+
+    fn ball(
+        position: [f32; 2],
+        radius: f32,
+    ) -> (
+        Transform,
+        Sprite,
+        RigidBody,
+        Velocity,
+        Force,
+        CircleCollider,
+    ) {
+        (
+            Transform::new(
+                [position[0], position[1], 0.5],
+                [radius * 2.0, radius * 2.0],
+            ),
+            Sprite {
+                uv: [0.0, 1.0, 0.0, 1.0],
+                atlas: "ball".into(),
+            },
+            RigidBody {
+                body_type: BodyType::Dynamic,
+                mass: 1.0,
+                restitution: 0.6,
+            },
+            Velocity {
+                linear: [1.5, 0.0],
+                angular: 0.5,
+            },
+            Force::default(),
+            CircleCollider { radius },
+        )
+    }
+
+    fn physics_scene(world: &mut World, resources: &mut ResourceContainer) -> Entity {
+        let ground = world.spawn((
+            Transform::new([0.0, -5.0, 0.5], [20.0, 1.0]),
+            RigidBody {
+                body_type: BodyType::Static,
+                mass: 1.0,
+                restitution: 0.5,
+            },
+            Velocity::default(),
+            Force::default(),
+            BoxCollider {
+                width: 20.0,
+                height: 1.0,
+                fixed_rotation: false,
+            },
+        ));
+        world.spawn((
+            Transform::new([0.0, 0.5, 0.1], [0.5, 0.5]),
+            Text {
+                content: "GROUND".to_string(),
+            },
+            TextStyle::default(),
+            Parent(ground),
+        ));
+        world.spawn(ball([-1.0, -3.9], 0.5));
+        world.spawn(ball([0.2, -3.0], 0.3));
+        resources.insert(Gravity { value: [0.0, -4.0] });
+        ground
+    }
+
+    fn step(world: &mut World, resources: &mut ResourceContainer) {
+        let mut time = Time::new(1.0 / 60.0);
+        time.begin_fixed_step();
+        resources.insert(time);
+        apply_gravity(world, resources);
+        update_physics(world, resources);
+        collision_system(world, resources);
+    }
+
+    /// Ball transforms and velocities, ordered by position
+    fn bodies(world: &World) -> Vec<([f32; 3], [f32; 2])> {
+        let mut query = world.query::<(&Transform, &Velocity, &CircleCollider)>();
+        let mut bodies: Vec<_> = query
+            .iter()
+            .map(|(_, (transform, velocity, _))| (transform.position, velocity.linear))
+            .collect();
+        bodies.sort_by(|a, b| a.0[0].total_cmp(&b.0[0]));
+        bodies
+    }
+
+    #[test]
+    fn physics_scene_round_trips_and_simulates_identically() {
+        let mut registry = SaveRegistry::new();
+        registry.register_resource::<Gravity>("Gravity");
+        let mut world = World::new();
+        let mut resources = ResourceContainer::new();
+        physics_scene(&mut world, &mut resources);
+        for _ in 0..10 {
+            step(&mut world, &mut resources);
+        }
+
+        let saved = registry.save_to_string(&world, &resources).unwrap();
+        assert!(saved.contains(r#""atlas":"ball""#));
+
+        let mut loaded = World::new();
+        // Occupy the first IDs so the loaded entities cannot keep their saved IDs
+        loaded.spawn(());
+        loaded.spawn(());
+        let mut loaded_resources = ResourceContainer::new();
+        let map = registry
+            .load_str(&saved, &mut loaded, &mut loaded_resources)
+            .unwrap();
+        assert_eq!(map.len(), 4);
+        assert_eq!(
+            *loaded_resources.get::<Gravity>().unwrap(),
+            Gravity { value: [0.0, -4.0] }
+        );
+
+        let (label, parent) = {
+            let mut query = loaded.query::<(&Text, &Parent)>();
+            let (label, (text, parent)) = query.iter().next().unwrap();
+            assert_eq!(text.content, "GROUND");
+            (label, parent.0)
+        };
+        let ground = loaded.query::<&BoxCollider>().iter().next().unwrap().0;
+        assert_eq!(parent, ground);
+        assert_ne!(label, ground);
+
+        assert_eq!(bodies(&loaded), bodies(&world));
+        for _ in 0..30 {
+            step(&mut world, &mut resources);
+            step(&mut loaded, &mut loaded_resources);
+        }
+        assert_eq!(bodies(&loaded), bodies(&world));
+    }
+
+    #[test]
+    fn pooled_entities_stay_pooled_after_loading() {
+        let registry = SaveRegistry::new();
+        let mut world = World::new();
+        let mut resources = ResourceContainer::new();
+        let mut pools = EntityPools::default();
+        let ground = physics_scene(&mut world, &mut resources);
+        pools.pool("platforms").release(&mut world, ground).unwrap();
+        resources.insert(pools);
+        let saved = registry.save_to_string(&world, &resources).unwrap();
+
+        let mut loaded = World::new();
+        loaded.spawn(());
+        let mut loaded_resources = ResourceContainer::new();
+        let map = registry
+            .load_str(&saved, &mut loaded, &mut loaded_resources)
+            .unwrap();
+        let ground = map.get(ground).unwrap();
+        assert!(loaded.satisfies::<&Disabled>(ground).unwrap());
+
+        let mut pools = loaded_resources.fetch_mut::<EntityPools>();
+        let platforms = pools.get_mut("platforms").unwrap();
+        assert_eq!(platforms.acquire(&mut loaded), Some(ground));
+        assert_eq!(loaded.query::<&Disabled>().iter().count(), 0);
+    }
+
+    #[test]
+    fn rejects_other_versions_and_unknown_names_without_spawning() {
+        let registry = SaveRegistry::new();
+        let mut world = World::new();
+        let mut resources = ResourceContainer::new();
+
+        assert!(matches!(
+            registry.load_str(
+                r#"{ "version": 99, "entities": [] }"#,
+                &mut world,
+                &mut resources
+            ),
+            Err(SaveError::UnsupportedVersion {
+                found: 99,
+                expected: SAVE_FORMAT_VERSION
+            })
+        ));
+        let unknown = r#"{
+            "version": 1,
+            "entities": [
+                { "id": 4294967296, "components": { "Transform": {} } },
+                { "id": 4294967297, "components": { "Mana": { "current": 3 } } }
+            ]
+        }"#;
+        assert!(matches!(
+            registry.load_str(unknown, &mut world, &mut resources),
+            Err(SaveError::UnknownComponent(name)) if name == "Mana"
+        ));
+        let resource = r#"{ "version": 1, "entities": [], "resources": { "Gravity": {} } }"#;
+        assert!(matches!(
+            registry.load_str(resource, &mut world, &mut resources),
+            Err(SaveError::UnknownResource(name)) if name == "Gravity"
+        ));
+        assert_eq!(world.len(), 0);
+    }
+}
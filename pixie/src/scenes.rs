@@ -0,0 +1,62 @@
+//! Scenes shared by the benches and the unit tests of the systems they time.
+
+use hecs::World;
+
+use crate::components::{
+    BodyType, BoxCollider, CircleCollider, Force, RigidBody, Transform, Velocity,
+};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+
+/// Walled box with `balls` random circles dropped into it from a staggered grid
+pub fn ball_pit(balls: usize, seed: u64) -> World {
+    let mut world = World::new();
+    let walls = [
+        ([0.0, -10.0], [22.0, 1.0]),
+        ([-10.5, 0.0], [1.0, 20.0]),
+        ([10.5, 0.0], [1.0, 20.0]),
+    ];
+    for (position, [width, height]) in walls {
+        world.spawn((
+            Transform::new([position[0], position[1], 0.0], [width, height]),
+            RigidBody {
+                body_type: BodyType::Static,
+                mass: f32::INFINITY,
+                restitution: 0.9,
+            },
+            Velocity::default(),
+            BoxCollider {
+                width,
+                height,
+                fixed_rotation: false,
+            },
+        ));
+    }
+
+    let mut rng = StdRng::seed_from_u64(seed);
+    for ball in 0..balls {
+        let radius = rng.gen_range(0.3..0.7);
+        let position = [
+            (ball % 12) as f32 * 1.5 - 8.25 + rng.gen_range(-0.1..0.1),
+            (ball / 12) as f32 * 1.5 - 8.0,
+        ];
+        world.spawn((
+            Transform::new(
+                [position[0], position[1], 0.0],
+                [radius * 2.0, radius * 2.0],
+            ),
+            RigidBody {
+                body_type: BodyType::Dynamic,
+                mass: radius * radius * 4.0,
+                restitution: 0.6,
+            },
+            Velocity {
+                linear: [rng.gen_range(-5.0..5.0), 0.0],
+                angular: 0.0,
+            },
+            Force::default(),
+            CircleCollider { radius },
+        ));
+    }
+    world
+}
@@ -0,0 +1,378 @@
+use std::path::{Path, PathBuf};
+
+use hecs::World;
+use instant::Instant;
+use winit::event::WindowEvent;
+
+use crate::application::Application;
+use crate::dispatcher::Schedules;
+use crate::recording::{is_recordable, InputRecording, RecordEntry, ReplayCursor};
+use crate::resources::{
+    record_since, ActionMap, AtlasLoaded, Binding, Camera, Commands, DeltaTime, EventBoundary,
+    FrameStats, GameRng, Input, InputEvent, InterpolationAlpha, ResourceContainer, Time,
+    TimerFired, Timers, WindowFocused, WindowResized, WorldNames, EXIT_ACTION,
+};
+use crate::systems::{
+    apply_commands, index_names, propagate_transforms, snapshot_transforms, tick_timers,
+};
+use crate::{
+    AtlasError, EngineConfig, EntityPools, InputMode, PrefabRegistry, SnapshotHistory, Text,
+    TextureAtlasAsset, TextureAtlasRegistry,
+};
+
+/// Where `Input` gets its events from while the simulation runs.
+enum InputSource {
+    Live,
+    Recording {
+        recording: InputRecording,
+        path: Option<PathBuf>,
+    },
+    Replaying(ReplayCursor),
+}
+
+/// World, resources and frame stepping shared by the windowed and headless runners.
+pub(crate) struct Simulation<A: Application> {
+    pub(crate) app: A,
+    pub(crate) world: World,
+    pub(crate) resources: ResourceContainer,
+    schedules: Schedules,
+    accumulator: f32,
+    fixed_dt: f32,
+    max_frame_time: f32,
+    max_fixed_steps_per_frame: u32,
+    input_source: InputSource,
+}
+
+impl<A: Application> Simulation<A> {
+    pub(crate) fn new(
+        mut app: A,
+        config: &EngineConfig,
+        texture_atlases: Vec<TextureAtlasAsset>,
+        mut schedules: Schedules,
+    ) -> Result<Self, AtlasError> {
+        let mut world = World::new();
+        let mut resources = ResourceContainer::new();
+
+        let (seed, input_source) = match &config.input_mode {
+            InputMode::Live => (
+                config.rng_seed.unwrap_or_else(rand::random),
+                InputSource::Live,
+            ),
+            InputMode::Record { path } => {
+                let seed = config.rng_seed.unwrap_or_else(rand::random);
+                let source = InputSource::Recording {
+                    recording: InputRecording::new(seed),
+                    path: path.clone(),
+                };
+                (seed, source)
+            }
+            InputMode::Replay(recording) => (
+                recording.seed(),
+                InputSource::Replaying(ReplayCursor::new(recording.clone())),
+            ),
+        };
+
+        // Insert engine-managed resources first
+        resources.insert(Camera::init_orthographic(
+            config.camera_height,
+            config.aspect_ratio(),
+        ));
+        resources.insert(DeltaTime(0.0));
+        resources.insert(Time::new(config.fixed_dt));
+        resources.insert(InterpolationAlpha::default());
+        resources.insert(Input::default());
+        resources.insert(Commands::default());
+        resources.insert(Timers::default());
+        resources.insert(GameRng::new(seed));
+        let mut frame_stats = FrameStats::default();
+        #[cfg(not(target_arch = "wasm32"))]
+        if config.chrome_trace.is_some() {
+            frame_stats.start_trace();
+        }
+        resources.insert(frame_stats);
+        let mut actions = ActionMap::default();
+        actions.bind(EXIT_ACTION, Binding::Key(winit::keyboard::KeyCode::Escape));
+        resources.insert(actions);
+        let mut atlas_registry = TextureAtlasRegistry::default();
+        for asset in texture_atlases {
+            atlas_registry.register(asset)?;
+        }
+        resources.insert(atlas_registry);
+        resources.insert(PrefabRegistry::new());
+        resources.insert(WorldNames::default());
+        resources.insert(EntityPools::default());
+        if config.snapshot_history > 0 {
+            resources.insert(SnapshotHistory::new(config.snapshot_history));
+        }
+        resources.add_events::<WindowResized>();
+        resources.add_events::<WindowFocused>();
+        resources.add_events::<AtlasLoaded>();
+        resources.add_events::<TimerFired>();
+        // Lets the render extractor reuse text buffers that did not change
+        resources.track_changes::<Text>();
+
+        // Initialize application (can adjust camera via resources)
+        app.init(&mut world, &mut resources);
+        apply_commands(&mut world, &mut resources);
+        propagate_transforms(&mut world, &mut resources);
+        index_names(&mut world, &mut resources);
+        schedules.fixed.init(&mut world, &mut resources);
+        schedules.variable.init(&mut world, &mut resources);
+        apply_commands(&mut world, &mut resources);
+        capture_snapshot(&world, &resources);
+
+        Ok(Self {
+            app,
+            world,
+            resources,
+            schedules,
+            accumulator: 0.0,
+            fixed_dt: config.fixed_dt,
+            max_frame_time: config.max_frame_time,
+            max_fixed_steps_per_frame: config.max_fixed_steps_per_frame,
+            input_source,
+        })
+    }
+
+    pub(crate) fn fixed_dt(&self) -> f32 {
+        self.fixed_dt
+    }
+
+    /// Fixed steps started since startup, including ones the application skipped.
+    ///
+    /// Read from `Time` so a rollback also rewinds the tick inputs are recorded at.
+    fn fixed_tick(&self) -> u64 {
+        fixed_tick(&self.resources)
+    }
+
+    /// Records a window event in `Input` and forwards it to the application,
+    /// returning whether the application consumed it.
+    ///
+    /// While replaying, live keyboard, mouse and focus events are dropped before
+    /// they reach `Input` or the application. Replayed inputs only reach `Input`, so
+    /// gameplay that has to replay polls `Input` or `ActionMap` instead of reacting here.
+    pub(crate) fn handle_input(&mut self, event: &WindowEvent) -> bool {
+        if let InputSource::Replaying(_) = self.input_source {
+            if is_input_event(event) {
+                return false;
+            }
+        } else {
+            if let Some(input_event) = InputEvent::from_window_event(event) {
+                self.send_input(input_event);
+            }
+            if let WindowEvent::Focused(focused) = event {
+                self.resources.send_event(WindowFocused(*focused));
+            }
+        }
+        self.app
+            .handle_input(&mut self.world, &mut self.resources, event)
+    }
+
+    /// Applies an input event to `Input`, capturing it when recording.
+    ///
+    /// Events a recording cannot encode are dropped in every mode, so a replay sees
+    /// exactly the inputs the recorded run did.
+    pub(crate) fn send_input(&mut self, event: InputEvent) {
+        if !is_recordable(&event) {
+            log::debug!("ignoring input {event:?} that recordings cannot encode");
+            return;
+        }
+        match &mut self.input_source {
+            InputSource::Replaying(_) => return,
+            InputSource::Recording { recording, .. } => recording.push(RecordEntry::Input {
+                tick: fixed_tick(&self.resources),
+                event,
+            }),
+            InputSource::Live => {}
+        }
+        if let Some(input) = self.resources.get_mut::<Input>() {
+            input.apply(event);
+        }
+    }
+
+    /// The recording being captured, if the simulation runs in record mode.
+    pub(crate) fn recording(&self) -> Option<&InputRecording> {
+        match &self.input_source {
+            InputSource::Recording { recording, .. } => Some(recording),
+            _ => None,
+        }
+    }
+
+    /// Where the captured recording should be written on exit.
+    pub(crate) fn recording_path(&self) -> Option<&Path> {
+        match &self.input_source {
+            InputSource::Recording { path, .. } => path.as_deref(),
+            _ => None,
+        }
+    }
+
+    pub(crate) fn is_replaying(&self) -> bool {
+        matches!(self.input_source, InputSource::Replaying(_))
+    }
+
+    /// Whether `event` presses a binding of `EXIT_ACTION`.
+    ///
+    /// Reads the raw window event rather than `Input`, so the user can also leave a replay,
+    /// which keeps live input out of `Input`.
+    pub(crate) fn exit_requested(&self, event: &WindowEvent) -> bool {
+        InputEvent::from_window_event(event).is_some_and(|event| self.is_exit_press(event))
+    }
+
+    pub(crate) fn is_exit_press(&self, event: InputEvent) -> bool {
+        let binding = match event {
+            InputEvent::KeyPressed(key) => Binding::Key(key),
+            InputEvent::MousePressed(button) => Binding::Mouse(button),
+            _ => return false,
+        };
+        self.resources
+            .get::<ActionMap>()
+            .is_some_and(|actions| actions.binds(EXIT_ACTION, binding))
+    }
+
+    /// Advances the simulation by `elapsed` seconds of wall-clock time.
+    ///
+    /// Runs as many fixed steps as the accumulator allows, then one variable-step update
+    /// followed by the variable schedule. `GlobalTransform`s are propagated after each of
+    /// them. Change detection runs last: tracked components get their ticks, removals are
+    /// collected and `is_changed` describes this frame until the next one ends.
+    /// Steps beyond `max_fixed_steps_per_frame` are dropped instead of carried over.
+    /// The accumulator and `update` receive the frame time scaled by `Time`.
+    ///
+    /// When replaying, the recorded frame time replaces `elapsed` and the inputs
+    /// recorded before that frame are applied first. Once the recording runs out
+    /// the simulation falls back to live input.
+    pub(crate) fn advance(&mut self, elapsed: f32) {
+        let advance_start = Instant::now();
+        let fixed_tick = self.fixed_tick();
+        let elapsed = match &mut self.input_source {
+            InputSource::Live => elapsed,
+            InputSource::Recording { recording, .. } => {
+                recording.push(RecordEntry::Frame { elapsed });
+                elapsed
+            }
+            InputSource::Replaying(cursor) => match cursor.next_frame() {
+                Some((inputs, recorded_elapsed)) => {
+                    for (tick, event) in inputs {
+                        if tick != fixed_tick {
+                            log::warn!(
+                                "replayed input recorded at tick {tick} applied at tick \
+                                 {fixed_tick}; the loop settings probably differ from the \
+                                 recording"
+                            );
+                        }
+                        if let Some(input) = self.resources.get_mut::<Input>() {
+                            input.apply(event);
+                        }
+                    }
+                    recorded_elapsed
+                }
+                None => {
+                    log::info!("input replay finished after {fixed_tick} fixed steps");
+                    self.input_source = InputSource::Live;
+                    elapsed
+                }
+            },
+        };
+        let unscaled_elapsed = elapsed.min(self.max_frame_time);
+        let elapsed = match self.resources.get_mut::<Time>() {
+            Some(time) => time.begin_frame(unscaled_elapsed),
+            None => unscaled_elapsed,
+        };
+
+        // Accumulate time for fixed-step updates
+        self.accumulator += elapsed;
+        let mut fixed_steps = 0;
+        while self.accumulator >= self.fixed_dt {
+            if fixed_steps == self.max_fixed_steps_per_frame {
+                self.accumulator %= self.fixed_dt;
+                break;
+            }
+            fixed_steps += 1;
+
+            // Keep the pre-step state so rendering can blend between the last two steps
+            snapshot_transforms(&mut self.world, &mut self.resources);
+
+            if let Some(input) = self.resources.get_mut::<Input>() {
+                input.begin_fixed_step();
+            }
+            if let Some(time) = self.resources.get_mut::<Time>() {
+                time.begin_fixed_step();
+            }
+
+            // Check if app wants to run fixed updates
+            if self.app.should_run_fixed(&self.world, &self.resources) {
+                // Provide fixed dt to systems and run the fixed schedule
+                self.resources.insert(DeltaTime(self.fixed_dt));
+                tick_timers(&mut self.world, &mut self.resources);
+                self.app
+                    .fixed_update(&mut self.world, &mut self.resources, self.fixed_dt);
+                apply_commands(&mut self.world, &mut self.resources);
+                self.schedules
+                    .fixed
+                    .run_now(&mut self.world, &mut self.resources);
+                // Sync-point dispatchers may leave commands queued after their last flush
+                apply_commands(&mut self.world, &mut self.resources);
+                propagate_transforms(&mut self.world, &mut self.resources);
+                index_names(&mut self.world, &mut self.resources);
+            }
+            if let Some(input) = self.resources.get_mut::<Input>() {
+                input.end_fixed_step();
+            }
+            if let Some(time) = self.resources.get_mut::<Time>() {
+                time.end_fixed_step();
+            }
+            capture_snapshot(&self.world, &self.resources);
+            self.resources.swap_event_buffers(EventBoundary::FixedStep);
+            self.accumulator -= self.fixed_dt;
+        }
+
+        self.resources
+            .insert(InterpolationAlpha(self.accumulator / self.fixed_dt));
+
+        // Variable-step update for non-physics
+        self.resources.insert(DeltaTime(elapsed));
+        self.app
+            .update(&mut self.world, &mut self.resources, elapsed);
+        apply_commands(&mut self.world, &mut self.resources);
+        self.schedules
+            .variable
+            .run_now(&mut self.world, &mut self.resources);
+        apply_commands(&mut self.world, &mut self.resources);
+        propagate_transforms(&mut self.world, &mut self.resources);
+        index_names(&mut self.world, &mut self.resources);
+
+        if let Some(input) = self.resources.get_mut::<Input>() {
+            input.end_frame();
+        }
+        self.resources.swap_event_buffers(EventBoundary::Frame);
+        self.resources.detect_changes(&mut self.world);
+        record_since(&mut self.resources, "simulation", advance_start);
+    }
+}
+
+fn fixed_tick(resources: &ResourceContainer) -> u64 {
+    resources.get::<Time>().map_or(0, Time::fixed_tick)
+}
+
+/// Record the state after the current fixed tick if a `SnapshotHistory` is kept
+fn capture_snapshot(world: &World, resources: &ResourceContainer) {
+    if !resources.contains::<SnapshotHistory>() {
+        return;
+    }
+    let tick = fixed_tick(resources);
+    resources
+        .fetch_mut::<SnapshotHistory>()
+        .capture(tick, world, resources);
+}
+
+fn is_input_event(event: &WindowEvent) -> bool {
+    matches!(
+        event,
+        WindowEvent::KeyboardInput { .. }
+            | WindowEvent::MouseInput { .. }
+            | WindowEvent::CursorMoved { .. }
+            | WindowEvent::CursorLeft { .. }
+            | WindowEvent::MouseWheel { .. }
+            | WindowEvent::Focused(_)
+    )
+}
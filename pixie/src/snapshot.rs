@@ -0,0 +1,487 @@
+use std::any::{Any, TypeId};
+use std::collections::{HashSet, VecDeque};
+use std::fmt;
+
+use hecs::{Component, Entity, World};
+
+use crate::components::{
+    Animation, BoxCollider, Children, CircleCollider, Collider, Disabled, Force, GlobalTransform,
+    Name, NoInterpolation, Parent, PreviousTransform, RigidBody, Sprite, Tags, Text, TextStyle,
+    Transform, Velocity,
+};
+use crate::pool::EntityPools;
+use crate::resources::{
+    GameRng, ResourceContainer, ResourceError, Time, Timer, Timers, WorldNames,
+};
+use crate::systems::Gravity;
+
+type Captured = Box<dyn Any + Send + Sync>;
+type CaptureComponentsFn = fn(&World) -> Captured;
+type RestoreComponentsFn = fn(&mut World, &Captured);
+type CaptureResourceFn = fn(&ResourceContainer) -> Option<Captured>;
+type RestoreResourceFn = fn(&ResourceContainer, Option<&Captured>) -> Option<PendingRestore>;
+/// Insert or removal left for when the container can be borrowed mutably
+type PendingRestore = Box<dyn FnOnce(&mut ResourceContainer)>;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SnapshotError {
+    /// No `SnapshotHistory` resource; enable it with `EngineConfig::with_snapshot_history`
+    NoHistory,
+    /// The tick is older than the history keeps, or was never simulated
+    NotRecorded(u64),
+}
+
+impl fmt::Display for SnapshotError {
+    fn fmt(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::NoHistory => write!(formatter, "no snapshot history is being recorded"),
+            Self::NotRecorded(tick) => write!(formatter, "no snapshot recorded for tick {tick}"),
+        }
+    }
+}
+
+impl std::error::Error for SnapshotError {}
+
+/// Simulation state captured by `SnapshotRegistry::capture`
+///
+/// Holds clones of the registered components of every entity and of the registered
+/// resources, so capturing costs one clone per component and nothing is serialized.
+pub struct WorldSnapshot {
+    entities: Vec<Entity>,
+    components: Vec<(RestoreComponentsFn, Captured)>,
+    resources: Vec<(RestoreResourceFn, Option<Captured>)>,
+}
+
+impl WorldSnapshot {
+    /// Put `world` and `resources` back into the captured state.
+    ///
+    /// Entities get their captured handles back. Entities spawned since the capture are
+    /// despawned, and registered components and resources are reset to their captured
+    /// values or removed. Unregistered components are kept on entities that survived and
+    /// lost on respawned ones. Entities spawned after a restore may get other handles than
+    /// in the original run.
+    pub fn restore(&self, world: &mut World, resources: &mut ResourceContainer) {
+        self.restore_world(world);
+        for pending in self.restore_resources(resources) {
+            pending(resources);
+        }
+    }
+
+    fn restore_world(&self, world: &mut World) {
+        let captured: HashSet<Entity> = self.entities.iter().copied().collect();
+        let spawned: Vec<Entity> = world
+            .iter()
+            .map(|entity| entity.entity())
+            .filter(|entity| !captured.contains(entity))
+            .collect();
+        for entity in spawned {
+            let _ = world.despawn(entity);
+        }
+        for entity in &self.entities {
+            if !world.contains(*entity) {
+                world.spawn_at(*entity, ());
+            }
+        }
+
+        for (restore, components) in &self.components {
+            restore(world, components);
+        }
+    }
+
+    /// Overwrite the resources that exist in place and return the inserts and removals
+    fn restore_resources(&self, resources: &ResourceContainer) -> Vec<PendingRestore> {
+        self.resources
+            .iter()
+            .filter_map(|(restore, resource)| restore(resources, resource.as_ref()))
+            .collect()
+    }
+
+    pub fn entity_count(&self) -> usize {
+        self.entities.len()
+    }
+}
+
+struct ComponentFns {
+    type_id: TypeId,
+    capture: CaptureComponentsFn,
+    restore: RestoreComponentsFn,
+}
+
+struct ResourceFns {
+    type_id: TypeId,
+    capture: CaptureResourceFn,
+    restore: RestoreResourceFn,
+}
+
+/// Component and resource types that make up the simulation state
+///
+/// `new` registers the built-in components and the `GameRng`, `Time`, `Timers`,
+/// `Gravity` and `EntityPools` resources. Games register the components and resources their systems read.
+pub struct SnapshotRegistry {
+    components: Vec<ComponentFns>,
+    resources: Vec<ResourceFns>,
+}
+
+impl SnapshotRegistry {
+    pub fn new() -> Self {
+        let mut registry = Self {
+            components: Vec::new(),
+            resources: Vec::new(),
+        };
+        registry.register_component::<Transform>();
+        registry.register_component::<GlobalTransform>();
+        registry.register_component::<PreviousTransform>();
+        registry.register_component::<Sprite>();
+        registry.register_component::<Animation>();
+        registry.register_component::<RigidBody>();
+        registry.register_component::<Velocity>();
+        registry.register_component::<Force>();
+        registry.register_component::<Collider>();
+        registry.register_component::<CircleCollider>();
+        registry.register_component::<BoxCollider>();
+        registry.register_component::<Text>();
+        registry.register_component::<TextStyle>();
+        registry.register_component::<NoInterpolation>();
+        registry.register_component::<Disabled>();
+        registry.register_component::<Name>();
+        registry.register_component::<Tags>();
+        registry.register_component::<Parent>();
+        registry.register_component::<Children>();
+        registry.register_component::<Timer>();
+        registry.register_resource::<GameRng>();
+        registry.register_resource::<Time>();
+        registry.register_resource::<Timers>();
+        registry.register_resource::<Gravity>();
+        registry.register_resource::<EntityPools>();
+        registry
+    }
+
+    pub fn register_component<T: Component + Clone>(&mut self) {
+        let type_id = TypeId::of::<T>();
+        if self.components.iter().all(|fns| fns.type_id != type_id) {
+            self.components.push(ComponentFns {
+                type_id,
+                capture: capture_components::<T>,
+                restore: restore_components::<T>,
+            });
+        }
+    }
+
+    pub fn register_resource<T: Clone + Send + Sync + 'static>(&mut self) {
+        let type_id = TypeId::of::<T>();
+        if self.resources.iter().all(|fns| fns.type_id != type_id) {
+            self.resources.push(ResourceFns {
+                type_id,
+                capture: capture_resource::<T>,
+                restore: restore_resource::<T>,
+            });
+        }
+    }
+
+    pub fn capture(&self, world: &World, resources: &ResourceContainer) -> WorldSnapshot {
+        WorldSnapshot {
+            entities: world.iter().map(|entity| entity.entity()).collect(),
+            components: self
+                .components
+                .iter()
+                .map(|fns| (fns.restore, (fns.capture)(world)))
+                .collect(),
+            resources: self
+                .resources
+                .iter()
+                .map(|fns| (fns.restore, (fns.capture)(resources)))
+                .collect(),
+        }
+    }
+}
+
+impl Default for SnapshotRegistry {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Snapshots of the last fixed ticks, for rollback netcode, instant replays and undo
+///
+/// With `EngineConfig::with_snapshot_history(capacity)` the engine inserts this resource
+/// and captures the world after every fixed step, keyed by `Time::fixed_tick`. Register
+/// game types through `registry_mut` in `Application::init`, and go back with `rollback`.
+pub struct SnapshotHistory {
+    registry: SnapshotRegistry,
+    capacity: usize,
+    snapshots: VecDeque<(u64, WorldSnapshot)>,
+}
+
+impl SnapshotHistory {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            registry: SnapshotRegistry::new(),
+            capacity,
+            snapshots: VecDeque::with_capacity(capacity),
+        }
+    }
+
+    pub fn registry_mut(&mut self) -> &mut SnapshotRegistry {
+        &mut self.registry
+    }
+
+    /// Capture the state after `tick`, replacing any later snapshots and dropping the
+    /// oldest one once `capacity` are kept.
+    pub fn capture(&mut self, tick: u64, world: &World, resources: &ResourceContainer) {
+        if self.capacity == 0 {
+            return;
+        }
+        self.discard_after(tick.saturating_sub(1));
+        if self.snapshots.len() == self.capacity {
+            self.snapshots.pop_front();
+        }
+        let snapshot = self.registry.capture(world, resources);
+        self.snapshots.push_back((tick, snapshot));
+    }
+
+    pub fn get(&self, tick: u64) -> Option<&WorldSnapshot> {
+        self.snapshots
+            .iter()
+            .find(|(captured, _)| *captured == tick)
+            .map(|(_, snapshot)| snapshot)
+    }
+
+    /// Ticks with a snapshot, oldest first
+    pub fn ticks(&self) -> impl Iterator<Item = u64> + '_ {
+        self.snapshots.iter().map(|(tick, _)| *tick)
+    }
+
+    pub fn latest_tick(&self) -> Option<u64> {
+        self.snapshots.back().map(|(tick, _)| *tick)
+    }
+
+    pub fn len(&self) -> usize {
+        self.snapshots.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.snapshots.is_empty()
+    }
+
+    pub fn clear(&mut self) {
+        self.snapshots.clear();
+    }
+
+    fn discard_after(&mut self, tick: u64) {
+        while self
+            .snapshots
+            .back()
+            .is_some_and(|(captured, _)| *captured > tick)
+        {
+            self.snapshots.pop_back();
+        }
+    }
+}
+
+/// Restore the snapshot taken after fixed tick `tick` and drop the snapshots after it.
+///
+/// The next fixed steps simulate `tick + 1` onwards again, so feeding them the same input
+/// reproduces the same state. The restored `Time` carries the fixed tick the engine
+/// continues from, and `WorldNames` is rebuilt for the restored entities.
+pub fn rollback(
+    world: &mut World,
+    resources: &mut ResourceContainer,
+    tick: u64,
+) -> Result<(), SnapshotError> {
+    let pending = {
+        let mut history = match resources.try_fetch_mut::<SnapshotHistory>() {
+            Ok(history) => history,
+            Err(ResourceError::Missing(_)) => return Err(SnapshotError::NoHistory),
+            Err(error) => panic!("{error}"),
+        };
+        let snapshot = history.get(tick).ok_or(SnapshotError::NotRecorded(tick))?;
+        snapshot.restore_world(world);
+        let pending = snapshot.restore_resources(resources);
+        history.discard_after(tick);
+        pending
+    };
+    for pending in pending {
+        pending(resources);
+    }
+    if let Some(names) = resources.get_mut::<WorldNames>() {
+        names.refresh(world);
+    }
+    Ok(())
+}
+
+fn capture_components<T: Component + Clone>(world: &World) -> Captured {
+    let mut components: Vec<(Entity, T)> = world
+        .query::<&T>()
+        .iter()
+        .map(|(entity, component)| (entity, component.clone()))
+        .collect();
+    components.sort_unstable_by_key(|(entity, _)| entity.to_bits());
+    Box::new(components)
+}
+
+fn restore_components<T: Component + Clone>(world: &mut World, captured: &Captured) {
+    let captured = captured
+        .downcast_ref::<Vec<(Entity, T)>>()
+        .expect("snapshot holds components of the registered type");
+    let is_captured = |entity: &Entity| {
+        captured
+            .binary_search_by_key(&entity.to_bits(), |(captured, _)| captured.to_bits())
+            .is_ok()
+    };
+    let added: Vec<Entity> = world
+        .query_mut::<&T>()
+        .into_iter()
+        .map(|(entity, _)| entity)
+        .filter(|entity| !is_captured(entity))
+        .collect();
+    for entity in added {
+        let _ = world.remove_one::<T>(entity);
+    }
+
+    for (entity, component) in captured {
+        if let Ok(mut current) = world.get::<&mut T>(*entity) {
+            *current = component.clone();
+            continue;
+        }
+        let _ = world.insert_one(*entity, component.clone());
+    }
+}
+
+fn capture_resource<T: Clone + Send + Sync + 'static>(
+    resources: &ResourceContainer,
+) -> Option<Captured> {
+    resources
+        .get::<T>()
+        .map(|resource| Box::new(resource.clone()) as Captured)
+}
+
+fn restore_resource<T: Clone + Send + Sync + 'static>(
+    resources: &ResourceContainer,
+    captured: Option<&Captured>,
+) -> Option<PendingRestore> {
+    let captured = captured.and_then(|captured| captured.downcast_ref::<T>());
+    match (resources.try_fetch_mut::<T>(), captured) {
+        (Ok(mut current), Some(resource)) => {
+            *current = resource.clone();
+            None
+        }
+        (Ok(_), None) => Some(Box::new(|resources| {
+            resources.remove::<T>();
+        })),
+        (Err(_), Some(resource)) => {
+            let resource = resource.clone();
+            Some(Box::new(move |resources| resources.insert(resource)))
+        }
+        (Err(_), None) => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Clone, Debug, PartialEq)]
+    struct Health(u32);
+
+    #[derive(Clone, Debug, PartialEq)]
+    struct Score(u32);
+
+    fn state(world: &World) -> Vec<(Entity, Option<Transform>, Option<Health>)> {
+        let mut state: Vec<_> = world
+            .iter()
+            .map(|entity| {
+                (
+                    entity.entity(),
+                    entity
+                        .get::<&Transform>()
+                        .map(|transform| Transform::clone(&transform)),
+                    entity.get::<&Health>().map(|health| Health::clone(&health)),
+                )
+            })
+            .collect();
+        state.sort_by_key(|(entity, _, _)| entity.to_bits());
+        state
+    }
+
+    #[test]
+    fn restore_undoes_spawns_despawns_edits_and_resources() {
+        let mut registry = SnapshotRegistry::new();
+        registry.register_component::<Health>();
+        registry.register_resource::<Score>();
+        let mut world = World::new();
+        let mut resources = ResourceContainer::new();
+        let hero = world.spawn((Transform::new([1.0, 2.0, 0.0], [1.0, 1.0]), Health(3)));
+        let doomed = world.spawn((Transform::default(), Health(1)));
+        let wall = world.spawn((Transform::default(),));
+        resources.insert(Score(10));
+        let before = state(&world);
+
+        let snapshot = registry.capture(&world, &resources);
+        world.get::<&mut Transform>(hero).unwrap().position[0] = 9.0;
+        world.remove_one::<Health>(hero).unwrap();
+        world.insert_one(wall, Health(5)).unwrap();
+        world.despawn(doomed).unwrap();
+        world.spawn((Transform::default(), Health(7)));
+        resources.remove::<Score>();
+
+        snapshot.restore(&mut world, &mut resources);
+
+        assert_eq!(state(&world), before);
+        assert!(world.contains(doomed));
+        assert_eq!(*resources.get::<Score>().unwrap(), Score(10));
+        assert_eq!(snapshot.entity_count(), 3);
+    }
+
+    #[test]
+    fn history_keeps_the_last_ticks_and_rolls_back() {
+        let mut world = World::new();
+        let mut resources = ResourceContainer::new();
+        let mut history = SnapshotHistory::new(3);
+        history.registry_mut().register_component::<Health>();
+        let hero = world.spawn((Health(0),));
+        for tick in 1..=5 {
+            world.get::<&mut Health>(hero).unwrap().0 = tick as u32;
+            history.capture(tick, &world, &resources);
+        }
+        assert_eq!(history.ticks().collect::<Vec<_>>(), [3, 4, 5]);
+        resources.insert(history);
+
+        assert_eq!(
+            rollback(&mut world, &mut resources, 2),
+            Err(SnapshotError::NotRecorded(2))
+        );
+        rollback(&mut world, &mut resources, 4).unwrap();
+        assert_eq!(*world.get::<&Health>(hero).unwrap(), Health(4));
+        let history = resources.get::<SnapshotHistory>().unwrap();
+        assert_eq!(history.latest_tick(), Some(4));
+
+        assert_eq!(
+            rollback(&mut World::new(), &mut ResourceContainer::new(), 4),
+            Err(SnapshotError::NoHistory)
+        );
+    }
+
+    #[test]
+    fn rollback_refreshes_world_names() {
+        let mut world = World::new();
+        let mut resources = ResourceContainer::new();
+        let mut history = SnapshotHistory::new(4);
+        let enemy = world.spawn((Tags::new(["enemy"]),));
+        history.capture(1, &world, &resources);
+        resources.insert(history);
+        resources.insert(WorldNames::default());
+
+        world.despawn(enemy).unwrap();
+        resources.fetch_mut::<WorldNames>().refresh(&world);
+        assert!(resources
+            .fetch::<WorldNames>()
+            .tagged(&world, "enemy")
+            .is_empty());
+
+        rollback(&mut world, &mut resources, 1).unwrap();
+        assert_eq!(
+            resources.fetch::<WorldNames>().tagged(&world, "enemy"),
+            [enemy]
+        );
+    }
+}
@@ -0,0 +1,13 @@
+use crate::resources::{Commands, ResourceContainer};
+use hecs::World;
+
+/// Apply queued `Commands` to the world
+///
+/// Dispatchers built with `commands: sync_points` only flush where this system is listed.
+pub fn apply_commands(world: &mut World, resources: &mut ResourceContainer) {
+    if let Some(commands) = resources.get_mut::<Commands>() {
+        if !commands.is_empty() {
+            commands.apply(world);
+        }
+    }
+}
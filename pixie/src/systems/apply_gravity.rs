@@ -1,8 +1,10 @@
 use hecs::World;
-use crate::components::{Force, RigidBody, BodyType};
+use serde::{Deserialize, Serialize};
+use crate::components::{Force, RigidBody, BodyType, Disabled};
 use crate::resources::ResourceContainer;
 
 /// Gravity resource
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
 pub struct Gravity {
     pub value: [f32; 2],
 }
@@ -21,7 +23,10 @@ pub fn apply_gravity(world: &mut World, resources: &mut ResourceContainer) {
         .expect("Gravity resource not found");
 
     // Query for entities with Force and RigidBody components
-    for (_entity, (force, body)) in world.query_mut::<(&mut Force, &RigidBody)>() {
+    for (_entity, (force, body)) in world
+        .query_mut::<(&mut Force, &RigidBody)>()
+        .without::<&Disabled>()
+    {
         if body.body_type == BodyType::Dynamic {
             force.linear[0] += gravity.value[0] * body.mass;
             force.linear[1] += gravity.value[1] * body.mass;
@@ -1,4 +1,6 @@
-use crate::components::{BodyType, BoxCollider, CircleCollider, RigidBody, Transform, Velocity};
+use crate::components::{
+    BodyType, BoxCollider, CircleCollider, Disabled, RigidBody, Transform, Velocity,
+};
 use crate::resources::ResourceContainer;
 use hecs::{Entity, World};
 
@@ -26,77 +28,225 @@ struct EntityCollisionData {
 }
 
 enum ColliderType {
-    Circle { radius: f32 },
-    Box { width: f32, height: f32 },
+    Circle {
+        radius: f32,
+    },
+    /// Half width and height, and the box's local x and y axes in world space
+    Box {
+        half_extents: [f32; 2],
+        axes: [[f32; 2]; 2],
+    },
+}
+
+/// How `collision_system` finds the pairs of colliders worth testing
+///
+/// Read from the resources each step; without the resource the sweep is used.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum BroadPhase {
+    /// Sort collider bounds along their most spread-out axis and pair overlapping ones
+    #[default]
+    SweepAndPrune,
+    /// Pair every collider with every other; the O(n²) reference for the sweep
+    BruteForce,
 }
 
 /// Collision system - detects and resolves collisions between rigid bodies
 ///
-/// Box collision remains axis-aligned and intentionally ignores `Transform::rotation`.
-pub fn collision_system(world: &mut World, _resources: &mut ResourceContainer) {
+/// Colliders are collected and paired by the `BroadPhase` once per step, then every
+/// iteration tests those pairs at the current positions. The sweep pairs colliders again
+/// once an iteration pushes one of them past the padding of its bounds. Boxes are oriented by
+/// `Transform::rotation` and tested with the separating axis theorem, unless their
+/// `BoxCollider::fixed_rotation` keeps them axis-aligned. Entities marked `Disabled`
+/// take no part.
+pub fn collision_system(world: &mut World, resources: &mut ResourceContainer) {
     // Collision iteration for stability
     const ITERATIONS: usize = 8;
 
-    for _ in 0..ITERATIONS {
-        let mut collisions = Vec::new();
-
-        // Collect entity data
-        let mut entity_data = Vec::new();
+    let broad_phase = resources
+        .get::<BroadPhase>()
+        .map_or(BroadPhase::default(), |broad_phase| *broad_phase);
+    let mut entity_data = collect_colliders(world);
+    let mut pairs = match broad_phase {
+        BroadPhase::SweepAndPrune => sweep_and_prune(&entity_data),
+        BroadPhase::BruteForce => all_pairs(&entity_data),
+    };
+    let mut swept_at: Vec<[f32; 2]> = entity_data.iter().map(|data| data.position).collect();
+
+    for iteration in 0..ITERATIONS {
+        // Earlier iterations moved bodies apart
+        if iteration > 0 {
+            for data in &mut entity_data {
+                if let Ok(transform) = world.get::<&Transform>(data.entity) {
+                    data.position = [transform.position[0], transform.position[1]];
+                }
+            }
+            if broad_phase == BroadPhase::SweepAndPrune
+                && entity_data.iter().zip(&swept_at).any(|(data, swept)| {
+                    let moved = (data.position[0] - swept[0])
+                        .abs()
+                        .max((data.position[1] - swept[1]).abs());
+                    moved > sweep_padding(data)
+                })
+            {
+                pairs = sweep_and_prune(&entity_data);
+                swept_at = entity_data.iter().map(|data| data.position).collect();
+            }
+        }
 
-        // Collect circle collider entities
-        for (entity, (transform, body, collider)) in world
-            .query::<(&Transform, &RigidBody, &CircleCollider)>()
+        let collisions = pairs
             .iter()
-        {
-            entity_data.push(EntityCollisionData {
-                entity,
-                position: [transform.position[0], transform.position[1]],
-                mass: body.mass,
-                restitution: body.restitution,
-                body_type: body.body_type,
-                collider: ColliderType::Circle {
-                    radius: collider.radius,
-                },
-            });
+            .filter_map(|&(i, j)| detect_collision(&entity_data[i], &entity_data[j]))
+            .collect();
+        resolve_collisions(world, collisions);
+    }
+}
+
+fn collect_colliders(world: &World) -> Vec<EntityCollisionData> {
+    let mut entity_data = Vec::new();
+
+    // Collect circle collider entities
+    for (entity, (transform, body, collider)) in world
+        .query::<(&Transform, &RigidBody, &CircleCollider)>()
+        .without::<&Disabled>()
+        .iter()
+    {
+        entity_data.push(EntityCollisionData {
+            entity,
+            position: [transform.position[0], transform.position[1]],
+            mass: body.mass,
+            restitution: body.restitution,
+            body_type: body.body_type,
+            collider: ColliderType::Circle {
+                radius: collider.radius,
+            },
+        });
+    }
+
+    // Collect box collider entities
+    for (entity, (transform, body, collider)) in world
+        .query::<(&Transform, &RigidBody, &BoxCollider)>()
+        .without::<&Disabled>()
+        .iter()
+    {
+        entity_data.push(EntityCollisionData {
+            entity,
+            position: [transform.position[0], transform.position[1]],
+            mass: body.mass,
+            restitution: body.restitution,
+            body_type: body.body_type,
+            collider: ColliderType::oriented_box(collider, transform.rotation),
+        });
+    }
+
+    entity_data
+}
+
+/// Every pair of colliders that are not both static, in index order
+fn all_pairs(entity_data: &[EntityCollisionData]) -> Vec<(usize, usize)> {
+    let mut pairs = Vec::new();
+    for i in 0..entity_data.len() {
+        for j in (i + 1)..entity_data.len() {
+            if may_collide(&entity_data[i], &entity_data[j]) {
+                pairs.push((i, j));
+            }
         }
+    }
+    pairs
+}
 
-        // Collect box collider entities
-        for (entity, (transform, body, collider)) in world
-            .query::<(&Transform, &RigidBody, &BoxCollider)>()
+/// How far a collider may move from where it was swept before its pairs are stale
+///
+/// Bounds are padded by `SWEEP_MARGIN` of their larger half extent. Colliders that both
+/// stay within their padding cannot touch unless their padded bounds overlapped, so the
+/// pairs hold until an iteration pushes one of them further.
+fn sweep_padding(data: &EntityCollisionData) -> f32 {
+    const SWEEP_MARGIN: f32 = 0.5;
+
+    let half = data.collider.half_extents();
+    half[0].max(half[1]) * SWEEP_MARGIN
+}
+
+/// Pairs whose bounds, padded by `sweep_padding`, overlap, in the same order as `all_pairs`
+fn sweep_and_prune(entity_data: &[EntityCollisionData]) -> Vec<(usize, usize)> {
+    let bounds: Vec<([f32; 2], [f32; 2])> = entity_data
+        .iter()
+        .map(|data| {
+            let half = data.collider.half_extents();
+            let padding = sweep_padding(data);
+            let half = [half[0] + padding, half[1] + padding];
+            (
+                [data.position[0] - half[0], data.position[1] - half[1]],
+                [data.position[0] + half[0], data.position[1] + half[1]],
+            )
+        })
+        .collect();
+
+    // Sweep the axis the colliders are most spread out on, so fewer bounds overlap on it
+    let variance = |axis: usize| {
+        let count = entity_data.len().max(1) as f32;
+        let mean = entity_data
+            .iter()
+            .map(|data| data.position[axis])
+            .sum::<f32>()
+            / count;
+        entity_data
             .iter()
-        {
-            entity_data.push(EntityCollisionData {
-                entity,
-                position: [transform.position[0], transform.position[1]],
-                mass: body.mass,
-                restitution: body.restitution,
-                body_type: body.body_type,
-                collider: ColliderType::Box {
-                    width: collider.width,
-                    height: collider.height,
-                },
-            });
+            .map(|data| (data.position[axis] - mean).powi(2))
+            .sum::<f32>()
+    };
+    let axis = if variance(1) > variance(0) { 1 } else { 0 };
+    let other = 1 - axis;
+
+    let mut order: Vec<usize> = (0..entity_data.len()).collect();
+    order.sort_unstable_by(|&a, &b| bounds[a].0[axis].total_cmp(&bounds[b].0[axis]));
+
+    let mut pairs = Vec::new();
+    let mut active: Vec<usize> = Vec::new();
+    for i in order {
+        let (min, max) = bounds[i];
+        active.retain(|&j| bounds[j].1[axis] >= min[axis]);
+        for &j in &active {
+            let overlaps = bounds[j].0[other] <= max[other] && bounds[j].1[other] >= min[other];
+            if overlaps && may_collide(&entity_data[i], &entity_data[j]) {
+                pairs.push((i.min(j), i.max(j)));
+            }
         }
+        active.push(i);
+    }
 
-        // Detect collisions
-        for i in 0..entity_data.len() {
-            for j in (i + 1)..entity_data.len() {
-                let data1 = &entity_data[i];
-                let data2 = &entity_data[j];
+    // Resolve in the brute-force order so both broad phases give the same result
+    pairs.sort_unstable();
+    pairs
+}
 
-                // Skip if both are static
-                if data1.body_type == BodyType::Static && data2.body_type == BodyType::Static {
-                    continue;
-                }
+fn may_collide(data1: &EntityCollisionData, data2: &EntityCollisionData) -> bool {
+    // Skip if both are static
+    !(data1.body_type == BodyType::Static && data2.body_type == BodyType::Static)
+}
 
-                if let Some(collision) = detect_collision(data1, data2) {
-                    collisions.push(collision);
-                }
-            }
+impl ColliderType {
+    fn oriented_box(collider: &BoxCollider, rotation: f32) -> Self {
+        let rotation = if collider.fixed_rotation {
+            0.0
+        } else {
+            rotation
+        };
+        let (sin, cos) = rotation.sin_cos();
+        ColliderType::Box {
+            half_extents: [collider.width / 2.0, collider.height / 2.0],
+            axes: [[cos, sin], [-sin, cos]],
         }
+    }
 
-        // Resolve collisions
-        resolve_collisions(world, collisions);
+    /// Half extents of the world-space bounding box
+    fn half_extents(&self) -> [f32; 2] {
+        match *self {
+            ColliderType::Circle { radius } => [radius, radius],
+            ColliderType::Box { half_extents, axes } => [
+                projected_radius(half_extents, axes, [1.0, 0.0]),
+                projected_radius(half_extents, axes, [0.0, 1.0]),
+            ],
+        }
     }
 }
 
@@ -108,22 +258,22 @@ fn detect_collision(
         (ColliderType::Circle { radius: r1 }, ColliderType::Circle { radius: r2 }) => {
             detect_circle_circle(data1, data2, *r1, *r2)
         }
-        (ColliderType::Circle { radius }, ColliderType::Box { width, height }) => {
-            detect_circle_box(data1, data2, *radius, *width, *height)
+        (ColliderType::Circle { radius }, ColliderType::Box { half_extents, axes }) => {
+            detect_circle_box(data1, data2, *radius, *half_extents, *axes)
         }
-        (ColliderType::Box { width, height }, ColliderType::Circle { radius }) => {
-            detect_box_circle(data1, data2, *width, *height, *radius)
+        (ColliderType::Box { half_extents, axes }, ColliderType::Circle { radius }) => {
+            detect_box_circle(data1, data2, *half_extents, *axes, *radius)
         }
         (
             ColliderType::Box {
-                width: w1,
-                height: h1,
+                half_extents: half1,
+                axes: axes1,
             },
             ColliderType::Box {
-                width: w2,
-                height: h2,
+                half_extents: half2,
+                axes: axes2,
             },
-        ) => detect_box_box(data1, data2, *w1, *h1, *w2, *h2),
+        ) => detect_box_box(data1, data2, (*half1, *axes1), (*half2, *axes2)),
     }
 }
 
@@ -168,58 +318,63 @@ fn detect_circle_box(
     circle_data: &EntityCollisionData,
     box_data: &EntityCollisionData,
     radius: f32,
-    box_width: f32,
-    box_height: f32,
+    half_extents: [f32; 2],
+    axes: [[f32; 2]; 2],
 ) -> Option<CollisionInfo> {
-    let half_width = box_width / 2.0;
-    let half_height = box_height / 2.0;
+    let [half_width, half_height] = half_extents;
+    let [x_axis, y_axis] = axes;
+    let to_world = |local: [f32; 2]| {
+        [
+            box_data.position[0] + x_axis[0] * local[0] + y_axis[0] * local[1],
+            box_data.position[1] + x_axis[1] * local[0] + y_axis[1] * local[1],
+        ]
+    };
+
+    // Circle center in the box's local frame
+    let offset = [
+        circle_data.position[0] - box_data.position[0],
+        circle_data.position[1] - box_data.position[1],
+    ];
+    let local = [dot(offset, x_axis), dot(offset, y_axis)];
 
     // Find closest point on box to circle center
-    let closest_x = (circle_data.position[0] - box_data.position[0])
-        .max(-half_width)
-        .min(half_width);
-    let closest_y = (circle_data.position[1] - box_data.position[1])
-        .max(-half_height)
-        .min(half_height);
-
-    // Convert to world space
-    let closest_point_x = box_data.position[0] + closest_x;
-    let closest_point_y = box_data.position[1] + closest_y;
+    let closest_x = local[0].max(-half_width).min(half_width);
+    let closest_y = local[1].max(-half_height).min(half_height);
+    let closest_point = to_world([closest_x, closest_y]);
 
     // Calculate distance from circle center to closest point
-    let dx = circle_data.position[0] - closest_point_x;
-    let dy = circle_data.position[1] - closest_point_y;
+    let dx = circle_data.position[0] - closest_point[0];
+    let dy = circle_data.position[1] - closest_point[1];
     let distance_squared = dx * dx + dy * dy;
 
     if distance_squared < radius * radius {
-        let distance = distance_squared.sqrt();
-
         // Handle case where circle center is inside box
-        let (normal, penetration) = if distance < -0.0001 {
-            // Circle center is inside box - push along closest axis
-            let overlap_x = half_width - (circle_data.position[0] - box_data.position[0]).abs();
-            let overlap_y = half_height - (circle_data.position[1] - box_data.position[1]).abs();
+        let (normal, penetration, contact_point) = if distance_squared < 1e-10 {
+            // Push the circle out through the nearest face
+            let overlap_x = half_width - local[0].abs();
+            let overlap_y = half_height - local[1].abs();
 
             if overlap_x < overlap_y {
-                let dir = if circle_data.position[0] < box_data.position[0] {
-                    -1.0
-                } else {
-                    1.0
-                };
-                ([dir, 0.0], radius + overlap_x)
+                let dir = if local[0] < 0.0 { 1.0 } else { -1.0 };
+                (
+                    [x_axis[0] * dir, x_axis[1] * dir],
+                    radius + overlap_x,
+                    to_world([-dir * half_width, local[1]]),
+                )
             } else {
-                let dir = if circle_data.position[1] < box_data.position[1] {
-                    -1.0
-                } else {
-                    1.0
-                };
-                ([0.0, dir], radius + overlap_y)
+                let dir = if local[1] < 0.0 { 1.0 } else { -1.0 };
+                (
+                    [y_axis[0] * dir, y_axis[1] * dir],
+                    radius + overlap_y,
+                    to_world([local[0], -dir * half_height]),
+                )
             }
         } else {
             // Normal case
+            let distance = distance_squared.sqrt();
             let normal = [-dx / distance, -dy / distance];
             let penetration = radius - distance;
-            (normal, penetration)
+            (normal, penetration, closest_point)
         };
 
         Some(CollisionInfo {
@@ -227,7 +382,7 @@ fn detect_circle_box(
             entity2: box_data.entity,
             normal,
             penetration,
-            contact_point: [closest_point_x, closest_point_y],
+            contact_point,
             mass1: circle_data.mass,
             mass2: box_data.mass,
             restitution: circle_data.restitution.min(box_data.restitution),
@@ -242,12 +397,12 @@ fn detect_circle_box(
 fn detect_box_circle(
     box_data: &EntityCollisionData,
     circle_data: &EntityCollisionData,
-    box_width: f32,
-    box_height: f32,
+    half_extents: [f32; 2],
+    axes: [[f32; 2]; 2],
     radius: f32,
 ) -> Option<CollisionInfo> {
     // Reuse circle-box detection but flip the result
-    let mut collision = detect_circle_box(circle_data, box_data, radius, box_width, box_height)?;
+    let mut collision = detect_circle_box(circle_data, box_data, radius, half_extents, axes)?;
 
     // Swap entities and flip normal
     std::mem::swap(&mut collision.entity1, &mut collision.entity2);
@@ -259,67 +414,124 @@ fn detect_box_circle(
     Some(collision)
 }
 
+/// Half extents and world-space axes of an oriented box
+type BoxShape = ([f32; 2], [[f32; 2]; 2]);
+
+/// Separating axis test between two oriented boxes
+///
+/// The face axes of both boxes are the only candidates in 2D. The one with the least
+/// overlap gives the normal and penetration depth.
 fn detect_box_box(
     data1: &EntityCollisionData,
     data2: &EntityCollisionData,
-    width1: f32,
-    height1: f32,
-    width2: f32,
-    height2: f32,
+    box1: BoxShape,
+    box2: BoxShape,
 ) -> Option<CollisionInfo> {
-    let half_width1 = width1 / 2.0;
-    let half_height1 = height1 / 2.0;
-    let half_width2 = width2 / 2.0;
-    let half_height2 = height2 / 2.0;
-
-    let left1 = data1.position[0] - half_width1;
-    let right1 = data1.position[0] + half_width1;
-    let top1 = data1.position[1] + half_height1;
-    let bottom1 = data1.position[1] - half_height1;
-
-    let left2 = data2.position[0] - half_width2;
-    let right2 = data2.position[0] + half_width2;
-    let top2 = data2.position[1] + half_height2;
-    let bottom2 = data2.position[1] - half_height2;
-
-    if right1 >= left2 && left1 <= right2 && top1 >= bottom2 && bottom1 <= top2 {
-        let overlap_x = (right1 - left2).min(right2 - left1);
-        let overlap_y = (top1 - bottom2).min(top2 - bottom1);
-
-        let (normal, penetration) = if overlap_x < overlap_y {
-            let dir = if data1.position[0] < data2.position[0] {
-                -1.0
-            } else {
-                1.0
-            };
-            ([dir, 0.0], overlap_x)
-        } else {
-            let dir = if data1.position[1] < data2.position[1] {
-                -1.0
+    let offset = [
+        data2.position[0] - data1.position[0],
+        data2.position[1] - data1.position[1],
+    ];
+    let candidates = [
+        (box1.1[0], true),
+        (box1.1[1], true),
+        (box2.1[0], false),
+        (box2.1[1], false),
+    ];
+
+    let mut best: Option<([f32; 2], f32, bool)> = None;
+    for (axis, from_first) in candidates {
+        let distance = dot(offset, axis);
+        let overlap = projected_radius(box1.0, box1.1, axis)
+            + projected_radius(box2.0, box2.1, axis)
+            - distance.abs();
+        if overlap < 0.0 {
+            return None;
+        }
+        if best.is_none_or(|(_, least, _)| overlap < least) {
+            // Point the normal from the first box toward the second
+            let normal = if distance < 0.0 {
+                [-axis[0], -axis[1]]
             } else {
-                1.0
+                axis
             };
-            ([0.0, dir], overlap_y)
-        };
+            best = Some((normal, overlap, from_first));
+        }
+    }
+    let (normal, penetration, from_first) = best?;
 
-        Some(CollisionInfo {
-            entity1: data1.entity,
-            entity2: data2.entity,
-            normal,
-            penetration,
-            contact_point: [
-                (data1.position[0] + data2.position[0]) / 2.0,
-                (data1.position[1] + data2.position[1]) / 2.0,
-            ],
-            mass1: data1.mass,
-            mass2: data2.mass,
-            restitution: data1.restitution.min(data2.restitution),
-            body_type1: data1.body_type,
-            body_type2: data2.body_type,
-        })
+    // The box that does not own the separating face touches it with a corner or an edge
+    let contact_point = if from_first {
+        box_contact((data2.position, box2), (data1.position, box1), normal)
     } else {
-        None
+        let outward = [-normal[0], -normal[1]];
+        box_contact((data1.position, box1), (data2.position, box2), outward)
+    };
+
+    Some(CollisionInfo {
+        entity1: data1.entity,
+        entity2: data2.entity,
+        normal,
+        penetration,
+        contact_point,
+        mass1: data1.mass,
+        mass2: data2.mass,
+        restitution: data1.restitution.min(data2.restitution),
+        body_type1: data1.body_type,
+        body_type2: data2.body_type,
+    })
+}
+
+/// Where `incident` touches the face of `reference` whose outward normal is `normal`
+///
+/// That is the deepest corner of `incident`, or the middle of its deepest edge clipped
+/// to the reference face when the two faces are parallel.
+fn box_contact(
+    incident: ([f32; 2], BoxShape),
+    reference: ([f32; 2], BoxShape),
+    normal: [f32; 2],
+) -> [f32; 2] {
+    let (position, (half_extents, [x_axis, y_axis])) = incident;
+    let corners = [[-1.0, -1.0], [1.0, -1.0], [1.0, 1.0], [-1.0, 1.0]].map(|[sx, sy]| {
+        let x = sx * half_extents[0];
+        let y = sy * half_extents[1];
+        [
+            position[0] + x_axis[0] * x + y_axis[0] * y,
+            position[1] + x_axis[1] * x + y_axis[1] * y,
+        ]
+    });
+    let deepest = corners
+        .iter()
+        .map(|corner| dot(*corner, normal))
+        .fold(f32::INFINITY, f32::min);
+    let tolerance = 1e-4 * (half_extents[0] + half_extents[1]);
+
+    let (reference_position, (reference_half, reference_axes)) = reference;
+    let tangent = [-normal[1], normal[0]];
+    let center = dot(reference_position, tangent);
+    let extent = projected_radius(reference_half, reference_axes, tangent);
+
+    let mut sum = [0.0, 0.0];
+    let mut count = 0.0;
+    for corner in corners {
+        if dot(corner, normal) - deepest > tolerance {
+            continue;
+        }
+        let along = dot(corner, tangent);
+        let clipped = along.max(center - extent).min(center + extent);
+        sum[0] += corner[0] + tangent[0] * (clipped - along);
+        sum[1] += corner[1] + tangent[1] * (clipped - along);
+        count += 1.0;
     }
+    [sum[0] / count, sum[1] / count]
+}
+
+/// Half length of the box's shadow on `axis`
+fn projected_radius(half_extents: [f32; 2], axes: [[f32; 2]; 2], axis: [f32; 2]) -> f32 {
+    half_extents[0] * dot(axes[0], axis).abs() + half_extents[1] * dot(axes[1], axis).abs()
+}
+
+fn dot(a: [f32; 2], b: [f32; 2]) -> f32 {
+    a[0] * b[0] + a[1] * b[1]
 }
 
 fn resolve_collisions(world: &mut World, collisions: Vec<CollisionInfo>) {
@@ -423,3 +635,236 @@ fn resolve_collisions(world: &mut World, collisions: Vec<CollisionInfo>) {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::resources::Time;
+    use crate::scenes::ball_pit;
+    use crate::systems::{apply_gravity, update_physics, Gravity};
+
+    fn resources_with(broad_phase: BroadPhase) -> ResourceContainer {
+        let mut resources = ResourceContainer::new();
+        let mut time = Time::new(1.0 / 60.0);
+        time.begin_fixed_step();
+        resources.insert(time);
+        resources.insert(Gravity::default());
+        resources.insert(broad_phase);
+        resources
+    }
+
+    fn body_bits(world: &World) -> Vec<(u64, [u32; 4])> {
+        let mut bodies: Vec<_> = world
+            .query::<(&Transform, &Velocity)>()
+            .iter()
+            .map(|(entity, (transform, velocity))| {
+                let bits = [
+                    transform.position[0].to_bits(),
+                    transform.position[1].to_bits(),
+                    velocity.linear[0].to_bits(),
+                    velocity.linear[1].to_bits(),
+                ];
+                (entity.to_bits().get(), bits)
+            })
+            .collect();
+        bodies.sort_unstable_by_key(|(entity, _)| *entity);
+        bodies
+    }
+
+    fn step(world: &mut World, resources: &mut ResourceContainer) {
+        apply_gravity(world, resources);
+        update_physics(world, resources);
+        collision_system(world, resources);
+    }
+
+    #[test]
+    fn sweep_pairs_every_colliding_pair() {
+        // Let the balls pile up so many of them touch
+        let mut world = ball_pit(200, 7);
+        let mut resources = resources_with(BroadPhase::BruteForce);
+        for _ in 0..150 {
+            step(&mut world, &mut resources);
+        }
+
+        let entity_data = collect_colliders(&world);
+        let every_pair = all_pairs(&entity_data);
+        let swept = sweep_and_prune(&entity_data);
+
+        let colliding = every_pair
+            .iter()
+            .filter(|&&(i, j)| detect_collision(&entity_data[i], &entity_data[j]).is_some())
+            .collect::<Vec<_>>();
+        assert!(colliding.len() > 100);
+        for pair in colliding {
+            assert!(swept.binary_search(pair).is_ok(), "missed pair {pair:?}");
+        }
+        assert!(swept.len() * 10 < every_pair.len());
+    }
+
+    #[test]
+    fn sweep_resolves_like_brute_force() {
+        let mut swept = ball_pit(200, 3);
+        let mut brute = ball_pit(200, 3);
+        let mut swept_resources = resources_with(BroadPhase::SweepAndPrune);
+        let mut brute_resources = resources_with(BroadPhase::BruteForce);
+
+        for tick in 0..180 {
+            for (world, resources) in [
+                (&mut swept, &mut swept_resources),
+                (&mut brute, &mut brute_resources),
+            ] {
+                step(world, resources);
+            }
+            assert_eq!(
+                body_bits(&swept),
+                body_bits(&brute),
+                "diverged at step {tick}"
+            );
+        }
+    }
+
+    fn collider_data(position: [f32; 2], collider: ColliderType) -> EntityCollisionData {
+        EntityCollisionData {
+            entity: Entity::DANGLING,
+            position,
+            mass: 1.0,
+            restitution: 0.5,
+            body_type: BodyType::Dynamic,
+            collider,
+        }
+    }
+
+    fn box_shape(width: f32, height: f32, rotation: f32, fixed_rotation: bool) -> ColliderType {
+        let collider = BoxCollider {
+            width,
+            height,
+            fixed_rotation,
+        };
+        ColliderType::oriented_box(&collider, rotation)
+    }
+
+    fn assert_close(actual: [f32; 2], expected: [f32; 2]) {
+        let error = (actual[0] - expected[0]).abs() + (actual[1] - expected[1]).abs();
+        assert!(error < 1e-4, "{actual:?} != {expected:?}");
+    }
+
+    #[test]
+    fn rotated_box_rests_on_its_corner() {
+        let ground = collider_data([0.0, 0.0], box_shape(4.0, 1.0, 0.0, false));
+        // A unit box turned 45 degrees, its bottom corner 0.1 into the ground
+        let corner_height = std::f32::consts::FRAC_1_SQRT_2;
+        let diamond_position = [0.0, 0.5 + corner_height - 0.1];
+        let diamond = collider_data(
+            diamond_position,
+            box_shape(1.0, 1.0, std::f32::consts::FRAC_PI_4, false),
+        );
+
+        let collision = detect_collision(&ground, &diamond).unwrap();
+        assert_close(collision.normal, [0.0, 1.0]);
+        assert!((collision.penetration - 0.1).abs() < 1e-4);
+        assert_close(collision.contact_point, [0.0, 0.4]);
+
+        // The flipped pair reports the same contact with the normal reversed
+        let flipped = detect_collision(&diamond, &ground).unwrap();
+        assert_close(flipped.normal, [0.0, -1.0]);
+        assert!((flipped.penetration - 0.1).abs() < 1e-4);
+        assert_close(flipped.contact_point, [0.0, 0.4]);
+
+        // As an axis-aligned box it would hover above the ground
+        let fixed = collider_data(
+            diamond_position,
+            box_shape(1.0, 1.0, std::f32::consts::FRAC_PI_4, true),
+        );
+        assert!(detect_collision(&ground, &fixed).is_none());
+    }
+
+    #[test]
+    fn parallel_boxes_touch_along_their_shared_edge() {
+        let left = collider_data([0.0, 0.0], box_shape(2.0, 2.0, 0.0, false));
+        let right = collider_data([1.8, 0.5], box_shape(2.0, 2.0, 0.0, false));
+
+        let collision = detect_collision(&left, &right).unwrap();
+        assert_close(collision.normal, [1.0, 0.0]);
+        assert!((collision.penetration - 0.2).abs() < 1e-4);
+        assert_close(collision.contact_point, [0.8, 0.25]);
+    }
+
+    #[test]
+    fn circles_hit_rotated_box_faces_and_corners() {
+        let angle = std::f32::consts::FRAC_PI_4;
+        let diamond = collider_data([0.0, 0.0], box_shape(2.0, 2.0, angle, false));
+        let diagonal = [angle.cos(), angle.sin()];
+
+        // Against the face whose normal points up and to the right
+        let face = collider_data(
+            [diagonal[0] * 1.3, diagonal[1] * 1.3],
+            ColliderType::Circle { radius: 0.5 },
+        );
+        let collision = detect_collision(&face, &diamond).unwrap();
+        assert_close(collision.normal, [-diagonal[0], -diagonal[1]]);
+        assert!((collision.penetration - 0.2).abs() < 1e-4);
+        assert_close(collision.contact_point, diagonal);
+
+        // Against the corner on the x axis, which an axis-aligned box would not reach
+        let corner = collider_data([1.8, 0.0], ColliderType::Circle { radius: 0.5 });
+        let collision = detect_collision(&diamond, &corner).unwrap();
+        assert_close(collision.normal, [1.0, 0.0]);
+        assert!((collision.penetration - (0.5 - (1.8 - 2f32.sqrt()))).abs() < 1e-4);
+        assert_close(collision.contact_point, [2f32.sqrt(), 0.0]);
+        let fixed = collider_data([0.0, 0.0], box_shape(2.0, 2.0, angle, true));
+        assert!(detect_collision(&fixed, &corner).is_none());
+    }
+
+    #[test]
+    fn circle_inside_box_leaves_through_nearest_face() {
+        let wall = collider_data([0.0, 0.0], box_shape(2.0, 4.0, 0.0, false));
+        let circle = collider_data([-0.75, 0.5], ColliderType::Circle { radius: 0.25 });
+
+        let collision = detect_collision(&circle, &wall).unwrap();
+        assert_close(collision.normal, [1.0, 0.0]);
+        assert!((collision.penetration - 0.5).abs() < 1e-4);
+        assert_close(collision.contact_point, [-1.0, 0.5]);
+    }
+
+    #[test]
+    fn resolves_box_box_overlap_apart() {
+        let mut world = World::new();
+        let crate_box = world.spawn((
+            Transform::with_rotation([0.0, 0.0, 0.0], [1.0, 1.0], 0.3),
+            RigidBody {
+                body_type: BodyType::Dynamic,
+                mass: 1.0,
+                restitution: 0.0,
+            },
+            Velocity {
+                linear: [2.0, 0.0],
+                angular: 0.0,
+            },
+            BoxCollider::default(),
+        ));
+        world.spawn((
+            Transform::new([1.0, 0.0, 0.0], [1.0, 4.0]),
+            RigidBody {
+                body_type: BodyType::Static,
+                mass: f32::INFINITY,
+                restitution: 0.0,
+            },
+            Velocity::default(),
+            BoxCollider {
+                width: 1.0,
+                height: 4.0,
+                fixed_rotation: false,
+            },
+        ));
+        let mut resources = ResourceContainer::new();
+        let entity_data = collect_colliders(&world);
+        assert!(detect_collision(&entity_data[0], &entity_data[1]).is_some());
+
+        collision_system(&mut world, &mut resources);
+
+        let entity_data = collect_colliders(&world);
+        assert!(detect_collision(&entity_data[0], &entity_data[1]).is_none());
+        assert!(world.get::<&Transform>(crate_box).unwrap().position[0] < 0.0);
+        assert!(world.get::<&Velocity>(crate_box).unwrap().linear[0] <= 0.0);
+    }
+}
@@ -0,0 +1,12 @@
+use crate::resources::{ResourceContainer, WorldNames};
+use hecs::World;
+
+/// Index names system - refreshes the `WorldNames` index from `Name` and `Tags` components
+///
+/// The engine runs it after every fixed step and frame; list it in a schedule to look up
+/// entities tagged earlier in the same step.
+pub fn index_names(world: &mut World, resources: &mut ResourceContainer) {
+    if let Some(names) = resources.get_mut::<WorldNames>() {
+        names.refresh(world);
+    }
+}
@@ -4,10 +4,20 @@ pub mod update_animation;
 pub mod update_physics;
 pub mod apply_gravity;
 pub mod collision_system;
+pub mod snapshot_transforms;
+pub mod propagate_transforms;
+pub mod apply_commands;
+pub mod tick_timers;
+pub mod index_names;
 
 // Re-export system functions
-pub use update_camera::update_camera;
-pub use update_animation::update_animation;
+pub use update_camera::{update_camera, update_camera_system};
+pub use update_animation::{update_animation, update_animation_system};
 pub use update_physics::update_physics;
 pub use apply_gravity::{apply_gravity, Gravity};
-pub use collision_system::collision_system;
+pub use collision_system::{collision_system, BroadPhase};
+pub use snapshot_transforms::snapshot_transforms;
+pub use propagate_transforms::propagate_transforms;
+pub use apply_commands::apply_commands;
+pub use tick_timers::tick_timers;
+pub use index_names::index_names;
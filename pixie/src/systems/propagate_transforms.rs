@@ -0,0 +1,152 @@
+use std::collections::HashMap;
+
+use crate::components::{Children, GlobalTransform, Parent, Transform};
+use crate::resources::ResourceContainer;
+use hecs::{Entity, World};
+
+/// Propagate transforms system - computes `GlobalTransform` down the `Parent` hierarchy
+///
+/// Rebuilds `Children` from the `Parent` components first, and gives every entity with a
+/// `Transform` a `GlobalTransform`. Entities whose parent was despawned or has no
+/// `Transform` are treated as roots. The engine runs it after every fixed step and frame;
+/// list it in a schedule to read up-to-date world transforms in between.
+pub fn propagate_transforms(world: &mut World, _resources: &mut ResourceContainer) {
+    sync_children(world);
+
+    let missing: Vec<Entity> = world
+        .query_mut::<()>()
+        .with::<&Transform>()
+        .without::<&GlobalTransform>()
+        .into_iter()
+        .map(|(entity, ())| entity)
+        .collect();
+    for entity in missing {
+        let _ = world.insert_one(entity, GlobalTransform::default());
+    }
+
+    let world = &*world;
+    let mut stack = Vec::new();
+    for (_entity, (transform, global, parent, children)) in world
+        .query::<(
+            &Transform,
+            &mut GlobalTransform,
+            Option<&Parent>,
+            Option<&Children>,
+        )>()
+        .iter()
+    {
+        if parent.is_some_and(|parent| world.satisfies::<&Transform>(parent.0).unwrap_or(false)) {
+            continue;
+        }
+        *global = GlobalTransform::from(transform);
+        if let Some(children) = children {
+            stack.extend(children.0.iter().map(|child| (*child, *global)));
+        }
+    }
+
+    // Entities in a `Parent` cycle have no root and keep their last value
+    while let Some((entity, parent_global)) = stack.pop() {
+        let Ok(mut query) =
+            world.query_one::<(&Transform, &mut GlobalTransform, Option<&Children>)>(entity)
+        else {
+            continue;
+        };
+        let Some((transform, global, children)) = query.get() else {
+            continue;
+        };
+        *global = parent_global.mul_transform(transform);
+        if let Some(children) = children {
+            stack.extend(children.0.iter().map(|child| (*child, *global)));
+        }
+    }
+}
+
+/// Make every `Children` list match the `Parent` components pointing at it
+fn sync_children(world: &mut World) {
+    let mut expected: HashMap<Entity, Vec<Entity>> = HashMap::new();
+    for (entity, parent) in world.query::<&Parent>().iter() {
+        if world.contains(parent.0) {
+            expected.entry(parent.0).or_default().push(entity);
+        }
+    }
+    for children in expected.values_mut() {
+        children.sort_unstable_by_key(|child| child.to_bits());
+    }
+
+    let mut emptied = Vec::new();
+    for (entity, children) in world.query_mut::<&mut Children>() {
+        match expected.remove(&entity) {
+            Some(list) => {
+                if children.0 != list {
+                    children.0 = list;
+                }
+            }
+            None => emptied.push(entity),
+        }
+    }
+    for entity in emptied {
+        let _ = world.remove_one::<Children>(entity);
+    }
+    for (entity, list) in expected {
+        let _ = world.insert_one(entity, Children(list));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn global(world: &World, entity: Entity) -> GlobalTransform {
+        *world.get::<&GlobalTransform>(entity).unwrap()
+    }
+
+    #[test]
+    fn composes_position_rotation_and_size_down_the_hierarchy() {
+        let mut world = World::new();
+        let mut resources = ResourceContainer::new();
+        let root = world.spawn((Transform::with_rotation(
+            [10.0, 0.0, 0.1],
+            [2.0, 2.0],
+            std::f32::consts::FRAC_PI_2,
+        ),));
+        let child = world.spawn((Transform::new([1.0, 0.0, 0.2], [0.5, 1.0]), Parent(root)));
+        let grandchild = world.spawn((Transform::new([0.0, 1.0, 0.0], [1.0, 1.0]), Parent(child)));
+
+        propagate_transforms(&mut world, &mut resources);
+
+        assert_eq!(
+            *world.get::<&Children>(root).unwrap(),
+            Children(vec![child])
+        );
+        assert_eq!(global(&world, root).position, [10.0, 0.0, 0.1]);
+        let child_global = global(&world, child);
+        assert!((child_global.position[0] - 10.0).abs() < 1.0e-5);
+        assert!((child_global.position[1] - 2.0).abs() < 1.0e-5);
+        assert!((child_global.position[2] - 0.3).abs() < 1.0e-5);
+        assert_eq!(child_global.size, [1.0, 2.0]);
+        let grandchild_global = global(&world, grandchild);
+        assert!((grandchild_global.position[0] - 8.0).abs() < 1.0e-5);
+        assert!((grandchild_global.position[1] - 2.0).abs() < 1.0e-5);
+        assert!((grandchild_global.rotation - std::f32::consts::FRAC_PI_2).abs() < 1.0e-5);
+    }
+
+    #[test]
+    fn orphans_become_roots_and_stale_children_are_dropped() {
+        let mut world = World::new();
+        let mut resources = ResourceContainer::new();
+        let parent = world.spawn((Transform::new([5.0, 0.0, 0.0], [1.0, 1.0]),));
+        let child = world.spawn((Transform::new([1.0, 0.0, 0.0], [1.0, 1.0]), Parent(parent)));
+        propagate_transforms(&mut world, &mut resources);
+        assert_eq!(global(&world, child).position, [6.0, 0.0, 0.0]);
+
+        let other = world.spawn((Transform::default(),));
+        world.insert_one(child, Parent(other)).unwrap();
+        propagate_transforms(&mut world, &mut resources);
+        assert!(world.get::<&Children>(parent).is_err());
+        assert_eq!(global(&world, child).position, [1.0, 0.0, 0.0]);
+
+        world.despawn(other).unwrap();
+        propagate_transforms(&mut world, &mut resources);
+        assert_eq!(global(&world, child).position, [1.0, 0.0, 0.0]);
+    }
+}
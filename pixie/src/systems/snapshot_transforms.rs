@@ -0,0 +1,72 @@
+use crate::components::{PreviousTransform, RigidBody, Transform};
+use crate::resources::ResourceContainer;
+use hecs::World;
+
+/// Snapshot transforms system - records each rigid body's `Transform` before a fixed step
+///
+/// Bodies without a `PreviousTransform` get one, so newly spawned bodies interpolate
+/// from their spawn position instead of the origin.
+pub fn snapshot_transforms(world: &mut World, _resources: &mut ResourceContainer) {
+    let missing: Vec<_> = world
+        .query::<&Transform>()
+        .with::<&RigidBody>()
+        .without::<&PreviousTransform>()
+        .iter()
+        .map(|(entity, transform)| (entity, PreviousTransform::from(transform)))
+        .collect();
+    for (entity, previous) in missing {
+        let _ = world.insert_one(entity, previous);
+    }
+
+    for (_entity, (transform, previous)) in world
+        .query_mut::<(&Transform, &mut PreviousTransform)>()
+        .with::<&RigidBody>()
+    {
+        *previous = PreviousTransform::from(transform);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn records_transforms_of_rigid_bodies_only() {
+        let mut world = World::new();
+        let body = world.spawn((
+            Transform::with_rotation([1.0, 2.0, 0.0], [1.0, 1.0], 0.5),
+            RigidBody::default(),
+        ));
+        let decoration = world.spawn((Transform::default(),));
+        let mut resources = ResourceContainer::new();
+
+        snapshot_transforms(&mut world, &mut resources);
+        world.get::<&mut Transform>(body).unwrap().position = [3.0, 4.0, 0.0];
+
+        let previous = world.get::<&PreviousTransform>(body).unwrap();
+        assert_eq!(previous.position, [1.0, 2.0, 0.0]);
+        assert_eq!(previous.rotation, 0.5);
+        assert!(world.get::<&PreviousTransform>(decoration).is_err());
+    }
+
+    #[test]
+    fn overwrites_existing_snapshot() {
+        let mut world = World::new();
+        let body = world.spawn((
+            Transform::new([1.0, 0.0, 0.0], [1.0, 1.0]),
+            PreviousTransform {
+                position: [0.0, 0.0, 0.0],
+                rotation: 0.0,
+            },
+            RigidBody::default(),
+        ));
+        let mut resources = ResourceContainer::new();
+
+        snapshot_transforms(&mut world, &mut resources);
+
+        assert_eq!(
+            world.get::<&PreviousTransform>(body).unwrap().position,
+            [1.0, 0.0, 0.0]
+        );
+    }
+}
@@ -0,0 +1,56 @@
+use crate::resources::{ResourceContainer, Time, Timer, Timers};
+use hecs::World;
+
+/// Tick system - advances `Timer` components and `Timers` entries by scaled `Time`
+///
+/// Expired `Timers` entries are reported through `Events<TimerFired>`.
+pub fn tick_timers(world: &mut World, resources: &mut ResourceContainer) {
+    let dt = resources
+        .get::<Time>()
+        .expect("Time resource not found")
+        .delta();
+
+    for (_entity, timer) in world.query_mut::<&mut Timer>() {
+        timer.tick(dt);
+    }
+
+    let Some(timers) = resources.get_mut::<Timers>() else {
+        return;
+    };
+    let fired = timers.tick(dt);
+    for fired in fired {
+        resources.send_event(fired);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::resources::{Events, TimerFired};
+
+    #[test]
+    fn ticks_components_and_reports_scheduled_actions() {
+        let mut world = World::new();
+        let mut resources = ResourceContainer::new();
+        let mut time = Time::new(0.5);
+        time.begin_fixed_step();
+        resources.insert(time);
+        let mut timers = Timers::default();
+        timers.schedule("boom", 0.5);
+        resources.insert(timers);
+        let entity = world.spawn((Timer::once(1.0),));
+
+        tick_timers(&mut world, &mut resources);
+        tick_timers(&mut world, &mut resources);
+
+        assert!(world.get::<&Timer>(entity).unwrap().just_finished());
+        let fired = resources.get::<Events<TimerFired>>().unwrap();
+        assert_eq!(
+            fired
+                .iter()
+                .map(|event| event.name.as_str())
+                .collect::<Vec<_>>(),
+            ["boom"]
+        );
+    }
+}
@@ -1,21 +1,51 @@
-use crate::components::{Animation, Sprite};
-use crate::resources::{DeltaTime, ResourceContainer};
+use crate::components::{Animation, Disabled, Sprite};
+use crate::dispatcher::{ParallelSystem, SystemAccess, SystemResources};
+use crate::resources::{ResourceContainer, Time};
 use hecs::World;
 
 /// Update animation system - advances sprite animation frames based on time
 pub fn update_animation(world: &mut World, resources: &mut ResourceContainer) {
     let dt = resources
-        .get::<DeltaTime>()
-        .expect("DeltaTime resource not found");
+        .get::<Time>()
+        .expect("Time resource not found")
+        .delta();
+    animate(world, dt);
+}
+
+/// `update_animation` with its access declared, so the native dispatcher can run it next
+/// to systems that leave sprites and animations alone
+pub fn update_animation_system() -> ParallelSystem {
+    fn run(world: &World, resources: &mut SystemResources) {
+        let dt = resources
+            .get::<Time>()
+            .expect("Time resource not found")
+            .delta();
+        animate(world, dt);
+    }
 
+    ParallelSystem::new(
+        std::any::type_name_of_val(&update_animation),
+        run,
+        SystemAccess::new()
+            .write::<Sprite>()
+            .write::<Animation>()
+            .read_resource::<Time>(),
+    )
+}
+
+fn animate(world: &World, dt: f32) {
     // Query for entities with both Sprite and Animation components
-    for (_entity, (sprite, animation)) in world.query_mut::<(&mut Sprite, &mut Animation)>() {
+    for (_entity, (sprite, animation)) in world
+        .query::<(&mut Sprite, &mut Animation)>()
+        .without::<&Disabled>()
+        .iter()
+    {
         // Skip if animation is finished and not looping
         if animation.finished && !animation.loop_animation {
             continue;
         }
 
-        animation.elapsed_time += dt.0;
+        animation.elapsed_time += dt;
 
         // Check if it's time to advance frame
         if animation.elapsed_time >= animation.frame_duration {
@@ -49,3 +79,38 @@ pub fn update_animation(world: &mut World, resources: &mut ResourceContainer) {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn declared_system_advances_frames_like_the_exclusive_one() {
+        let mut world = World::new();
+        let animation = Animation {
+            frame_count: 4,
+            frame_duration: 0.1,
+            atlas_columns: 2,
+            atlas_rows: 2,
+            ..Animation::default()
+        };
+        let sprite = Sprite {
+            atlas: "main".into(),
+            uv: [0.0, 0.5, 0.0, 0.5],
+        };
+        let entity = world.spawn((sprite, animation));
+        let mut resources = ResourceContainer::new();
+        let mut time = Time::new(0.1);
+        time.begin_fixed_step();
+        resources.insert(time);
+
+        update_animation(&mut world, &mut resources);
+        update_animation_system().run_alone(&world, &mut resources);
+
+        assert_eq!(world.get::<&Animation>(entity).unwrap().current_frame, 2);
+        assert_eq!(
+            world.get::<&Sprite>(entity).unwrap().uv,
+            [0.0, 0.5, 0.5, 1.0]
+        );
+    }
+}
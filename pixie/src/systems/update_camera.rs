@@ -1,4 +1,5 @@
 use hecs::World;
+use crate::dispatcher::{ParallelSystem, SystemAccess, SystemResources};
 use crate::resources::ResourceContainer;
 
 /// Update camera system - currently a no-op placeholder
@@ -14,4 +15,17 @@ pub fn update_camera(_world: &mut World, _resources: &mut ResourceContainer) {
     // {
     //     camera.move_camera([transform.position[0], transform.position[1]]);
     // }
-}
\ No newline at end of file
+}
+
+/// `update_camera` with its access declared, so the native dispatcher can run it next to
+/// other systems. A camera-follow version would declare `read::<Transform>()` and
+/// `write_resource::<Camera>()`.
+pub fn update_camera_system() -> ParallelSystem {
+    fn run(_world: &World, _resources: &mut SystemResources) {}
+
+    ParallelSystem::new(
+        std::any::type_name_of_val(&update_camera),
+        run,
+        SystemAccess::new(),
+    )
+}
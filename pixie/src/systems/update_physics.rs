@@ -1,16 +1,18 @@
-use crate::components::{BodyType, Force, RigidBody, Transform, Velocity};
-use crate::resources::{DeltaTime, ResourceContainer};
+use crate::components::{BodyType, Disabled, Force, RigidBody, Transform, Velocity};
+use crate::resources::{ResourceContainer, Time};
 use hecs::World;
 
 /// Update physics system - applies forces and updates positions
 pub fn update_physics(world: &mut World, resources: &mut ResourceContainer) {
     let dt = resources
-        .get::<DeltaTime>()
-        .expect("DeltaTime resource not found");
+        .get::<Time>()
+        .expect("Time resource not found")
+        .delta();
 
     // Query for entities with physics components
-    for (_entity, (transform, velocity, force, body)) in
-        world.query_mut::<(&mut Transform, &mut Velocity, &mut Force, &RigidBody)>()
+    for (_entity, (transform, velocity, force, body)) in world
+        .query_mut::<(&mut Transform, &mut Velocity, &mut Force, &RigidBody)>()
+        .without::<&Disabled>()
     {
         if body.body_type != BodyType::Dynamic {
             continue;
@@ -20,14 +22,14 @@ pub fn update_physics(world: &mut World, resources: &mut ResourceContainer) {
         let acceleration = [force.linear[0] / body.mass, force.linear[1] / body.mass];
 
         // Update velocity
-        velocity.linear[0] += acceleration[0] * dt.0;
-        velocity.linear[1] += acceleration[1] * dt.0;
-        velocity.angular += force.torque / body.mass * dt.0;
+        velocity.linear[0] += acceleration[0] * dt;
+        velocity.linear[1] += acceleration[1] * dt;
+        velocity.angular += force.torque / body.mass * dt;
 
         // Update transform using semi-implicit Euler integration
-        transform.position[0] += velocity.linear[0] * dt.0;
-        transform.position[1] += velocity.linear[1] * dt.0;
-        transform.rotation += velocity.angular * dt.0;
+        transform.position[0] += velocity.linear[0] * dt;
+        transform.position[1] += velocity.linear[1] * dt;
+        transform.rotation += velocity.angular * dt;
 
         // Clear forces
         force.linear = [0.0, 0.0];
@@ -39,6 +41,12 @@ pub fn update_physics(world: &mut World, resources: &mut ResourceContainer) {
 mod tests {
     use super::*;
 
+    fn fixed_step_time(fixed_delta: f32) -> Time {
+        let mut time = Time::new(fixed_delta);
+        time.begin_fixed_step();
+        time
+    }
+
     fn dynamic_body() -> RigidBody {
         RigidBody {
             body_type: BodyType::Dynamic,
@@ -63,7 +71,7 @@ mod tests {
             dynamic_body(),
         ));
         let mut resources = ResourceContainer::new();
-        resources.insert(DeltaTime(0.5));
+        resources.insert(fixed_step_time(0.5));
 
         update_physics(&mut world, &mut resources);
 
@@ -111,7 +119,7 @@ mod tests {
             },
         ));
         let mut resources = ResourceContainer::new();
-        resources.insert(DeltaTime(0.5));
+        resources.insert(fixed_step_time(0.5));
 
         update_physics(&mut world, &mut resources);
 
@@ -124,4 +132,25 @@ mod tests {
             -0.5
         );
     }
+
+    #[test]
+    fn skips_disabled_bodies() {
+        let mut world = World::new();
+        let entity = world.spawn((
+            Transform::new([0.0, 0.0, 0.0], [1.0, 1.0]),
+            Velocity {
+                linear: [1.0, 0.0],
+                angular: 0.0,
+            },
+            Force::default(),
+            dynamic_body(),
+            Disabled,
+        ));
+        let mut resources = ResourceContainer::new();
+        resources.insert(fixed_step_time(0.5));
+
+        update_physics(&mut world, &mut resources);
+
+        assert_eq!(world.get::<&Transform>(entity).unwrap().position[0], 0.0);
+    }
 }